@@ -399,6 +399,22 @@ pub extern "C" fn atom_to_str(atom: *const atom_ref_t, buf: *mut c_char, buf_len
     write_into_buf(atom, buf, buf_len)
 }
 
+/// @brief Renders a text description of an atom splitting it into the lines
+/// of the default width
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to render
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the description string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+/// called again with a larger buffer.
+///
+#[no_mangle]
+pub extern "C" fn atom_to_pretty_str(atom: *const atom_ref_t, buf: *mut c_char, buf_len: usize) -> usize {
+    let atom = unsafe{ (&*atom).borrow() };
+    write_into_buf(hyperon_atom::pretty::PrettyPrinter::new().print(atom), buf, buf_len)
+}
+
 /// @brief Renders the name of an atom into a text buffer
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to get the name of
@@ -995,6 +1011,22 @@ pub unsafe extern "C" fn atom_vec_len(vec: *const atom_vec_t) -> usize {
     (*vec).len
 }
 
+/// @brief Renders a text description of the list of atoms splitting it into
+/// the lines of the default width
+/// @ingroup atom_vec_group
+/// @param[in]  vec  The vec to render
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the description string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+/// called again with a larger buffer.
+///
+#[no_mangle]
+pub unsafe extern "C" fn atom_vec_to_pretty_str(vec: *const atom_vec_t, buf: *mut c_char, buf_len: usize) -> usize {
+    let atoms = (*vec).as_slice();
+    write_into_buf(hyperon_atom::pretty::PrettyPrinter::new().print_list(atoms), buf, buf_len)
+}
+
 /// @brief Removes the last element from a vec, and returns it
 /// @ingroup atom_vec_group
 /// @param[in]  vec  The vec from which to pop the atom
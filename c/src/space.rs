@@ -351,9 +351,19 @@ struct CObserver {
 
 impl SpaceObserver for CObserver {
     fn notify(&mut self, event: &SpaceEvent) {
-        let api = unsafe{ &*self.api };
-        let event = space_event_t::ref_wrapper(event);
-        (api.notify)(self.payload, &event);
+        // C API doesn't support batch events, thus they are passed one by one
+        match event {
+            SpaceEvent::Batch(events) => events.iter().for_each(|event| self.notify(event)),
+            SpaceEvent::RemoveCount(atom, count) => {
+                let remove = SpaceEvent::Remove(atom.clone());
+                (0..*count).for_each(|_| self.notify(&remove));
+            },
+            _ => {
+                let api = unsafe{ &*self.api };
+                let event = space_event_t::ref_wrapper(event);
+                (api.notify)(self.payload, &event);
+            },
+        }
     }
 }
 
@@ -404,6 +414,8 @@ pub extern "C" fn space_event_get_type(event: *const space_event_t) -> space_eve
         SpaceEvent::Add(_) => space_event_type_t::SPACE_EVENT_TYPE_ADD,
         SpaceEvent::Remove(_) => space_event_type_t::SPACE_EVENT_TYPE_REMOVE,
         SpaceEvent::Replace(_, _) => space_event_type_t::SPACE_EVENT_TYPE_REPLACE,
+        SpaceEvent::Batch(_) | SpaceEvent::RemoveCount(_, _) =>
+            unreachable!("Batch events are not passed to C observers"),
     }
 }
 
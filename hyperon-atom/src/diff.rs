@@ -0,0 +1,250 @@
+//! Structural difference between atoms. Difference is calculated as a tree
+//! edit distance: children of two expressions are aligned and the cheapest
+//! sequence of edits which converts the left atom into the right one is
+//! selected. Deleting or inserting a sub-atom costs its size in atoms,
+//! replacing costs the size of the largest of the replaced atoms. Thus only
+//! the minimal differing sub-atoms are reported.
+
+use crate::*;
+use hyperon_common::collections::SliceDisplay;
+
+use std::fmt::{Display, Formatter};
+use std::ops::ControlFlow;
+
+/// Single edit of the atom. All paths are paths inside the left atom, see
+/// [Atom::get_at]. Path of [AtomEdit::Insert] points to the position of the
+/// left atom before which the new atom is inserted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtomEdit {
+    /// Sub-atom `from` is replaced by `to`.
+    Replace { path: Vec<usize>, from: Atom, to: Atom },
+    /// New sub-atom is inserted.
+    Insert { path: Vec<usize>, atom: Atom },
+    /// Sub-atom is deleted.
+    Delete { path: Vec<usize>, atom: Atom },
+}
+
+impl AtomEdit {
+    /// Returns path of the edit.
+    pub fn path(&self) -> &[usize] {
+        match self {
+            AtomEdit::Replace{ path, .. } => path,
+            AtomEdit::Insert{ path, .. } => path,
+            AtomEdit::Delete{ path, .. } => path,
+        }
+    }
+
+    fn path_mut(&mut self) -> &mut Vec<usize> {
+        match self {
+            AtomEdit::Replace{ path, .. } => path,
+            AtomEdit::Insert{ path, .. } => path,
+            AtomEdit::Delete{ path, .. } => path,
+        }
+    }
+}
+
+impl Display for AtomEdit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtomEdit::Replace{ path, from, to } => write!(f, "{}: {} -> {}", SliceDisplay(path), from, to),
+            AtomEdit::Insert{ path, atom } => write!(f, "{}: + {}", SliceDisplay(path), atom),
+            AtomEdit::Delete{ path, atom } => write!(f, "{}: - {}", SliceDisplay(path), atom),
+        }
+    }
+}
+
+/// Structural difference between two atoms returned by [diff_atoms].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AtomDiff {
+    distance: usize,
+    edits: Vec<AtomEdit>,
+}
+
+impl AtomDiff {
+    /// Returns tree edit distance between atoms.
+    pub fn distance(&self) -> usize {
+        self.distance
+    }
+
+    /// Returns edits in the order of their paths.
+    pub fn edits(&self) -> &[AtomEdit] {
+        &self.edits
+    }
+
+    /// Returns `true` if atoms are equal.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Applies the edits to the left atom converting it into the right one.
+    /// Edits are applied in reverse order so the paths of the remaining edits
+    /// stay valid. Returns `false` if some edit cannot be applied, which
+    /// means `atom` is not the left atom of the difference.
+    pub fn apply(&self, atom: &mut Atom) -> bool {
+        self.edits.iter().rev().all(|edit| match edit {
+            AtomEdit::Replace{ path, to, .. } => atom.replace_at(path, to.clone()).is_some(),
+            AtomEdit::Insert{ path, atom: new } => atom.insert_at(path, new.clone()),
+            AtomEdit::Delete{ path, .. } => atom.remove_at(path).is_some(),
+        })
+    }
+}
+
+impl Display for AtomDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.edits.iter().enumerate().try_for_each(|(i, edit)| {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", edit)
+        })
+    }
+}
+
+/// Calculates structural difference between `left` and `right` atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::diff::{diff_atoms, AtomEdit};
+///
+/// let left = expr!("A" ("B" "C") "D");
+/// let right = expr!("A" ("B" "E"));
+/// let diff = diff_atoms(&left, &right);
+///
+/// assert_eq!(diff.edits(), &[
+///     AtomEdit::Replace{ path: vec![1, 1], from: expr!("C"), to: expr!("E") },
+///     AtomEdit::Delete{ path: vec![2], atom: expr!("D") },
+/// ]);
+/// assert_eq!(diff.to_string(), "[1, 1]: C -> E\n[2]: - D");
+/// ```
+pub fn diff_atoms(left: &Atom, right: &Atom) -> AtomDiff {
+    if left == right {
+        return AtomDiff::default();
+    }
+    let replace_distance = std::cmp::max(atom_size(left), atom_size(right));
+    if let (Atom::Expression(l), Atom::Expression(r)) = (left, right) {
+        let aligned = diff_children(l.children(), r.children());
+        if aligned.distance < replace_distance {
+            return aligned;
+        }
+    }
+    AtomDiff {
+        distance: replace_distance,
+        edits: vec![AtomEdit::Replace{ path: vec![], from: left.clone(), to: right.clone() }],
+    }
+}
+
+/// Returns number of atoms inside `atom` including `atom` itself. It is the
+/// cost of inserting or deleting the atom.
+pub fn atom_size(atom: &Atom) -> usize {
+    atom.fold(VisitOrder::TopDown, 0, |size, _path, _atom| ControlFlow::Continue(size + 1))
+}
+
+fn diff_children(left: &[Atom], right: &[Atom]) -> AtomDiff {
+    let (n, m) = (left.len(), right.len());
+    let left_size: Vec<usize> = left.iter().map(atom_size).collect();
+    let right_size: Vec<usize> = right.iter().map(atom_size).collect();
+    let mut pairs: Vec<Vec<AtomDiff>> = left.iter()
+        .map(|l| right.iter().map(|r| diff_atoms(l, r)).collect())
+        .collect();
+
+    let mut cost = vec![vec![0; m + 1]; n + 1];
+    for i in 1..=n {
+        cost[i][0] = cost[i - 1][0] + left_size[i - 1];
+    }
+    for j in 1..=m {
+        cost[0][j] = cost[0][j - 1] + right_size[j - 1];
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            cost[i][j] = (cost[i - 1][j - 1] + pairs[i - 1][j - 1].distance)
+                .min(cost[i - 1][j] + left_size[i - 1])
+                .min(cost[i][j - 1] + right_size[j - 1]);
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        // Trailing insertions and deletions are preferred to keep the
+        // replaced children aligned to the left
+        if j > 0 && cost[i][j] == cost[i][j - 1] + right_size[j - 1] {
+            edits.push(AtomEdit::Insert{ path: vec![i], atom: right[j - 1].clone() });
+            j -= 1;
+        } else if i > 0 && cost[i][j] == cost[i - 1][j] + left_size[i - 1] {
+            edits.push(AtomEdit::Delete{ path: vec![i - 1], atom: left[i - 1].clone() });
+            i -= 1;
+        } else {
+            let pair = std::mem::take(&mut pairs[i - 1][j - 1].edits);
+            edits.extend(pair.into_iter().rev().map(|mut edit| {
+                edit.path_mut().insert(0, i - 1);
+                edit
+            }));
+            i -= 1;
+            j -= 1;
+        }
+    }
+    edits.reverse();
+    AtomDiff{ distance: cost[n][m], edits }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_diff_applies(left: Atom, right: Atom) {
+        let diff = diff_atoms(&left, &right);
+        let mut actual = left.clone();
+        assert!(diff.apply(&mut actual), "cannot apply {} to {}", diff, left);
+        assert_eq!(actual, right);
+    }
+
+    #[test]
+    fn diff_atoms_equal() {
+        let diff = diff_atoms(&expr!("A" ("B" x)), &expr!("A" ("B" x)));
+        assert!(diff.is_empty());
+        assert_eq!(diff.distance(), 0);
+    }
+
+    #[test]
+    fn diff_atoms_replace_leaf() {
+        let diff = diff_atoms(&expr!("A" ("B" "C")), &expr!("A" ("B" "D")));
+        assert_eq!(diff.distance(), 1);
+        assert_eq!(diff.edits(), &[AtomEdit::Replace{ path: vec![1, 1], from: expr!("C"), to: expr!("D") }]);
+    }
+
+    #[test]
+    fn diff_atoms_replace_whole() {
+        let diff = diff_atoms(&expr!("A" "B"), &expr!("C"));
+        assert_eq!(diff.edits(), &[AtomEdit::Replace{ path: vec![], from: expr!("A" "B"), to: expr!("C") }]);
+        let diff = diff_atoms(&expr!("A" ("B" "C")), &expr!("D"));
+        assert_eq!(diff.distance(), 4);
+        assert_eq!(diff.edits().len(), 1);
+    }
+
+    #[test]
+    fn diff_atoms_insert_and_delete() {
+        let diff = diff_atoms(&expr!("A" "B" "C" "D"), &expr!("A" "C" "D" ("E" "F")));
+        assert_eq!(diff.distance(), 4);
+        assert_eq!(diff.edits(), &[
+            AtomEdit::Delete{ path: vec![1], atom: expr!("B") },
+            AtomEdit::Insert{ path: vec![4], atom: expr!("E" "F") },
+        ]);
+    }
+
+    #[test]
+    fn diff_atoms_apply() {
+        assert_diff_applies(expr!("A" "B" "C" "D"), expr!("A" "C" "D" ("E" "F")));
+        assert_diff_applies(expr!("A" ("B" "C" "D") "E"), expr!("X" "A" ("B" "D") ("E")));
+        assert_diff_applies(expr!("A" "B"), expr!("B" "A"));
+        assert_diff_applies(expr!(), expr!("A" ("B")));
+        assert_diff_applies(expr!("A" ("B" "C")), expr!());
+    }
+
+    #[test]
+    fn diff_atoms_display() {
+        let diff = diff_atoms(&expr!("A" "B"), &expr!("A" "C" "D"));
+        assert_eq!(diff.to_string(), "[1]: B -> C\n[2]: + D");
+    }
+}
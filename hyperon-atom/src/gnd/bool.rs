@@ -35,6 +35,13 @@ impl TryFrom<&Atom> for Bool {
     }
 }
 
+impl TryFrom<&Atom> for bool {
+    type Error = &'static str;
+    fn try_from(value: &Atom) -> Result<Self, Self::Error> {
+        Bool::try_from(value).map(|b| b.0)
+    }
+}
+
 impl TryFrom<&dyn GroundedAtom> for Bool {
     type Error = &'static str;
     fn try_from(value: &dyn GroundedAtom) -> Result<Self, Self::Error> {
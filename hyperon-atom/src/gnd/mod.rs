@@ -1,6 +1,7 @@
 pub mod str;
 pub mod number;
 pub mod bool;
+pub mod op;
 
 use std::rc::Rc;
 
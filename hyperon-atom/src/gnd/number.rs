@@ -71,6 +71,23 @@ impl TryFrom<&dyn GroundedAtom> for Number {
     }
 }
 
+impl TryFrom<&Atom> for i64 {
+    type Error = &'static str;
+    fn try_from(value: &Atom) -> Result<Self, Self::Error> {
+        match Number::try_from(value)? {
+            Number::Integer(n) => Ok(n),
+            Number::Float(_) => Err("Integer number is expected"),
+        }
+    }
+}
+
+impl TryFrom<&Atom> for f64 {
+    type Error = &'static str;
+    fn try_from(value: &Atom) -> Result<Self, Self::Error> {
+        Number::try_from(value).map(Into::into)
+    }
+}
+
 impl Number {
     pub fn from_int_str(num: &str) -> Result<Self, String> {
         let n = num.parse::<i64>().map_err(|e| format!("Could not parse integer: '{num}', {e}"))?;
@@ -137,6 +154,10 @@ impl Grounded for Number {
         ATOM_TYPE_NUMBER
     }
 
+    fn as_index(&self) -> Option<&dyn CustomIndex> {
+        Some(self)
+    }
+
     fn serialize(&self, serializer: &mut dyn serial::Serializer) -> serial::Result {
         match self {
             &Self::Integer(n) => serializer.serialize_i64(n),
@@ -145,6 +166,89 @@ impl Grounded for Number {
     }
 }
 
+impl CustomIndex for Number {
+    fn index_key(&self) -> Option<IndexKey> {
+        Some(IndexKey::number(self.clone().into()))
+    }
+}
+
+/// Pattern which matches numbers between `min` and `max` inclusively. It
+/// provides range of the index keys thus matching numbers are looked up in
+/// the atom index instead of scanning all atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::matcher::match_atoms;
+/// use hyperon_atom::gnd::number::{Number, NumberRange};
+///
+/// let range = Atom::gnd(NumberRange::new(Number::Integer(10), Number::Integer(20)));
+///
+/// assert_eq!(match_atoms(&range, &Atom::gnd(Number::Float(12.5))).count(), 1);
+/// assert_eq!(match_atoms(&range, &Atom::gnd(Number::Integer(21))).count(), 0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberRange {
+    min: Number,
+    max: Number,
+}
+
+impl NumberRange {
+    /// Constructs range of numbers between `min` and `max` inclusively.
+    pub fn new(min: Number, max: Number) -> Self {
+        Self{ min, max }
+    }
+
+    /// Returns `true` if `n` is inside the range.
+    pub fn contains(&self, n: &Number) -> bool {
+        fn le(a: &Number, b: &Number) -> bool {
+            match Number::promote(a.clone(), b.clone()) {
+                (Number::Integer(a), Number::Integer(b)) => a <= b,
+                (Number::Float(a), Number::Float(b)) => a <= b,
+                _ => panic!("Unexpected state!"),
+            }
+        }
+        le(&self.min, n) && le(n, &self.max)
+    }
+}
+
+impl Grounded for NumberRange {
+    fn type_(&self) -> Atom {
+        ATOM_TYPE_NUMBER
+    }
+
+    fn as_match(&self) -> Option<&dyn CustomMatch> {
+        Some(self)
+    }
+
+    fn as_index(&self) -> Option<&dyn CustomIndex> {
+        Some(self)
+    }
+}
+
+impl CustomMatch for NumberRange {
+    fn match_(&self, other: &Atom) -> matcher::MatchResultIter {
+        match Number::from_atom(other) {
+            Some(n) if self.contains(&n) => Box::new(std::iter::once(matcher::Bindings::new())),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+impl CustomIndex for NumberRange {
+    fn index_range(&self) -> Option<IndexRange> {
+        Some(IndexRange::inclusive(IndexKey::number(self.min.clone().into()),
+            IndexKey::number(self.max.clone().into())))
+    }
+}
+
+impl Display for NumberRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..={}", self.min, self.max)
+    }
+}
+
 #[derive(Default)]
 struct NumberSerializer {
     value: Option<Number>,
@@ -184,4 +288,14 @@ mod tests {
         assert_eq!(format!("{}", Number::Integer(12345i64)), "12345");
         assert_eq!(format!("{}", Number::Float(123.45f64)), "123.45");
     }
+
+    #[test]
+    fn number_range() {
+        let range = NumberRange::new(Number::Integer(-1), Number::Float(2.5));
+        assert!(range.contains(&Number::Integer(-1)));
+        assert!(range.contains(&Number::Float(2.5)));
+        assert!(!range.contains(&Number::Integer(3)));
+        assert_eq!(range.index_range(), Some(IndexRange::inclusive(IndexKey::number(-1.0), IndexKey::number(2.5))));
+        assert_eq!(Number::Float(-0.0).index_key(), Number::Integer(0).index_key());
+    }
 }
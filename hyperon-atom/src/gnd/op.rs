@@ -0,0 +1,147 @@
+//! Traits used by the grounded operations generated by the
+//! `hyperon_macros::metta_op` attribute. They describe how Rust values are
+//! represented in MeTTa: which MeTTa type corresponds to a Rust type and how
+//! a value returned by a Rust function is converted into results of the
+//! grounded operation. Arguments are extracted from atoms using
+//! `TryFrom<&Atom>` implementations.
+
+use crate::*;
+use super::str::*;
+use super::number::*;
+use super::bool::*;
+
+/// Grounded operation which is referred by a fixed token from MeTTa code.
+/// Implemented by the `metta_op` macro and used to register operation in
+/// a tokenizer.
+pub trait GroundedOp: CustomGroundedType + Default {
+    /// Name of the operation which is used as a token.
+    const NAME: &'static str;
+}
+
+/// Rust type which has a corresponding MeTTa type.
+pub trait MettaType {
+    /// Returns MeTTa type of the values of the Rust type.
+    fn metta_type() -> Atom;
+}
+
+macro_rules! metta_type {
+    ($typ:ty, $name:literal) => {
+        impl MettaType for $typ {
+            fn metta_type() -> Atom {
+                sym!($name)
+            }
+        }
+    }
+}
+
+metta_type!(i64, "Number");
+metta_type!(f64, "Number");
+metta_type!(Number, "Number");
+metta_type!(bool, "Bool");
+metta_type!(Bool, "Bool");
+metta_type!(String, "String");
+metta_type!(Str, "String");
+metta_type!(&str, "String");
+metta_type!(Atom, "%Undefined%");
+metta_type!(&Atom, "%Undefined%");
+metta_type!(Vec<Atom>, "%Undefined%");
+metta_type!(ExpressionAtom, "Expression");
+metta_type!(&ExpressionAtom, "Expression");
+metta_type!(&[Atom], "Expression");
+metta_type!(SymbolAtom, "Symbol");
+metta_type!(&SymbolAtom, "Symbol");
+metta_type!(VariableAtom, "Variable");
+metta_type!(&VariableAtom, "Variable");
+
+/// Argument of the grounded operation which has the `Atom` MeTTa type. Such
+/// argument is passed to the operation without evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unevaluated<'a>(pub &'a Atom);
+
+impl<'a> From<&'a Atom> for Unevaluated<'a> {
+    fn from(atom: &'a Atom) -> Self {
+        Self(atom)
+    }
+}
+
+impl MettaType for Unevaluated<'_> {
+    fn metta_type() -> Atom {
+        sym!("Atom")
+    }
+}
+
+impl MettaType for () {
+    fn metta_type() -> Atom {
+        Atom::expr([sym!("->")])
+    }
+}
+
+impl<T: MettaType, E> MettaType for Result<T, E> {
+    fn metta_type() -> Atom {
+        T::metta_type()
+    }
+}
+
+/// Converts value returned by a Rust function into the list of results of
+/// the grounded operation.
+pub trait IntoOpResult {
+    fn into_op_result(self) -> Result<Vec<Atom>, ExecError>;
+}
+
+macro_rules! into_op_result {
+    ($typ:ty, $v:ident => $atom:expr) => {
+        impl IntoOpResult for $typ {
+            fn into_op_result(self) -> Result<Vec<Atom>, ExecError> {
+                let $v = self;
+                Ok(vec![$atom])
+            }
+        }
+    }
+}
+
+into_op_result!(i64, n => Atom::gnd(Number::Integer(n)));
+into_op_result!(f64, n => Atom::gnd(Number::Float(n)));
+into_op_result!(Number, n => Atom::gnd(n));
+into_op_result!(bool, b => Atom::gnd(Bool(b)));
+into_op_result!(Bool, b => Atom::gnd(b));
+into_op_result!(String, s => Atom::gnd(Str::from_string(s)));
+into_op_result!(Str, s => Atom::gnd(s));
+into_op_result!(Atom, atom => atom);
+into_op_result!(ExpressionAtom, expr => Atom::Expression(expr));
+into_op_result!(SymbolAtom, sym => Atom::Symbol(sym));
+into_op_result!(VariableAtom, var => Atom::Variable(var));
+into_op_result!((), _unit => Atom::expr([]));
+
+impl IntoOpResult for Vec<Atom> {
+    fn into_op_result(self) -> Result<Vec<Atom>, ExecError> {
+        Ok(self)
+    }
+}
+
+impl<T: IntoOpResult, E: Into<ExecError>> IntoOpResult for Result<T, E> {
+    fn into_op_result(self) -> Result<Vec<Atom>, ExecError> {
+        self.map_err(Into::into).and_then(IntoOpResult::into_op_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metta_type_of_rust_types() {
+        assert_eq!(<i64 as MettaType>::metta_type(), ATOM_TYPE_NUMBER);
+        assert_eq!(<&str as MettaType>::metta_type(), ATOM_TYPE_STRING);
+        assert_eq!(<Result<bool, ExecError> as MettaType>::metta_type(), ATOM_TYPE_BOOL);
+        assert_eq!(<() as MettaType>::metta_type(), expr!(("->")));
+        assert_eq!(<Unevaluated as MettaType>::metta_type(), sym!("Atom"));
+    }
+
+    #[test]
+    fn into_op_result() {
+        assert_eq!(42i64.into_op_result(), Ok(vec![Atom::gnd(Number::Integer(42))]));
+        assert_eq!(String::from("a").into_op_result(), Ok(vec![Atom::gnd(Str::from_str("a"))]));
+        assert_eq!(().into_op_result(), Ok(vec![Atom::expr([])]));
+        assert_eq!(Err::<i64, _>("error").into_op_result(), Err(ExecError::from("error")));
+    }
+}
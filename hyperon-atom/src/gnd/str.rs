@@ -39,11 +39,90 @@ impl Grounded for Str {
         ATOM_TYPE_STRING
     }
 
+    fn as_index(&self) -> Option<&dyn CustomIndex> {
+        Some(self)
+    }
+
     fn serialize(&self, serializer: &mut dyn serial::Serializer) -> serial::Result {
         serializer.serialize_str(self.as_str())
     }
 }
 
+impl CustomIndex for Str {
+    fn index_key(&self) -> Option<IndexKey> {
+        Some(IndexKey::String(self.as_str().into()))
+    }
+}
+
+/// Pattern which matches strings starting with the prefix. It provides
+/// range of the index keys thus matching strings are looked up in the atom
+/// index instead of scanning all atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::matcher::match_atoms;
+/// use hyperon_atom::gnd::str::{Str, StrPrefix};
+///
+/// let prefix = Atom::gnd(StrPrefix::new("http://"));
+///
+/// assert_eq!(match_atoms(&prefix, &Atom::gnd(Str::from_str("http://example.com"))).count(), 1);
+/// assert_eq!(match_atoms(&prefix, &Atom::gnd(Str::from_str("ftp://example.com"))).count(), 0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrPrefix(ImmutableString);
+
+impl StrPrefix {
+    /// Constructs pattern matching strings which start with `prefix`.
+    pub fn new(prefix: &'static str) -> Self {
+        Self(ImmutableString::Literal(prefix))
+    }
+    /// Constructs pattern matching strings which start with owned `prefix`.
+    pub fn from_string(prefix: String) -> Self {
+        Self(ImmutableString::Allocated(prefix))
+    }
+    /// Returns the prefix.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Grounded for StrPrefix {
+    fn type_(&self) -> Atom {
+        ATOM_TYPE_STRING
+    }
+
+    fn as_match(&self) -> Option<&dyn CustomMatch> {
+        Some(self)
+    }
+
+    fn as_index(&self) -> Option<&dyn CustomIndex> {
+        Some(self)
+    }
+}
+
+impl CustomMatch for StrPrefix {
+    fn match_(&self, other: &Atom) -> matcher::MatchResultIter {
+        match Str::from_atom(other) {
+            Some(s) if s.as_str().starts_with(self.as_str()) => Box::new(std::iter::once(matcher::Bindings::new())),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+impl CustomIndex for StrPrefix {
+    fn index_range(&self) -> Option<IndexRange> {
+        Some(IndexRange::prefix(self.as_str()))
+    }
+}
+
+impl std::fmt::Display for StrPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}..", self.as_str())
+    }
+}
+
 impl std::fmt::Display for Str {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.0.as_str())
@@ -64,6 +143,13 @@ impl TryFrom<&Atom> for Str {
     }
 }
 
+impl TryFrom<&Atom> for String {
+    type Error = &'static str;
+    fn try_from(value: &Atom) -> Result<Self, Self::Error> {
+        Str::try_from(value).map(Into::into)
+    }
+}
+
 impl TryFrom<&dyn GroundedAtom> for Str {
     type Error = &'static str;
     fn try_from(value: &dyn GroundedAtom) -> Result<Self, Self::Error> {
@@ -135,6 +221,7 @@ pub fn expect_string_like_atom(atom: &Atom) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ops::RangeBounds;
 
     #[test]
     fn str_display_escape() {
@@ -148,6 +235,25 @@ mod tests {
         assert_eq!("\\ \" \' \n \r \t \x1b abc", s.unwrap());
     }
 
+    #[test]
+    fn str_prefix_match() {
+        let prefix = Atom::gnd(StrPrefix::new("ab"));
+        assert_eq!(matcher::match_atoms(&prefix, &Atom::gnd(Str::from_str("ab"))).count(), 1);
+        assert_eq!(matcher::match_atoms(&prefix, &Atom::gnd(Str::from_str("abc"))).count(), 1);
+        assert_eq!(matcher::match_atoms(&prefix, &Atom::gnd(Str::from_str("a"))).count(), 0);
+        assert_eq!(matcher::match_atoms(&prefix, &Atom::sym("abc")).count(), 0);
+    }
+
+    #[test]
+    fn str_prefix_index_range() {
+        let range = StrPrefix::new("ab").index_range().unwrap();
+        let key = |s: &str| Str::from_string(s.into()).index_key().unwrap();
+        assert!(range.contains(&key("ab")));
+        assert!(range.contains(&key("abz")));
+        assert!(!range.contains(&key("ac")));
+        assert!(!range.contains(&key("aa")));
+    }
+
     #[test]
     fn test_atom_to_string() {
         let atom = Atom::gnd(Str::from_str("A\nB"));
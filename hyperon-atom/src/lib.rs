@@ -113,10 +113,15 @@ macro_rules! sym {
 pub mod matcher;
 pub mod subexpr;
 mod iter;
+mod visit;
+pub mod zipper;
+pub mod diff;
+pub mod pretty;
 pub mod serial;
 pub mod gnd;
 
 pub use iter::*;
+pub use visit::*;
 
 use std::any::Any;
 use std::fmt::{Display, Debug};
@@ -341,7 +346,12 @@ impl Display for VariableAtom {
 /// Returns `atom` with all variables replaced by unique instances.
 pub fn make_variables_unique(mut atom: Atom) -> Atom {
     let mut mapper = hyperon_common::CachingMapper::new(|v: &VariableAtom| v.clone().make_unique());
-    atom.iter_mut().filter_type::<&mut VariableAtom>().for_each(|var| *var = mapper.replace(var));
+    atom.visit_mut(VisitOrder::TopDown, |_path, atom| {
+        if let Atom::Variable(var) = atom {
+            *var = mapper.replace(var);
+        }
+        VisitResult::Continue
+    });
     atom
 }
 
@@ -502,6 +512,14 @@ pub trait Grounded : Display {
         None
     }
 
+    /// Returns reference to the custom index API implementation. If `None`
+    /// is returned then atom index compares the atom by equality or scans
+    /// all atoms when atom has custom matching.
+    /// See [CustomIndex] for details.
+    fn as_index(&self) -> Option<&dyn CustomIndex> {
+        None
+    }
+
     /// Implements serialization logic of the grounded atom. The logic is
     /// implemented in terms of the Rust native types.
     /// See [serial] for details.
@@ -627,6 +645,146 @@ pub trait CustomMatch {
     fn match_(&self, other: &Atom) -> matcher::MatchResultIter;
 }
 
+/// Ordered key of the grounded value which is used by the atom index to
+/// look up grounded atoms by range of keys. Numbers are ordered before
+/// strings.
+#[derive(Clone, Debug)]
+pub enum IndexKey {
+    /// Numeric key, integers and floats share the same key space.
+    Number(f64),
+    /// String key.
+    String(String),
+}
+
+impl IndexKey {
+    /// Constructs numeric key. Negative zero is normalized to make it equal
+    /// to zero.
+    pub fn number(n: f64) -> Self {
+        IndexKey::Number(n + 0.0)
+    }
+}
+
+impl PartialEq for IndexKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for IndexKey {}
+
+impl std::hash::Hash for IndexKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            IndexKey::Number(n) => n.to_bits().hash(state),
+            IndexKey::String(s) => s.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for IndexKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IndexKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (IndexKey::Number(a), IndexKey::Number(b)) => a.total_cmp(b),
+            (IndexKey::String(a), IndexKey::String(b)) => a.cmp(b),
+            (IndexKey::Number(_), IndexKey::String(_)) => std::cmp::Ordering::Less,
+            (IndexKey::String(_), IndexKey::Number(_)) => std::cmp::Ordering::Greater,
+        }
+    }
+}
+
+/// Range of the [IndexKey] values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexRange {
+    pub start: std::ops::Bound<IndexKey>,
+    pub end: std::ops::Bound<IndexKey>,
+}
+
+impl IndexRange {
+    /// Constructs range which includes both `start` and `end`.
+    pub fn inclusive(start: IndexKey, end: IndexKey) -> Self {
+        use std::ops::Bound::Included;
+        Self{ start: Included(start), end: Included(end) }
+    }
+
+    /// Constructs range of the string keys which start with `prefix`.
+    pub fn prefix(prefix: &str) -> Self {
+        use std::ops::Bound::{Included, Excluded, Unbounded};
+        let mut chars: Vec<char> = prefix.chars().collect();
+        let mut end = Unbounded;
+        while let Some(c) = chars.pop() {
+            if let Some(next) = char::from_u32(c as u32 + 1) {
+                chars.push(next);
+                end = Excluded(IndexKey::String(chars.into_iter().collect()));
+                break;
+            }
+        }
+        Self{ start: Included(IndexKey::String(prefix.into())), end }
+    }
+
+    /// Returns `true` if there are no keys inside the range.
+    pub fn is_empty(&self) -> bool {
+        use std::ops::Bound::{Included, Excluded};
+        match (&self.start, &self.end) {
+            (Included(start), Included(end)) => start > end,
+            (Included(start), Excluded(end))
+                | (Excluded(start), Included(end))
+                | (Excluded(start), Excluded(end)) => start >= end,
+            _ => false,
+        }
+    }
+}
+
+impl std::ops::RangeBounds<IndexKey> for IndexRange {
+    fn start_bound(&self) -> std::ops::Bound<&IndexKey> {
+        self.start.as_ref()
+    }
+    fn end_bound(&self) -> std::ops::Bound<&IndexKey> {
+        self.end.as_ref()
+    }
+}
+
+/// Trait for the grounded atoms which can be looked up in the atom index by
+/// range of keys instead of scanning all atoms. In order to make it work one
+/// should also implement [Grounded::as_index] method.
+///
+/// Grounded value which is stored in the index provides its key using
+/// [CustomIndex::index_key]. Grounded pattern which has [CustomMatch]
+/// implementation and is used in query provides range of keys using
+/// [CustomIndex::index_range]. Pattern should match only atoms which keys
+/// are inside the range, atoms without key are not matched with the pattern
+/// by index (except variables and atoms with custom matching).
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::gnd::number::{Number, NumberRange};
+///
+/// let range = NumberRange::new(Number::Integer(10), Number::Integer(20));
+///
+/// assert_eq!(range.index_range(), Some(IndexRange::inclusive(IndexKey::number(10.0), IndexKey::number(20.0))));
+/// assert_eq!(Number::Integer(15).index_key(), Some(IndexKey::number(15.0)));
+/// ```
+pub trait CustomIndex {
+    /// Returns key of the value, `None` if value is not looked up by key.
+    fn index_key(&self) -> Option<IndexKey> {
+        None
+    }
+
+    /// Returns range of the keys of the atoms which can be matched by this
+    /// atom when it is used as a pattern. `None` means matched atoms cannot
+    /// be looked up by range.
+    fn index_range(&self) -> Option<IndexRange> {
+        None
+    }
+}
+
 /// Returns the name of the Rust type wrapped into [Atom::Symbol]. This is a
 /// default implementation of `type_()` for the grounded types wrapped
 /// automatically.
@@ -778,6 +936,59 @@ impl<T: CustomGroundedType> CustomGroundedTypeToAtom for &Wrap<T> {
     }
 }
 
+/// Converts Rust values into atoms inside [metta!] macro. It is kept in a
+/// separate module to not affect [expr!] which imports all traits of the
+/// crate. Not intended to be used by library users.
+#[doc(hidden)]
+pub mod metta_macro {
+    use super::*;
+    use gnd::number::Number;
+    use gnd::bool::Bool;
+    use gnd::str::Str;
+
+    /// Converts Rust value into atom using its MeTTa representation: atoms
+    /// are inserted as is, numbers, strings and booleans are converted into
+    /// the corresponding MeTTa grounded values. All primitive integer and
+    /// float types are converted into [Number], unsuffixed literals get the
+    /// default `i32` and `f64` types. Integer which doesn't fit into `i64`
+    /// causes panic.
+    pub trait MettaValueToAtom { fn to_atom(&self) -> Atom; }
+
+    macro_rules! metta_value_to_atom {
+        ($typ:ty, $v:ident => $atom:expr) => {
+            impl MettaValueToAtom for &&Wrap<$typ> {
+                fn to_atom(&self) -> Atom {
+                    let $v = &self.0;
+                    $atom
+                }
+            }
+        }
+    }
+
+    metta_value_to_atom!(Atom, atom => atom.clone());
+    metta_value_to_atom!(&Atom, atom => (*atom).clone());
+    metta_value_to_atom!(i64, n => Atom::gnd(Number::Integer(*n)));
+    metta_value_to_atom!(f64, n => Atom::gnd(Number::Float(*n)));
+    metta_value_to_atom!(f32, n => Atom::gnd(Number::Float(f64::from(*n))));
+
+    macro_rules! metta_integer_to_atom {
+        ($convert:path, $($typ:ty),*) => {
+            $( metta_value_to_atom!($typ, n => Atom::gnd(Number::Integer($convert(*n)))); )*
+        }
+    }
+
+    /// Panics if value doesn't fit into `i64` used by MeTTa [Number].
+    fn checked_i64<T: TryInto<i64>>(n: T) -> i64 {
+        n.try_into().unwrap_or_else(|_| panic!("Integer is out of range of MeTTa Number"))
+    }
+
+    metta_integer_to_atom!(i64::from, i8, i16, i32, u8, u16, u32);
+    metta_integer_to_atom!(checked_i64, isize, usize, u64, i128, u128);
+    metta_value_to_atom!(bool, b => Atom::gnd(Bool(*b)));
+    metta_value_to_atom!(String, s => Atom::gnd(Str::from_string(s.clone())));
+    metta_value_to_atom!(&str, s => Atom::gnd(Str::from_string(s.to_string())));
+}
+
 impl Clone for Box<dyn GroundedAtom> {
     fn clone(&self) -> Self {
         self.clone_gnd()
@@ -1105,6 +1316,27 @@ impl<'a> TryFrom<&'a Atom> for &'a dyn GroundedAtom {
 
 impl Eq for Atom {}
 
+/// Hash is consistent with the equality of atoms. Grounded atoms are
+/// compared by [GroundedAtom::eq_gnd] which can treat different values as
+/// equal, for instance `1` and `1.0`, thus only the type of the grounded
+/// atom and its [IndexKey] are hashed.
+impl std::hash::Hash for Atom {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Atom::Symbol(sym) => sym.hash(state),
+            Atom::Variable(var) => var.hash(state),
+            Atom::Expression(expr) => expr.children().hash(state),
+            Atom::Grounded(gnd) => {
+                gnd.type_().hash(state);
+                gnd.as_grounded().as_index()
+                    .and_then(|index| index.index_key())
+                    .hash(state);
+            },
+        }
+    }
+}
+
 impl Display for Atom {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1407,4 +1639,28 @@ mod test {
         assert_eq!(I64Serializer::convert(&AutoGroundedAtom("42")), Err("Incorrect type"));
         assert_eq!(I64Serializer::convert(&CustomGroundedAtom(F64Gnd(42.0))), Err("Incorrect type"));
     }
+
+    #[test]
+    fn index_range_prefix() {
+        use std::ops::{Bound, RangeBounds};
+        let range = IndexRange::prefix("ab");
+        assert_eq!(range.end, Bound::Excluded(IndexKey::String("ac".into())));
+        assert!(range.contains(&IndexKey::String("abz".into())));
+        assert!(!range.contains(&IndexKey::String("ac".into())));
+        assert!(!range.contains(&IndexKey::number(1.0)));
+        assert!(!range.is_empty());
+        assert!(IndexRange::inclusive(IndexKey::number(2.0), IndexKey::number(1.0)).is_empty());
+    }
+
+    #[test]
+    fn atom_hash_is_consistent_with_eq() {
+        use crate::gnd::number::Number;
+        use std::hash::{BuildHasher, RandomState};
+        let state = RandomState::new();
+        let integer = expr!("a" ("b" x) {Number::Integer(1)});
+        let float = expr!("a" ("b" x) {Number::Float(1.0)});
+        assert_eq!(integer, float);
+        assert_eq!(state.hash_one(&integer), state.hash_one(&float));
+        assert_ne!(state.hash_one(&integer), state.hash_one(&expr!("a" ("b" y) {Number::Integer(1)})));
+    }
 }
@@ -1165,14 +1165,15 @@ pub fn apply_bindings_to_atom_mut(atom: &mut Atom, bindings: &Bindings) {
     };
     let mut updated = false;
     if !bindings.is_empty() {
-        atom.iter_mut().for_each(|atom| match atom {
+        atom.visit_mut(VisitOrder::TopDown, |_path, atom| match atom {
             Atom::Variable(var) => {
                 bindings.resolve(var).map(|value| {
                     *atom = value;
                     updated = true;
                 });
+                VisitResult::SkipChildren
             },
-            _ => {},
+            _ => VisitResult::Continue,
         });
     }
     if updated {
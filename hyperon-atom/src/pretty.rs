@@ -0,0 +1,283 @@
+//! Width-aware pretty printer for atoms. Layout algorithm follows Wadler's
+//! "A prettier printer": expression is printed on a single line when it fits
+//! into the configured width, otherwise its children are placed on separate
+//! lines and indented. Printer inserts only whitespace between the tokens
+//! printed by [Display] implementation thus the result can be parsed back.
+//!
+//! Expressions which start from one of the configured block symbols are
+//! formatted as blocks: the first arguments are kept on the line of the
+//! head symbol and the rest of the arguments is placed on the next lines.
+//!
+//! # Examples
+//!
+//! ```
+//! use hyperon_atom::*;
+//! use hyperon_atom::pretty::PrettyPrinter;
+//!
+//! let atom = expr!("let" x ("foo" "A") ("bar" x x));
+//!
+//! assert_eq!(PrettyPrinter::new().print(&atom), "(let $x (foo A) (bar $x $x))");
+//! assert_eq!(PrettyPrinter::new().width(20).print(&atom), "(let $x (foo A)\n  (bar $x $x))");
+//! ```
+
+use crate::*;
+
+/// Default maximal width of the line.
+pub const DEFAULT_WIDTH: usize = 80;
+/// Default indentation of the nested lines.
+pub const DEFAULT_INDENT: usize = 2;
+
+/// Default block symbols and number of arguments kept on the head line.
+const DEFAULT_BLOCKS: &[(&str, usize)] = &[
+    ("=", 1),
+    ("let", 2),
+    ("let*", 1),
+    ("chain", 2),
+    ("case", 1),
+    ("if", 1),
+    ("match", 2),
+    ("unify", 2),
+];
+
+#[derive(Debug, Clone)]
+enum Doc {
+    Text(String),
+    Line,
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Pretty printer configuration.
+#[derive(Debug, Clone)]
+pub struct PrettyPrinter {
+    width: usize,
+    indent: usize,
+    blocks: Vec<(String, usize)>,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrettyPrinter {
+    /// Constructs printer with [DEFAULT_WIDTH], [DEFAULT_INDENT] and the
+    /// block formatting for the `=`, `let`, `let*`, `chain`, `case`, `if`,
+    /// `match` and `unify` expressions.
+    pub fn new() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            indent: DEFAULT_INDENT,
+            blocks: DEFAULT_BLOCKS.iter().map(|&(head, args)| (head.into(), args)).collect(),
+        }
+    }
+
+    /// Sets maximal width of the line. Atoms which are longer than the
+    /// width are not split thus the line can still exceed it.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets indentation of the nested lines.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Formats expressions started from the `head` symbol as blocks keeping
+    /// first `args` arguments on the line of the head.
+    pub fn block(mut self, head: &str, args: usize) -> Self {
+        match self.blocks.iter_mut().find(|(h, _)| h.as_str() == head) {
+            Some(block) => block.1 = args,
+            None => self.blocks.push((head.into(), args)),
+        }
+        self
+    }
+
+    /// Disables block formatting for all symbols.
+    pub fn no_blocks(mut self) -> Self {
+        self.blocks.clear();
+        self
+    }
+
+    /// Prints `atom`.
+    pub fn print(&self, atom: &Atom) -> String {
+        self.render(&self.atom_doc(atom))
+    }
+
+    /// Prints list of atoms in the same format as
+    /// [VecDisplay](hyperon_common::collections::VecDisplay) does when the
+    /// list fits into the line.
+    pub fn print_list(&self, atoms: &[Atom]) -> String {
+        let mut items = Vec::with_capacity(atoms.len() * 3);
+        for (i, atom) in atoms.iter().enumerate() {
+            if i > 0 {
+                items.push(Doc::Text(",".into()));
+                items.push(Doc::Line);
+            }
+            items.push(self.atom_doc(atom));
+        }
+        let doc = Doc::Group(Box::new(Doc::Concat(vec![
+            Doc::Text("[".into()),
+            Doc::Nest(1, Box::new(Doc::Concat(items))),
+            Doc::Text("]".into()),
+        ])));
+        self.render(&doc)
+    }
+
+    fn block_args(&self, head: &Atom) -> Option<usize> {
+        match head {
+            Atom::Symbol(sym) => self.blocks.iter()
+                .find(|(h, _)| h.as_str() == sym.name())
+                .map(|&(_, args)| args),
+            _ => None,
+        }
+    }
+
+    fn atom_doc(&self, atom: &Atom) -> Doc {
+        let children = match atom {
+            Atom::Expression(expr) if !expr.children().is_empty() => expr.children(),
+            _ => return Doc::Text(atom.to_string()),
+        };
+        let mut docs = vec![Doc::Text("(".into()), self.atom_doc(&children[0])];
+        let header = match self.block_args(&children[0]) {
+            Some(args) if children.len() > args + 1 => args,
+            _ => 0,
+        };
+        for child in &children[1..header + 1] {
+            docs.push(Doc::Text(" ".into()));
+            docs.push(self.atom_doc(child));
+        }
+        let body = children[header + 1..].iter()
+            .flat_map(|child| [Doc::Line, self.atom_doc(child)])
+            .collect();
+        docs.push(Doc::Nest(self.indent, Box::new(Doc::Concat(body))));
+        docs.push(Doc::Text(")".into()));
+        Doc::Group(Box::new(Doc::Concat(docs)))
+    }
+
+    fn render(&self, doc: &Doc) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack: Vec<(usize, Mode, &Doc)> = vec![(0, Mode::Break, doc)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => {
+                    out.push_str(text);
+                    column += text.chars().count();
+                },
+                Doc::Line => match mode {
+                    Mode::Flat => {
+                        out.push(' ');
+                        column += 1;
+                    },
+                    Mode::Break => {
+                        out.push('\n');
+                        out.extend(std::iter::repeat_n(' ', indent));
+                        column = indent;
+                    },
+                },
+                Doc::Nest(nest, doc) => stack.push((indent + nest, mode, &**doc)),
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+                Doc::Group(doc) => {
+                    let mode = if mode == Mode::Flat || fits(self.width as isize - column as isize, doc, &stack) {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, &**doc));
+                },
+            }
+        }
+        out
+    }
+}
+
+/// Checks whether `doc` printed on a single line fits into the `rest` of the
+/// line. Text which follows the `doc` until the next line break is also
+/// taken into account.
+fn fits(mut rest: isize, doc: &Doc, stack: &[(usize, Mode, &Doc)]) -> bool {
+    let mut docs: Vec<(Mode, &Doc)> = vec![(Mode::Flat, doc)];
+    let mut next = stack.len();
+    loop {
+        if rest < 0 {
+            return false;
+        }
+        let (mode, doc) = match docs.pop() {
+            Some(item) => item,
+            None if next > 0 => {
+                next -= 1;
+                let (_indent, mode, doc) = stack[next];
+                (mode, doc)
+            },
+            None => return true,
+        };
+        match doc {
+            Doc::Text(text) => rest -= text.chars().count() as isize,
+            Doc::Line => match mode {
+                Mode::Flat => rest -= 1,
+                Mode::Break => return true,
+            },
+            Doc::Nest(_, doc) => docs.push((mode, &**doc)),
+            Doc::Concat(items) => docs.extend(items.iter().rev().map(|doc| (mode, doc))),
+            Doc::Group(doc) => docs.push((mode, &**doc)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pretty_print_fits_line() {
+        let printer = PrettyPrinter::new();
+        assert_eq!(printer.print(&expr!("A" ("B" x) "C")), "(A (B $x) C)");
+        assert_eq!(printer.print(&expr!()), "()");
+        assert_eq!(printer.print(&expr!("A")), "A");
+    }
+
+    #[test]
+    fn pretty_print_breaks_long_expression() {
+        let printer = PrettyPrinter::new().width(12);
+        assert_eq!(printer.print(&expr!("foo" ("bar" "a" "b") ("baz" "c"))),
+            "(foo\n  (bar a b)\n  (baz c))");
+    }
+
+    #[test]
+    fn pretty_print_nested_groups() {
+        let printer = PrettyPrinter::new().width(16);
+        assert_eq!(printer.print(&expr!("foo" ("bar" "aaaa" "bbbb" "cccc") "d")),
+            "(foo\n  (bar\n    aaaa\n    bbbb\n    cccc)\n  d)");
+    }
+
+    #[test]
+    fn pretty_print_blocks() {
+        let atom = expr!("=" ("foo" x) ("chain" ("bar" x) y ("baz" y)));
+        assert_eq!(PrettyPrinter::new().width(24).print(&atom),
+            "(= (foo $x)\n  (chain (bar $x) $y\n    (baz $y)))");
+        assert_eq!(PrettyPrinter::new().width(24).indent(1).print(&atom),
+            "(= (foo $x)\n (chain (bar $x) $y\n  (baz $y)))");
+        assert_eq!(PrettyPrinter::new().width(24).no_blocks().print(&atom),
+            "(=\n  (foo $x)\n  (chain\n    (bar $x)\n    $y\n    (baz $y)))");
+        assert_eq!(PrettyPrinter::new().width(8).no_blocks().block("foo", 1).print(&expr!("foo" "a" "b" "c")),
+            "(foo a\n  b\n  c)");
+    }
+
+    #[test]
+    fn pretty_print_list() {
+        let atoms = [expr!("A" "B"), expr!("C" "D")];
+        assert_eq!(PrettyPrinter::new().print_list(&atoms), "[(A B), (C D)]");
+        assert_eq!(PrettyPrinter::new().width(8).print_list(&atoms), "[(A B),\n (C D)]");
+        assert_eq!(PrettyPrinter::new().print_list(&[]), "[]");
+    }
+}
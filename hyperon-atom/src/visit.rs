@@ -0,0 +1,308 @@
+use super::*;
+
+use std::ops::ControlFlow;
+
+/// Order in which sub-atoms of the [Atom] are visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitOrder {
+    /// Parent expression is visited before its children.
+    TopDown,
+    /// Children are visited before the parent expression.
+    BottomUp,
+}
+
+/// Value returned by the visitor to control the traversal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitResult {
+    /// Continue traversal.
+    Continue,
+    /// Don't visit children of the current atom. Makes sense only for the
+    /// [VisitOrder::TopDown] traversal, works as [VisitResult::Continue] otherwise.
+    SkipChildren,
+    /// Stop traversal.
+    Stop,
+}
+
+impl Atom {
+    /// Visits the atom and all its sub-atoms in the given order. Visitor
+    /// receives the path of each sub-atom which is a list of the child
+    /// indexes starting from the root atom. Returns `false` if traversal
+    /// was stopped by the visitor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let atom = expr!("A" ("B" x));
+    /// let mut paths = Vec::new();
+    /// atom.visit(VisitOrder::TopDown, |path, _atom| {
+    ///     paths.push(path.to_vec());
+    ///     VisitResult::Continue
+    /// });
+    ///
+    /// assert_eq!(paths, vec![vec![], vec![0], vec![1], vec![1, 0], vec![1, 1]]);
+    /// ```
+    pub fn visit<F>(&self, order: VisitOrder, mut visitor: F) -> bool
+        where F: FnMut(&[usize], &Atom) -> VisitResult
+    {
+        visit_rec(self, order, &mut Vec::new(), &mut visitor)
+    }
+
+    /// Visits the atom and all its sub-atoms in the given order allowing
+    /// visitor to modify them in place. When the atom is replaced by the
+    /// visitor in [VisitOrder::TopDown] traversal the children of the new
+    /// atom are visited. Returns `false` if traversal was stopped by the
+    /// visitor.
+    pub fn visit_mut<F>(&mut self, order: VisitOrder, mut visitor: F) -> bool
+        where F: FnMut(&[usize], &mut Atom) -> VisitResult
+    {
+        visit_mut_rec(self, order, &mut Vec::new(), &mut visitor)
+    }
+
+    /// Folds the atom and all its sub-atoms in the given order. Folding
+    /// function can short-circuit traversal returning [ControlFlow::Break].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let atom = expr!("A" ("B" x) y);
+    /// let vars = atom.fold(VisitOrder::TopDown, 0, |n, _path, atom| match atom {
+    ///     Atom::Variable(_) => ControlFlow::Continue(n + 1),
+    ///     _ => ControlFlow::Continue(n),
+    /// });
+    ///
+    /// assert_eq!(vars, 2);
+    /// ```
+    pub fn fold<T, F>(&self, order: VisitOrder, init: T, mut f: F) -> T
+        where F: FnMut(T, &[usize], &Atom) -> ControlFlow<T, T>
+    {
+        let mut acc = Some(init);
+        self.visit(order, |path, atom| {
+            match f(acc.take().unwrap(), path, atom) {
+                ControlFlow::Continue(next) => {
+                    acc = Some(next);
+                    VisitResult::Continue
+                },
+                ControlFlow::Break(last) => {
+                    acc = Some(last);
+                    VisitResult::Stop
+                },
+            }
+        });
+        acc.unwrap()
+    }
+
+    /// Rewrites sub-atoms of the atom in the given order. Rewriting function
+    /// returns `Some(atom)` to replace the sub-atom or `None` to keep it
+    /// unchanged. In [VisitOrder::TopDown] traversal children of the
+    /// replaced atom are not visited, thus each position is rewritten once.
+    /// Returns `true` if atom was changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let mut atom = expr!("A" ("B" "A"));
+    /// atom.rewrite(VisitOrder::BottomUp, |_path, atom| {
+    ///     if *atom == expr!("A") { Some(expr!("C")) } else { None }
+    /// });
+    ///
+    /// assert_eq!(atom, expr!("C" ("B" "C")));
+    /// ```
+    pub fn rewrite<F>(&mut self, order: VisitOrder, mut f: F) -> bool
+        where F: FnMut(&[usize], &Atom) -> Option<Atom>
+    {
+        let mut changed = false;
+        self.visit_mut(order, |path, atom| {
+            match f(path, atom) {
+                Some(new) => {
+                    *atom = new;
+                    changed = true;
+                    VisitResult::SkipChildren
+                },
+                None => VisitResult::Continue,
+            }
+        });
+        changed
+    }
+
+    /// Rewrites atom bottom-up repeatedly until rewriting function doesn't
+    /// change it. Caller is responsible for making rewriting rules
+    /// terminating. Returns `true` if atom was changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let mut atom = expr!("S" ("S" ("S" "Z")));
+    /// atom.rewrite_fixpoint(|_path, atom| match atom {
+    ///     Atom::Expression(expr) if expr.children() == [expr!("S"), expr!("Z")].as_slice() => Some(expr!("Z")),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(atom, expr!("Z"));
+    /// ```
+    pub fn rewrite_fixpoint<F>(&mut self, mut f: F) -> bool
+        where F: FnMut(&[usize], &Atom) -> Option<Atom>
+    {
+        let mut changed = false;
+        while self.rewrite(VisitOrder::BottomUp, &mut f) {
+            changed = true;
+        }
+        changed
+    }
+}
+
+fn visit_rec<F>(atom: &Atom, order: VisitOrder, path: &mut Vec<usize>, visitor: &mut F) -> bool
+    where F: FnMut(&[usize], &Atom) -> VisitResult
+{
+    if order == VisitOrder::TopDown {
+        match visitor(path.as_slice(), atom) {
+            VisitResult::Stop => return false,
+            VisitResult::SkipChildren => return true,
+            VisitResult::Continue => {},
+        }
+    }
+    if let Atom::Expression(expr) = atom {
+        for (i, child) in expr.children().iter().enumerate() {
+            path.push(i);
+            let proceed = visit_rec(child, order, path, visitor);
+            path.pop();
+            if !proceed {
+                return false;
+            }
+        }
+    }
+    if order == VisitOrder::BottomUp {
+        if visitor(path.as_slice(), atom) == VisitResult::Stop {
+            return false;
+        }
+    }
+    true
+}
+
+fn visit_mut_rec<F>(atom: &mut Atom, order: VisitOrder, path: &mut Vec<usize>, visitor: &mut F) -> bool
+    where F: FnMut(&[usize], &mut Atom) -> VisitResult
+{
+    if order == VisitOrder::TopDown {
+        match visitor(path.as_slice(), atom) {
+            VisitResult::Stop => return false,
+            VisitResult::SkipChildren => return true,
+            VisitResult::Continue => {},
+        }
+    }
+    if let Atom::Expression(expr) = atom {
+        for (i, child) in expr.children_mut().iter_mut().enumerate() {
+            path.push(i);
+            let proceed = visit_mut_rec(child, order, path, visitor);
+            path.pop();
+            if !proceed {
+                return false;
+            }
+        }
+    }
+    if order == VisitOrder::BottomUp {
+        if visitor(path.as_slice(), atom) == VisitResult::Stop {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn paths(atom: &Atom, order: VisitOrder) -> Vec<(Vec<usize>, Atom)> {
+        let mut paths = Vec::new();
+        atom.visit(order, |path, atom| {
+            paths.push((path.to_vec(), atom.clone()));
+            VisitResult::Continue
+        });
+        paths
+    }
+
+    #[test]
+    fn visit_top_down() {
+        assert_eq!(paths(&expr!("A" ("B" x)), VisitOrder::TopDown), vec![
+            (vec![], expr!("A" ("B" x))),
+            (vec![0], expr!("A")),
+            (vec![1], expr!("B" x)),
+            (vec![1, 0], expr!("B")),
+            (vec![1, 1], expr!(x)),
+        ]);
+    }
+
+    #[test]
+    fn visit_bottom_up() {
+        assert_eq!(paths(&expr!("A" ("B" x)), VisitOrder::BottomUp), vec![
+            (vec![0], expr!("A")),
+            (vec![1, 0], expr!("B")),
+            (vec![1, 1], expr!(x)),
+            (vec![1], expr!("B" x)),
+            (vec![], expr!("A" ("B" x))),
+        ]);
+    }
+
+    #[test]
+    fn visit_skip_children_and_stop() {
+        let atom = expr!("A" ("B" x) ("C" y));
+        let mut visited = Vec::new();
+        let completed = atom.visit(VisitOrder::TopDown, |path, atom| {
+            visited.push(atom.clone());
+            match path {
+                [1] => VisitResult::SkipChildren,
+                [2, 0] => VisitResult::Stop,
+                _ => VisitResult::Continue,
+            }
+        });
+        assert!(!completed);
+        assert_eq!(visited, vec![expr!("A" ("B" x) ("C" y)), expr!("A"),
+            expr!("B" x), expr!("C" y), expr!("C")]);
+    }
+
+    #[test]
+    fn fold_short_circuit() {
+        let atom = expr!("A" x ("B" y));
+        let first_var = atom.fold(VisitOrder::TopDown, None, |found, path, atom| match atom {
+            Atom::Variable(_) => ControlFlow::Break(Some(path.to_vec())),
+            _ => ControlFlow::Continue(found),
+        });
+        assert_eq!(first_var, Some(vec![1]));
+    }
+
+    #[test]
+    fn visit_mut_replace() {
+        let mut atom = expr!("A" x ("B" x));
+        atom.visit_mut(VisitOrder::TopDown, |_path, atom| {
+            if *atom == expr!(x) {
+                *atom = expr!("X");
+            }
+            VisitResult::Continue
+        });
+        assert_eq!(atom, expr!("A" "X" ("B" "X")));
+    }
+
+    #[test]
+    fn rewrite_top_down_once_per_position() {
+        let mut atom = expr!("A" x);
+        let changed = atom.rewrite(VisitOrder::TopDown, |_path, atom| {
+            if *atom == expr!(x) { Some(expr!("f" x)) } else { None }
+        });
+        assert!(changed);
+        assert_eq!(atom, expr!("A" ("f" x)));
+    }
+
+    #[test]
+    fn rewrite_fixpoint_no_change() {
+        let mut atom = expr!("A" "B");
+        assert!(!atom.rewrite_fixpoint(|_path, _atom| None));
+        assert_eq!(atom, expr!("A" "B"));
+    }
+}
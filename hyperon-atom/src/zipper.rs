@@ -0,0 +1,510 @@
+//! Path-addressed access and structural edits of atoms. Path is a list of
+//! the child indexes starting from the root atom, the same as the one passed
+//! to the visitors of [Atom::visit]. Empty path addresses the root atom.
+//!
+//! [AtomZipper] keeps a focus inside the atom and allows moving it around
+//! and editing the atom at the focus. Moving the focus detaches the focused
+//! sub-atom from its parent instead of copying it, thus moving along the path
+//! of the allocated expressions takes O(depth) time and doesn't clone the
+//! siblings. There are two exceptions:
+//! - expression which keeps its children in a static literal (see
+//!   [CowArray::Literal]) is converted into an allocated one when focus
+//!   moves into it first time, it takes O(width) time; children of the
+//!   literal are literals as well thus they are cloned without allocation;
+//! - cloning the [AtomZipper] copies the focused atom only, the chain of
+//!   its parents is shared between the clones; the parent is copied when
+//!   one of the clones moves the focus up or to a sibling and the parent is
+//!   still shared, it takes time proportional to the size of the parent
+//!   without the detached child.
+
+use crate::*;
+use crate::matcher::match_atoms;
+
+use std::rc::Rc;
+
+/// Placeholder which is put in place of the detached sub-atom. Empty literal
+/// expression doesn't allocate memory.
+const HOLE: Atom = Atom::Expression(ExpressionAtom::new(CowArray::Literal(&[])));
+
+/// Parent expression with the focused child detached. Frames are linked
+/// from the nearest parent to the root and shared between the clones of the
+/// zipper.
+#[derive(Debug, Clone)]
+struct Frame {
+    parent: ExpressionAtom,
+    index: usize,
+    up: Option<Rc<Frame>>,
+}
+
+/// Zipper over an [Atom]. Keeps the focused sub-atom and the chain of its
+/// parent expressions with the focused child detached. Cloning the zipper
+/// copies the focused sub-atom only, parents are shared until they are
+/// modified.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::zipper::AtomZipper;
+///
+/// let mut zipper = AtomZipper::new(expr!("A" ("B" "C")));
+/// assert!(zipper.down(1));
+/// assert!(zipper.down(1));
+/// assert_eq!(zipper.focus(), &expr!("C"));
+/// assert_eq!(zipper.path(), vec![1, 1]);
+///
+/// zipper.replace(expr!("D"));
+/// assert!(zipper.left());
+/// assert!(!zipper.insert(0, expr!("E")));
+///
+/// assert_eq!(zipper.into_atom(), expr!("A" ("B" "D")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AtomZipper {
+    focus: Atom,
+    frames: Option<Rc<Frame>>,
+    depth: usize,
+}
+
+impl AtomZipper {
+    /// Constructs new zipper focused on the root of the `atom`.
+    pub fn new(atom: Atom) -> Self {
+        Self{ focus: atom, frames: None, depth: 0 }
+    }
+
+    /// Returns the focused sub-atom.
+    pub fn focus(&self) -> &Atom {
+        &self.focus
+    }
+
+    /// Returns a mutable reference to the focused sub-atom.
+    pub fn focus_mut(&mut self) -> &mut Atom {
+        &mut self.focus
+    }
+
+    /// Returns the path of the focused sub-atom.
+    pub fn path(&self) -> Vec<usize> {
+        let mut path = vec![0; self.depth];
+        let mut frame = self.frames.as_deref();
+        for index in path.iter_mut().rev() {
+            let current = frame.unwrap();
+            *index = current.index;
+            frame = current.up.as_deref();
+        }
+        path
+    }
+
+    /// Returns the depth of the focus, root atom has zero depth.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns `true` if the root atom is focused.
+    pub fn is_root(&self) -> bool {
+        self.frames.is_none()
+    }
+
+    /// Moves focus to the child of the focused expression with the given
+    /// index. Returns `false` and keeps the focus when the focused atom is
+    /// not an expression or it has no such child.
+    pub fn down(&mut self, index: usize) -> bool {
+        match &self.focus {
+            Atom::Expression(expr) if index < expr.children().len() => {},
+            _ => return false,
+        }
+        let mut parent = match std::mem::replace(&mut self.focus, HOLE) {
+            Atom::Expression(expr) => expr,
+            _ => unreachable!(),
+        };
+        self.focus = std::mem::replace(&mut parent.children_mut()[index], HOLE);
+        self.frames = Some(Rc::new(Frame{ parent, index, up: self.frames.take() }));
+        self.depth += 1;
+        true
+    }
+
+    /// Removes the nearest frame from the chain and returns its parent
+    /// expression and the index of the focus. The frame is copied when it
+    /// is shared with a clone of the zipper.
+    fn pop_frame(&mut self) -> Option<(ExpressionAtom, usize)> {
+        let frame = self.frames.take()?;
+        let Frame{ parent, index, up } = Rc::try_unwrap(frame)
+            .unwrap_or_else(|frame| (*frame).clone());
+        self.frames = up;
+        self.depth -= 1;
+        Some((parent, index))
+    }
+
+    /// Moves focus to the parent expression. Returns `false` when the root
+    /// atom is focused.
+    pub fn up(&mut self) -> bool {
+        match self.pop_frame() {
+            Some((mut parent, index)) => {
+                parent.children_mut()[index] = std::mem::replace(&mut self.focus, HOLE);
+                self.focus = Atom::Expression(parent);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Moves focus to the previous sibling. Returns `false` and keeps the
+    /// focus when there is no previous sibling.
+    pub fn left(&mut self) -> bool {
+        match self.frames.as_deref() {
+            Some(&Frame{ index, .. }) if index > 0 => self.sibling(index - 1),
+            _ => false,
+        }
+    }
+
+    /// Moves focus to the next sibling. Returns `false` and keeps the focus
+    /// when there is no next sibling.
+    pub fn right(&mut self) -> bool {
+        match self.frames.as_deref() {
+            Some(Frame{ parent, index, .. }) if index + 1 < parent.children().len() => {
+                let next = index + 1;
+                self.sibling(next)
+            },
+            _ => false,
+        }
+    }
+
+    fn sibling(&mut self, index: usize) -> bool {
+        let frame = Rc::make_mut(self.frames.as_mut().unwrap());
+        let children = frame.parent.children_mut();
+        let sibling = std::mem::replace(&mut children[index], HOLE);
+        children[frame.index] = std::mem::replace(&mut self.focus, sibling);
+        frame.index = index;
+        true
+    }
+
+    /// Moves focus to the root atom.
+    pub fn root(&mut self) {
+        while self.up() {}
+    }
+
+    /// Moves focus to the sub-atom with the given path relative to the
+    /// currently focused atom. Returns `false` and keeps the focus when there
+    /// is no such sub-atom.
+    pub fn descend(&mut self, path: &[usize]) -> bool {
+        if self.focus.get_at(path).is_none() {
+            return false;
+        }
+        for &index in path {
+            self.down(index);
+        }
+        true
+    }
+
+    /// Moves focus to the sub-atom with the given absolute path. Returns
+    /// `false` and keeps the focus when there is no such sub-atom.
+    pub fn goto(&mut self, path: &[usize]) -> bool {
+        let current = self.path();
+        let common = current.iter().zip(path)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for _ in common..current.len() {
+            self.up();
+        }
+        if self.descend(&path[common..]) {
+            true
+        } else {
+            self.descend(&current[common..]);
+            false
+        }
+    }
+
+    /// Replaces the focused sub-atom by `atom` and returns the previous one.
+    pub fn replace(&mut self, atom: Atom) -> Atom {
+        std::mem::replace(&mut self.focus, atom)
+    }
+
+    /// Inserts `atom` as a child of the focused expression at the `index`
+    /// position shifting the following children to the right. Returns
+    /// `false` when the focused atom is not an expression or `index` is
+    /// greater than number of its children.
+    pub fn insert(&mut self, index: usize, atom: Atom) -> bool {
+        match &mut self.focus {
+            Atom::Expression(expr) if index <= expr.children().len() => {
+                expr.children_mut().insert(index, atom);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Removes the focused sub-atom from its parent expression and returns
+    /// it. The focus is moved to the parent expression. Returns `None` when
+    /// the root atom is focused.
+    pub fn remove(&mut self) -> Option<Atom> {
+        let (mut parent, index) = self.pop_frame()?;
+        parent.children_mut().remove(index);
+        Some(std::mem::replace(&mut self.focus, Atom::Expression(parent)))
+    }
+
+    /// Moves focus to the root and returns the root atom.
+    pub fn into_atom(mut self) -> Atom {
+        self.root();
+        self.focus
+    }
+}
+
+impl From<Atom> for AtomZipper {
+    fn from(atom: Atom) -> Self {
+        Self::new(atom)
+    }
+}
+
+impl Atom {
+    /// Returns the sub-atom with the given path or `None` if there is no
+    /// such sub-atom.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let atom = expr!("A" ("B" "C"));
+    ///
+    /// assert_eq!(atom.get_at(&[1, 0]), Some(&expr!("B")));
+    /// assert_eq!(atom.get_at(&[]), Some(&atom));
+    /// assert_eq!(atom.get_at(&[0, 0]), None);
+    /// ```
+    pub fn get_at(&self, path: &[usize]) -> Option<&Atom> {
+        path.iter().try_fold(self, |atom, &index| match atom {
+            Atom::Expression(expr) => expr.children().get(index),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the sub-atom with the given path or
+    /// `None` if there is no such sub-atom.
+    pub fn get_at_mut(&mut self, path: &[usize]) -> Option<&mut Atom> {
+        path.iter().try_fold(self, |atom, &index| match atom {
+            Atom::Expression(expr) => expr.children_mut().get_mut(index),
+            _ => None,
+        })
+    }
+
+    /// Replaces the sub-atom with the given path by `atom` and returns the
+    /// previous sub-atom. Returns `None` and keeps the atom unchanged if
+    /// there is no such sub-atom.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let mut atom = expr!("A" ("B" "C"));
+    ///
+    /// assert_eq!(atom.replace_at(&[1, 1], expr!("D")), Some(expr!("C")));
+    /// assert_eq!(atom, expr!("A" ("B" "D")));
+    /// ```
+    pub fn replace_at(&mut self, path: &[usize], atom: Atom) -> Option<Atom> {
+        self.get_at_mut(path).map(|sub| std::mem::replace(sub, atom))
+    }
+
+    /// Returns a copy of the atom with the sub-atom at the given path
+    /// replaced by `atom`. Unlike cloning the atom and calling
+    /// [Atom::replace_at] it doesn't copy the replaced sub-atom. Returns
+    /// `None` if there is no such sub-atom.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let atom = expr!("A" ("B" "C"));
+    ///
+    /// assert_eq!(atom.with_replaced_at(&[1, 1], expr!("D")), Some(expr!("A" ("B" "D"))));
+    /// assert_eq!(atom.with_replaced_at(&[0, 0], expr!("D")), None);
+    /// ```
+    pub fn with_replaced_at(&self, path: &[usize], atom: Atom) -> Option<Atom> {
+        let (&index, rest) = match path.split_first() {
+            Some(first) => first,
+            None => return Some(atom),
+        };
+        match self {
+            Atom::Expression(expr) if index < expr.children().len() => {
+                let mut child = Some(expr.children()[index].with_replaced_at(rest, atom)?);
+                let children: Vec<Atom> = expr.children().iter().enumerate()
+                    .map(|(i, sibling)| if i == index { child.take().unwrap() } else { sibling.clone() })
+                    .collect();
+                Some(Atom::expr(children))
+            },
+            _ => None,
+        }
+    }
+
+    /// Inserts `atom` so it has the given path after insertion. Last index
+    /// of the path can be equal to the number of children of the parent
+    /// expression to append a child. Returns `false` and keeps the atom
+    /// unchanged if there is no such parent expression or index is out of
+    /// bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let mut atom = expr!("A" ("B" "C"));
+    ///
+    /// assert!(atom.insert_at(&[1, 1], expr!("D")));
+    /// assert!(atom.insert_at(&[2], expr!("E")));
+    /// assert_eq!(atom, expr!("A" ("B" "D" "C") "E"));
+    /// ```
+    pub fn insert_at(&mut self, path: &[usize], atom: Atom) -> bool {
+        match path.split_last() {
+            Some((&index, parent)) => match self.get_at_mut(parent) {
+                Some(Atom::Expression(expr)) if index <= expr.children().len() => {
+                    expr.children_mut().insert(index, atom);
+                    true
+                },
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Removes the sub-atom with the given path from its parent expression
+    /// and returns it. Returns `None` and keeps the atom unchanged if there
+    /// is no such sub-atom or path is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let mut atom = expr!("A" ("B" "C"));
+    ///
+    /// assert_eq!(atom.remove_at(&[1, 0]), Some(expr!("B")));
+    /// assert_eq!(atom, expr!("A" ("C")));
+    /// ```
+    pub fn remove_at(&mut self, path: &[usize]) -> Option<Atom> {
+        let (&index, parent) = path.split_last()?;
+        match self.get_at_mut(parent) {
+            Some(Atom::Expression(expr)) if index < expr.children().len() =>
+                Some(expr.children_mut().remove(index)),
+            _ => None,
+        }
+    }
+
+    /// Returns paths of all sub-atoms which match the `pattern` in the
+    /// top-down order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    ///
+    /// let atom = expr!("A" ("B" "C") ("B" "D"));
+    ///
+    /// assert_eq!(atom.paths_matching(&expr!("B" x)), vec![vec![1], vec![2]]);
+    /// ```
+    pub fn paths_matching(&self, pattern: &Atom) -> Vec<Vec<usize>> {
+        let mut paths = Vec::new();
+        self.visit(VisitOrder::TopDown, |path, atom| {
+            if match_atoms(pattern, atom).next().is_some() {
+                paths.push(path.to_vec());
+            }
+            VisitResult::Continue
+        });
+        paths
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zipper_moves() {
+        let mut zipper = AtomZipper::new(expr!("A" ("B" "C") "D"));
+        assert!(!zipper.up());
+        assert!(!zipper.left());
+        assert!(zipper.down(1));
+        assert_eq!(zipper.focus(), &expr!("B" "C"));
+        assert!(zipper.right());
+        assert_eq!(zipper.focus(), &expr!("D"));
+        assert!(!zipper.right());
+        assert!(!zipper.down(0));
+        assert!(zipper.left());
+        assert!(zipper.left());
+        assert_eq!(zipper.focus(), &expr!("A"));
+        assert_eq!(zipper.path(), vec![0]);
+        assert!(zipper.up());
+        assert!(zipper.is_root());
+        assert_eq!(zipper.into_atom(), expr!("A" ("B" "C") "D"));
+    }
+
+    #[test]
+    fn zipper_goto() {
+        let mut zipper = AtomZipper::new(expr!("A" ("B" ("C" "D")) ("E" "F")));
+        assert!(zipper.goto(&[1, 1, 0]));
+        assert_eq!(zipper.focus(), &expr!("C"));
+        assert!(zipper.goto(&[2, 1]));
+        assert_eq!(zipper.focus(), &expr!("F"));
+        assert!(!zipper.goto(&[1, 3]));
+        assert_eq!(zipper.path(), vec![2, 1]);
+        assert!(zipper.goto(&[]));
+        assert!(zipper.is_root());
+    }
+
+    #[test]
+    fn zipper_edit() {
+        let mut zipper = AtomZipper::new(expr!("A" ("B" "C") "D"));
+        assert!(zipper.goto(&[1, 1]));
+        assert_eq!(zipper.replace(expr!("X")), expr!("C"));
+        assert!(zipper.up());
+        assert!(zipper.insert(2, expr!("Y")));
+        assert!(!zipper.insert(4, expr!("Z")));
+        assert!(zipper.goto(&[2]));
+        assert_eq!(zipper.remove(), Some(expr!("D")));
+        assert!(zipper.is_root());
+        assert_eq!(zipper.remove(), None);
+        assert_eq!(zipper.into_atom(), expr!("A" ("B" "X" "Y")));
+    }
+
+    #[test]
+    fn zipper_keeps_literal_expression() {
+        let children: &'static [Atom] = Box::leak(Box::new([expr!("A"), expr!("B" "C")]));
+        let atom = Atom::Expression(ExpressionAtom::new(CowArray::Literal(children)));
+        let mut zipper = AtomZipper::new(atom.clone());
+        assert!(zipper.goto(&[1, 0]));
+        zipper.replace(expr!("D"));
+        assert_eq!(zipper.into_atom(), expr!("A" ("D" "C")));
+        assert_eq!(atom, expr!("A" ("B" "C")));
+    }
+
+    #[test]
+    fn zipper_clone_is_independent() {
+        let mut zipper = AtomZipper::new(expr!("A" ("B" "C") "D"));
+        assert!(zipper.goto(&[1, 1]));
+        let mut clone = zipper.clone();
+        zipper.replace(expr!("X"));
+        assert!(clone.left());
+        clone.replace(expr!("Y"));
+        assert_eq!(clone.path(), vec![1, 0]);
+        assert_eq!(clone.depth(), 2);
+        assert_eq!(zipper.into_atom(), expr!("A" ("B" "X") "D"));
+        assert_eq!(clone.into_atom(), expr!("A" ("Y" "C") "D"));
+    }
+
+    #[test]
+    fn atom_path_edits_out_of_bounds() {
+        let mut atom = expr!("A" ("B" "C"));
+        assert_eq!(atom.replace_at(&[0, 0], expr!("X")), None);
+        assert!(!atom.insert_at(&[1, 3], expr!("X")));
+        assert!(!atom.insert_at(&[], expr!("X")));
+        assert_eq!(atom.remove_at(&[2]), None);
+        assert_eq!(atom.remove_at(&[]), None);
+        assert_eq!(atom, expr!("A" ("B" "C")));
+    }
+
+    #[test]
+    fn atom_paths_matching() {
+        let atom = expr!("A" ("A" x) ("B" ("A" "C")));
+        assert_eq!(atom.paths_matching(&expr!("A" y)), vec![vec![1], vec![2, 1]]);
+        assert_eq!(atom.paths_matching(&expr!("A")), vec![vec![0], vec![1, 0], vec![2, 1, 0]]);
+        assert!(atom.paths_matching(&expr!("D")).is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use hyperon_atom::*;
-use hyperon_macros::metta;
+use hyperon_atom::gnd::str::Str;
+use hyperon_atom::gnd::number::Number;
+use hyperon_macros::{metta, metta_op};
 
 #[test]
 fn macros_metta_symbol() {
@@ -22,3 +24,74 @@ fn macros_metta_expression() {
         Atom::var("a")
     ]));
 }
+
+#[metta_op(name = "str-len")]
+fn str_len(s: &str) -> i64 {
+    s.chars().count() as i64
+}
+
+#[test]
+fn macros_metta_op() {
+    let op = StrLenOp{};
+    assert_eq!(op.to_string(), "str-len");
+    assert_eq!(op.type_(), Atom::expr([Atom::sym("->"), Atom::sym("String"), Atom::sym("Number")]));
+    assert_eq!(op.execute(&[Atom::gnd(Str::from_str("abc"))]), Ok(vec![Atom::gnd(Number::Integer(3))]));
+    assert_eq!(op.execute(&[]), Err(ExecError::from("str-len expects 1 argument")));
+    assert_eq!(op.execute(&[Atom::sym("abc")]), Err(ExecError::from("str-len expects String as argument 1")));
+}
+
+#[test]
+fn macros_metta_interpolation() {
+    let name = Atom::sym("Bob");
+    let n = 42i64;
+    assert_eq!(metta!((add-atom &self (age {name} {n}))), Atom::expr([
+        Atom::sym("add-atom"),
+        Atom::sym("&self"),
+        Atom::expr([Atom::sym("age"), Atom::sym("Bob"), Atom::gnd(Number::Integer(42))]),
+    ]));
+    let s = String::from("text");
+    assert_eq!(metta!((A {s.clone()} {s.as_str()} {true} {1.5f64})), Atom::expr([
+        Atom::sym("A"),
+        Atom::gnd(Str::from_str("text")),
+        Atom::gnd(Str::from_str("text")),
+        Atom::gnd(hyperon_atom::gnd::bool::Bool(true)),
+        Atom::gnd(Number::Float(1.5)),
+    ]));
+}
+
+#[test]
+fn macros_metta_interpolation_numbers() {
+    let (a, b, c, d) = (1usize, -2i32, 3u8, 0.5f32);
+    assert_eq!(metta!((A {a} {b} {c} {d} {4} {5.5})), Atom::expr([
+        Atom::sym("A"),
+        Atom::gnd(Number::Integer(1)),
+        Atom::gnd(Number::Integer(-2)),
+        Atom::gnd(Number::Integer(3)),
+        Atom::gnd(Number::Float(0.5)),
+        Atom::gnd(Number::Integer(4)),
+        Atom::gnd(Number::Float(5.5)),
+    ]));
+}
+
+#[test]
+#[should_panic(expected = "Integer is out of range of MeTTa Number")]
+fn macros_metta_interpolation_integer_out_of_range() {
+    let n = u64::MAX;
+    let _ = metta!((A {n}));
+}
+
+#[test]
+fn macros_metta_grounded_literals() {
+    assert_eq!(metta!{(A 1 -2 "a\"b")}, Atom::expr([
+        Atom::sym("A"),
+        Atom::gnd(Number::Integer(1)),
+        Atom::gnd(Number::Integer(-2)),
+        Atom::gnd(Str::from_str("a\"b")),
+    ]));
+}
+
+#[test]
+fn macros_metta_compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/metta_*.rs");
+}
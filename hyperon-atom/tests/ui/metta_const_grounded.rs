@@ -0,0 +1,5 @@
+use hyperon_macros::metta_const;
+
+fn main() {
+    let _ = metta_const!((A "text"));
+}
@@ -0,0 +1,5 @@
+use hyperon_macros::metta;
+
+fn main() {
+    let _ = metta!((A 9223372036854775808));
+}
@@ -13,6 +13,27 @@ pub fn print_stream(input: TokenStream) -> TokenStream {
 /// strings, numbers and booleans. For other grounded symbols use braces.
 /// Macros has a performance penalty because it creates and uses an additional
 /// wrapper for grounded atoms.
+///
+/// Braces can contain any Rust expression which value is inserted into
+/// the resulting atom. [hyperon_atom::Atom] values are inserted as is, Rust
+/// primitive integers and floats, `bool`, `String` and `&str` values are
+/// converted into MeTTa numbers, booleans and strings, other values are
+/// wrapped into grounded atoms. Integer which doesn't fit into `i64` causes
+/// panic at runtime.
+///
+/// Macro input should contain exactly one atom, empty input or multiple
+/// top-level atoms like `metta!(A B)` are rejected with "Atom is expected"
+/// and "Single atom is expected" errors respectively. Use `metta!((A B))` to
+/// construct an expression. Syntax errors are reported at compile time and
+/// point to the incorrect part of the input.
+///
+/// # Examples
+///
+/// ```ignore
+/// let name = Atom::sym("Bob");
+/// let n = 42i64;
+/// let atom = metta!((add-atom &self (age {name} {n})));
+/// ```
 #[proc_macro]
 pub fn metta(input: TokenStream) -> TokenStream {
     MettaConverter::new(input, PrinterMut::default()).run()
@@ -28,10 +49,303 @@ pub fn metta_const(input: TokenStream) -> TokenStream {
     MettaConverter::new(input, PrinterConst::default()).run()
 }
 
+/// Generates a grounded operation from a plain Rust function. The function
+/// itself is left untouched. In addition the macro generates a structure
+/// named after the function in camel case with the `Op` suffix (for example
+/// `str_len` gives `StrLenOp`) and implements [hyperon_atom::Grounded],
+/// [hyperon_atom::CustomExecute] and [hyperon_atom::gnd::op::GroundedOp]
+/// for it.
+///
+/// The type of the operation is built from the types of the arguments and
+/// the returned value using [hyperon_atom::gnd::op::MettaType]. Each argument
+/// is extracted from the atom using `TryFrom<&Atom>`; `&str` arguments are
+/// extracted via [hyperon_atom::gnd::str::Str]. The returned value is
+/// converted into the results using [hyperon_atom::gnd::op::IntoOpResult].
+/// Incorrect number of arguments or incorrect argument type are reported as
+/// [hyperon_atom::ExecError::Runtime] errors.
+///
+/// The name of the MeTTa token can be passed as `name = "..."`, by default
+/// it is the name of the function with underscores replaced by dashes.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[metta_op(name = "str-len")]
+/// fn str_len(s: &str) -> i64 {
+///     s.chars().count() as i64
+/// }
+///
+/// tokenizer.register_op::<StrLenOp>();
+/// ```
+#[proc_macro_attribute]
+pub fn metta_op(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut output = item.clone();
+    match OpFunction::parse(attr, item) {
+        Ok(op) => output.extend(op.generate()),
+        Err(err) => output.extend(err.to_compile_error()),
+    }
+    output
+}
+
+/// Error which is reported at compile time pointing to the span of the
+/// macro input.
+struct MacroError {
+    span: Span,
+    message: String,
+}
+
+impl MacroError {
+    fn new<S: Into<String>>(span: Span, message: S) -> Self {
+        Self{ span, message: message.into() }
+    }
+
+    fn to_compile_error(&self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let tokens: Vec<TokenTree> = vec![
+            Punct::new(':', Spacing::Joint).into(),
+            Punct::new(':', Spacing::Alone).into(),
+            Ident::new("core", self.span).into(),
+            Punct::new(':', Spacing::Joint).into(),
+            Punct::new(':', Spacing::Alone).into(),
+            Ident::new("compile_error", self.span).into(),
+            Punct::new('!', Spacing::Alone).into(),
+            Group::new(Delimiter::Brace, TokenStream::from(TokenTree::Literal(message))).into(),
+        ];
+        tokens.into_iter().map(|mut tt| { tt.set_span(self.span); tt }).collect()
+    }
+}
+
+struct OpArgument {
+    typ: Vec<TokenTree>,
+}
+
+impl OpArgument {
+    fn type_str(&self) -> String {
+        TokenStream::from_iter(self.typ.iter().cloned()).to_string()
+    }
+
+    /// `&str` cannot be extracted using `TryFrom<&Atom>` because the string
+    /// is owned by the grounded atom, thus it is handled separately.
+    fn is_str_ref(&self) -> bool {
+        let mut tokens = self.typ.iter();
+        let is_ref = matches!(tokens.next(), Some(TokenTree::Punct(p)) if p.as_char() == '&');
+        let rest: Vec<String> = tokens
+            .filter(|tt| !matches!(tt, TokenTree::Punct(p) if p.as_char() == '\''))
+            .map(ToString::to_string).collect();
+        is_ref && rest.last().map_or(false, |last| last == "str") && rest.len() <= 2
+    }
+}
+
+struct OpFunction {
+    vis: TokenStream,
+    fn_name: Ident,
+    name: String,
+    args: Vec<OpArgument>,
+    ret: Option<Vec<TokenTree>>,
+}
+
+impl OpFunction {
+    fn parse(attr: TokenStream, item: TokenStream) -> Result<Self, MacroError> {
+        let name = Self::parse_attr(attr)?;
+        let mut tokens = item.into_iter().peekable();
+        let mut vis = TokenStream::new();
+        loop {
+            match tokens.next() {
+                Some(TokenTree::Punct(p)) if p.as_char() == '#' => { tokens.next(); },
+                Some(TokenTree::Ident(i)) if i.to_string() == "pub" => {
+                    vis.extend([TokenTree::Ident(i)]);
+                    if let Some(TokenTree::Group(g)) = tokens.peek() {
+                        if g.delimiter() == Delimiter::Parenthesis {
+                            vis.extend(tokens.next());
+                        }
+                    }
+                },
+                Some(TokenTree::Ident(i)) if i.to_string() == "fn" => break,
+                Some(tt) => return Err(MacroError::new(tt.span(), "metta_op expects a plain function")),
+                None => return Err(MacroError::new(Span::call_site(), "metta_op expects a function")),
+            }
+        }
+        let fn_name = match tokens.next() {
+            Some(TokenTree::Ident(i)) => i,
+            Some(tt) => return Err(MacroError::new(tt.span(), "function name is expected")),
+            None => return Err(MacroError::new(Span::call_site(), "function name is expected")),
+        };
+        let args = match tokens.next() {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => Self::parse_args(g.stream())?,
+            Some(TokenTree::Punct(p)) if p.as_char() == '<' =>
+                return Err(MacroError::new(p.span(), "metta_op doesn't support generic functions")),
+            Some(tt) => return Err(MacroError::new(tt.span(), "function arguments are expected")),
+            None => return Err(MacroError::new(Span::call_site(), "function arguments are expected")),
+        };
+        let ret = match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '-' => {
+                tokens.next();
+                let mut ret = Vec::new();
+                loop {
+                    match tokens.next() {
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => break,
+                        Some(TokenTree::Ident(i)) if i.to_string() == "where" =>
+                            return Err(MacroError::new(i.span(), "metta_op doesn't support where clauses")),
+                        Some(tt) => ret.push(tt),
+                        None => return Err(MacroError::new(Span::call_site(), "function body is expected")),
+                    }
+                }
+                Some(ret)
+            },
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => None,
+            Some(tt) => return Err(MacroError::new(tt.span(), "function body is expected")),
+            None => return Err(MacroError::new(Span::call_site(), "function body is expected")),
+        };
+        let name = name.unwrap_or_else(|| fn_name.to_string().replace('_', "-"));
+        Ok(Self{ vis, fn_name, name, args, ret })
+    }
+
+    fn parse_attr(attr: TokenStream) -> Result<Option<String>, MacroError> {
+        let mut tokens = attr.into_iter();
+        match (tokens.next(), tokens.next(), tokens.next()) {
+            (None, _, _) => Ok(None),
+            (Some(TokenTree::Ident(key)), Some(TokenTree::Punct(eq)), Some(TokenTree::Literal(lit)))
+                if key.to_string() == "name" && eq.as_char() == '=' => {
+                let name = litrs::StringLit::parse(lit.to_string())
+                    .map_err(|_| MacroError::new(lit.span(), "string literal is expected"))?;
+                match tokens.next() {
+                    None => Ok(Some(name.value().to_string())),
+                    Some(tt) => Err(MacroError::new(tt.span(), "unexpected token")),
+                }
+            },
+            (Some(tt), _, _) => Err(MacroError::new(tt.span(), "metta_op expects name = \"<token>\" argument")),
+        }
+    }
+
+    fn parse_args(input: TokenStream) -> Result<Vec<OpArgument>, MacroError> {
+        let mut args = Vec::new();
+        let mut arg = Vec::new();
+        let mut depth = 0;
+        let mut prev_minus = false;
+        for tt in input.into_iter().chain(std::iter::once(Punct::new(',', Spacing::Alone).into())) {
+            match &tt {
+                TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                TokenTree::Punct(p) if p.as_char() == '>' && !prev_minus => depth -= 1,
+                TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                    if !arg.is_empty() {
+                        args.push(Self::parse_arg(std::mem::take(&mut arg))?);
+                    }
+                    prev_minus = false;
+                    continue;
+                },
+                _ => {},
+            }
+            prev_minus = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '-');
+            arg.push(tt);
+        }
+        Ok(args)
+    }
+
+    fn parse_arg(mut arg: Vec<TokenTree>) -> Result<OpArgument, MacroError> {
+        if matches!(arg.first(), Some(TokenTree::Ident(i)) if i.to_string() == "mut") {
+            arg.remove(0);
+        }
+        if let Some(TokenTree::Ident(i)) = arg.first() {
+            if i.to_string() == "self" {
+                return Err(MacroError::new(i.span(), "metta_op doesn't support methods"));
+            }
+        }
+        let is_named = matches!(arg.first(), Some(TokenTree::Ident(_)))
+            && matches!(arg.get(1), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+        if is_named && arg.len() > 2 {
+            Ok(OpArgument{ typ: arg.split_off(2) })
+        } else {
+            Err(MacroError::new(arg[0].span(), "argument is expected in format <name>: <type>"))
+        }
+    }
+
+    fn op_name(&self) -> String {
+        let camel: String = self.fn_name.to_string().split('_')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut chars = part.chars();
+                chars.next().map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+            }).collect();
+        camel + "Op"
+    }
+
+    fn generate(&self) -> TokenStream {
+        let op = self.op_name();
+        let vis = self.vis.to_string();
+        let name = &self.name;
+        let ret = self.ret.as_ref()
+            .map_or("()".to_string(), |ret| TokenStream::from_iter(ret.iter().cloned()).to_string());
+        let n = self.args.len();
+
+        let mut types = String::new();
+        let mut extract = String::new();
+        let mut call = Vec::new();
+        for (i, arg) in self.args.iter().enumerate() {
+            let typ = arg.type_str();
+            types.push_str(&format!("<{typ} as ::hyperon_atom::gnd::op::MettaType>::metta_type(), "));
+            let from = if arg.is_str_ref() { "::hyperon_atom::gnd::str::Str".to_string() } else { typ.clone() };
+            extract.push_str(&format!("
+                let __arg{i}: {from} = <{from} as ::core::convert::TryFrom<&::hyperon_atom::Atom>>::try_from(&args[{i}])
+                    .map_err(|_| ::hyperon_atom::ExecError::from(::std::format!(\"{{}} expects {{}} as argument {}\",
+                        {name:?}, <{typ} as ::hyperon_atom::gnd::op::MettaType>::metta_type())))?;", i + 1));
+            if arg.is_str_ref() {
+                extract.push_str(&format!("
+                let __arg{i}: &str = __arg{i}.as_str();"));
+            }
+            call.push(format!("__arg{i}"));
+        }
+        let arity_error = format!("{name} expects {n} argument{}", if n == 1 { "" } else { "s" });
+
+        let code = format!("
+            #[derive(Clone, Debug, Default)]
+            {vis} struct {op} {{}}
+
+            impl ::core::cmp::PartialEq for {op} {{
+                fn eq(&self, _other: &Self) -> bool {{
+                    true
+                }}
+            }}
+
+            impl ::std::fmt::Display for {op} {{
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{
+                    f.write_str(<Self as ::hyperon_atom::gnd::op::GroundedOp>::NAME)
+                }}
+            }}
+
+            impl ::hyperon_atom::gnd::op::GroundedOp for {op} {{
+                const NAME: &'static str = {name:?};
+            }}
+
+            impl ::hyperon_atom::Grounded for {op} {{
+                fn type_(&self) -> ::hyperon_atom::Atom {{
+                    ::hyperon_atom::Atom::expr([::hyperon_atom::Atom::sym(\"->\"), {types}
+                        <{ret} as ::hyperon_atom::gnd::op::MettaType>::metta_type()])
+                }}
+
+                fn as_execute(&self) -> ::core::option::Option<&dyn ::hyperon_atom::CustomExecute> {{
+                    ::core::option::Option::Some(self)
+                }}
+            }}
+
+            impl ::hyperon_atom::CustomExecute for {op} {{
+                fn execute(&self, args: &[::hyperon_atom::Atom]) -> ::core::result::Result<::std::vec::Vec<::hyperon_atom::Atom>, ::hyperon_atom::ExecError> {{
+                    if args.len() != {n} {{
+                        return ::core::result::Result::Err(::hyperon_atom::ExecError::from({arity_error:?}));
+                    }}
+                    {extract}
+                    ::hyperon_atom::gnd::op::IntoOpResult::into_op_result({fn_name}({call}))
+                }}
+            }}
+            ", fn_name = self.fn_name, call = call.join(", "));
+        code.parse().expect("Failed to parse generated code")
+    }
+}
+
 #[derive(Debug)]
 enum InternalToken {
-    ExprStart((usize, usize), (usize, usize)),
-    ExprEnd((usize, usize), (usize, usize)),
+    ExprStart((usize, usize), (usize, usize), Span),
+    ExprEnd((usize, usize), (usize, usize), Span),
     TokenTree(TokenTree),
     Space,
 }
@@ -41,20 +355,20 @@ impl InternalToken {
         (span.line(), span.column())
     }
 
-    fn start_expr(tt: &TokenTree) -> Self {
+    fn start_expr(tt: &TokenTree, g: &Group) -> Self {
         let (l, c) = Self::range(tt.span().start());
-        Self::ExprStart((l, c), (l, c+1))
+        Self::ExprStart((l, c), (l, c+1), g.span_open())
     }
 
-    fn end_expr(tt: &TokenTree) -> Self {
+    fn end_expr(tt: &TokenTree, g: &Group) -> Self {
         let (l, c) = Self::range(tt.span().start());
-        Self::ExprEnd((l, c), (l, c+1))
+        Self::ExprEnd((l, c), (l, c+1), g.span_close())
     }
 
     fn start(&self) -> (usize, usize) {
         match self {
-            Self::ExprStart(s, _) => *s,
-            Self::ExprEnd(s, _) => *s,
+            Self::ExprStart(s, _, _) => *s,
+            Self::ExprEnd(s, _, _) => *s,
             Self::TokenTree(tt) => Self::range(tt.span().start()),
             Self::Space => unreachable!(),
         }
@@ -62,8 +376,8 @@ impl InternalToken {
 
     fn end(&self) -> (usize, usize) {
         match self {
-            Self::ExprStart(_, e) => *e,
-            Self::ExprEnd(_, e) => *e,
+            Self::ExprStart(_, e, _) => *e,
+            Self::ExprEnd(_, e, _) => *e,
             Self::TokenTree(tt) => Self::range(tt.span().end()),
             Self::Space => unreachable!(),
         }
@@ -73,10 +387,10 @@ impl InternalToken {
 #[derive(Debug)]
 enum TokenizerState {
     Start,
-    Symbol(String),
-    Variable(String),
-    Sign(String),
-    Gnd(String, GndType),
+    Symbol(String, Span),
+    Variable(String, Span),
+    Sign(String, Span),
+    Gnd(String, GndType, Span),
     Token(InternalToken),
 }
 
@@ -140,8 +454,8 @@ impl Tokenizer {
     fn unroll_group(tt: TokenTree) -> Box<dyn Iterator<Item=InternalToken>> {
         match &tt {
             TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
-                let open = std::iter::once(InternalToken::start_expr(&tt));
-                let close = std::iter::once(InternalToken::end_expr(&tt));
+                let open = std::iter::once(InternalToken::start_expr(&tt, g));
+                let close = std::iter::once(InternalToken::end_expr(&tt, g));
                 Box::new(open.chain(g.stream().into_iter().flat_map(Self::unroll_group)).chain(close))
             },
             _ => {
@@ -150,7 +464,12 @@ impl Tokenizer {
         }
     }
 
-    fn next(&mut self) -> Token {
+    fn parse_literal(s: &str, span: Span) -> Result<litrs::Literal<String>, MacroError> {
+        litrs::Literal::parse(s.to_string())
+            .map_err(|e| MacroError::new(span, format!("Failed to parse literal: {}", e)))
+    }
+
+    fn next(&mut self) -> Result<(Token, Span), MacroError> {
         type TS = TokenizerState;
         type IT = InternalToken;
         type T = Token;
@@ -160,71 +479,77 @@ impl Tokenizer {
                 state => (state, self.input.next()),
             };
             let (token, state) = match (state, it) {
-                (TS::Start, None) => return T::End,
-                (TS::Start, Some(IT::ExprStart(_, _))) => (Some(T::ExprStart), TS::Start),
-                (TS::Start, Some(IT::ExprEnd(_, _))) => (Some(T::ExprEnd), TS::Start),
+                (TS::Start, None) => return Ok((T::End, Span::call_site())),
+                (TS::Start, Some(IT::ExprStart(_, _, span))) => (Some((T::ExprStart, span)), TS::Start),
+                (TS::Start, Some(IT::ExprEnd(_, _, span))) => (Some((T::ExprEnd, span)), TS::Start),
                 (TS::Start, Some(IT::TokenTree(tt))) => {
+                    let span = tt.span();
                     match tt {
                         TokenTree::Literal(l) => {
                             let s = l.to_string();
-                            let lit = litrs::Literal::parse(s.clone()).expect("Failed to parse literal");
-                            match lit {
-                                litrs::Literal::Integer(_) => (None, TS::Gnd(s, GndType::Int)),
-                                litrs::Literal::Float(_) => (None, TS::Gnd(s, GndType::Float)),
-                                litrs::Literal::String(_) => (None, TS::Gnd(s, GndType::Str)),
-                                _ => (None, TS::Symbol(s)), 
+                            match Self::parse_literal(&s, span)? {
+                                litrs::Literal::Integer(_) => (None, TS::Gnd(s, GndType::Int, span)),
+                                litrs::Literal::Float(_) => (None, TS::Gnd(s, GndType::Float, span)),
+                                litrs::Literal::String(_) => (None, TS::Gnd(s, GndType::Str, span)),
+                                _ => (None, TS::Symbol(s, span)), 
                             }
                         },
                         TokenTree::Ident(i) => {
                             let s = i.to_string();
                             if s == "True" || s == "False" {
-                                (None, TS::Gnd(s, GndType::Bool))
+                                (None, TS::Gnd(s, GndType::Bool, span))
                             } else {
-                                (None, TS::Symbol(s))
+                                (None, TS::Symbol(s, span))
                             }
                         },
                         TokenTree::Punct(p) if p.as_char() == '$' => {
-                            (None, TS::Variable(String::new()))
+                            (None, TS::Variable(String::new(), span))
                         },
                         TokenTree::Punct(p)
                             if p.as_char() == '+' || p.as_char() == '-' =>
-                                (None, TS::Sign(p.to_string())),
+                                (None, TS::Sign(p.to_string(), span)),
                         TokenTree::Group(g)
-                            if g.delimiter() == Delimiter::Brace =>
-                                (Some(T::Gnd(g)), TS::Start),
-                        tt => (None, TS::Symbol(tt.to_string())), 
+                            if g.delimiter() == Delimiter::Brace => {
+                                if g.stream().is_empty() {
+                                    return Err(MacroError::new(span, "Rust expression is expected inside braces"));
+                                }
+                                (Some((T::Gnd(g), span)), TS::Start)
+                            },
+                        tt => (None, TS::Symbol(tt.to_string(), span)), 
                     }
                 }
 
-                (TS::Sign(s), Some(IT::TokenTree(tt))) => {
+                (TS::Sign(s, span), Some(IT::TokenTree(tt))) => {
                     match &tt {
                         TokenTree::Literal(l) => {
                             let l = l.to_string();
-                            let lit = litrs::Literal::parse(l.clone()).expect("Failed to parse literal");
+                            let lit = Self::parse_literal(&l, tt.span())?;
                             let s = s + l.as_str();
                             match lit {
-                                litrs::Literal::Integer(_) => (None, TS::Gnd(s, GndType::Int)),
-                                litrs::Literal::Float(_) => (None, TS::Gnd(s, GndType::Float)),
-                                _ => (None, TS::Symbol(s)), 
+                                litrs::Literal::Integer(_) => (None, TS::Gnd(s, GndType::Int, span)),
+                                litrs::Literal::Float(_) => (None, TS::Gnd(s, GndType::Float, span)),
+                                _ => (None, TS::Symbol(s, span)), 
                             }
                         },
-                        _ => (None, TS::Symbol(s + tt.to_string().as_str())), 
+                        _ => (None, TS::Symbol(s + tt.to_string().as_str(), span)), 
                     }
                 }
-                (TS::Sign(s), Some(t)) => (Some(T::Symbol(s)), TS::Token(t)),
-                (TS::Sign(s), None) => (Some(T::Symbol(s)), TS::Start),
+                (TS::Sign(s, span), Some(t)) => (Some((T::Symbol(s), span)), TS::Token(t)),
+                (TS::Sign(s, span), None) => (Some((T::Symbol(s), span)), TS::Start),
 
-                (TS::Gnd(s, _), Some(IT::TokenTree(tt))) => (None, TS::Symbol(s + tt.to_string().as_str())),
-                (TS::Gnd(s, typ), Some(token)) => (Some(Self::gnd_to_token(s, typ)), TS::Token(token)),
-                (TS::Gnd(s, typ), None) => (Some(Self::gnd_to_token(s, typ)), TS::Start),
+                (TS::Gnd(s, _, span), Some(IT::TokenTree(tt))) => (None, TS::Symbol(s + tt.to_string().as_str(), span)),
+                (TS::Gnd(s, typ, span), Some(token)) => (Some((Self::gnd_to_token(s, typ), span)), TS::Token(token)),
+                (TS::Gnd(s, typ, span), None) => (Some((Self::gnd_to_token(s, typ), span)), TS::Start),
 
-                (TS::Symbol(s), Some(IT::TokenTree(tt))) => (None, TS::Symbol(s + tt.to_string().as_str())),
-                (TS::Symbol(s), Some(t)) => (Some(T::Symbol(s)), TS::Token(t)),
-                (TS::Symbol(s), None) => (Some(T::Symbol(s)), TS::Start),
+                (TS::Symbol(s, span), Some(IT::TokenTree(tt))) => (None, TS::Symbol(s + tt.to_string().as_str(), span)),
+                (TS::Symbol(s, span), Some(t)) => (Some((T::Symbol(s), span)), TS::Token(t)),
+                (TS::Symbol(s, span), None) => (Some((T::Symbol(s), span)), TS::Start),
 
-                (TS::Variable(s), Some(IT::TokenTree(tt))) => (None, TS::Variable(s + tt.to_string().as_str())),
-                (TS::Variable(s), Some(t)) => (Some(T::Variable(s)), TS::Token(t)),
-                (TS::Variable(s), None) => (Some(T::Variable(s)), TS::Start),
+                (TS::Variable(s, span), Some(IT::TokenTree(tt))) => (None, TS::Variable(s + tt.to_string().as_str(), span)),
+                (TS::Variable(s, span), _) if s.is_empty() =>
+                    return Err(MacroError::new(span, "Variable name is expected after $")),
+                (TS::Variable(s, span), Some(t)) => (Some((T::Variable(s), span)), TS::Token(t)),
+                (TS::Variable(s, span), None) => (Some((T::Variable(s), span)), TS::Start),
 
                 (TS::Start, Some(IT::Space)) => (None, TS::Start),
 
@@ -233,7 +558,7 @@ impl Tokenizer {
 
             self.state = state;
             if let Some(token) = token {
-                return token
+                return Ok(token)
             }
         }
     }
@@ -345,10 +670,18 @@ impl PrinterBase {
     }
 
     fn gnd(&mut self, g: Group) {
-        self.group('(').punct("&&").ident("hyperon_atom").punct("::").ident("Wrap").group('(')
+        self.group('{')
+            .punct("#").group('[').ident("allow").group('(').ident("unused_imports").group(')').group(']')
+            .ident("use").ident("hyperon_atom").punct("::").group('{')
+                .ident("AutoGroundedTypeToAtom").punct(",")
+                .ident("CustomGroundedTypeToAtom").punct(",")
+                .ident("metta_macro").punct("::").ident("MettaValueToAtom")
+            .group('}').punct(";")
+            .group('(').punct("&&&").ident("hyperon_atom").punct("::").ident("Wrap").group('(')
             .push(TokenTree::Group(Group::new(Delimiter::Parenthesis, g.stream())))
             .group(')').group(')')
-            .punct(".").ident("to_atom").group('(').group(')');
+            .punct(".").ident("to_atom").group('(').group(')')
+            .group('}');
     }
 
     fn expr_delimiter(&mut self) {
@@ -357,6 +690,7 @@ impl PrinterBase {
 }
 
 trait Printer {
+    fn supports_grounded(&self) -> bool { true }
     fn symbol(&mut self, name: &str);
     fn variable(&mut self, name: &str);
     fn bool(&mut self, b: &str);
@@ -421,6 +755,8 @@ struct PrinterConst {
 }
 
 impl Printer for PrinterConst {
+    fn supports_grounded(&self) -> bool { false }
+
     fn symbol(&mut self, name: &str) {
         self.base.ident("hyperon_atom").punct("::").ident("Atom").punct("::").ident("Symbol").group('(')
             .ident("hyperon_atom").punct("::").ident("SymbolAtom").punct("::").ident("new").group('(')
@@ -470,6 +806,7 @@ struct MettaConverter<P: Printer> {
     state: State,
     input: Tokenizer,
     output: P,
+    atoms: usize,
 }
 
 impl<P: Printer> MettaConverter<P> {
@@ -478,28 +815,51 @@ impl<P: Printer> MettaConverter<P> {
             state: State::Start,
             input: Tokenizer::new(input),
             output,
+            atoms: 0,
         }
     }
 
     fn run(&mut self) -> TokenStream {
+        match self.convert() {
+            Ok(()) => self.output.get_token_stream(),
+            Err(err) => err.to_compile_error(),
+        }
+    }
+
+    fn convert(&mut self) -> Result<(), MacroError> {
         loop {
             if self.state == State::Final {
                 break
             }
-            self.next_state();
+            self.next_state()?;
         }
-        self.output.get_token_stream()
+        Ok(())
     }
 
-    fn next_state(&mut self) {
-        let token = self.input.next();
+    fn next_state(&mut self) -> Result<(), MacroError> {
+        let (token, span) = self.input.next()?;
 
         if matches!(token, Token::End) {
             if !matches!(self.state, State::Start) {
-                panic!("Unexpected expression end");
+                return Err(MacroError::new(span, "Unexpected expression end"));
+            }
+            if self.atoms == 0 {
+                return Err(MacroError::new(span, "Atom is expected"));
             }
             self.state = State::Final;
-            return;
+            return Ok(());
+        }
+
+        if matches!(self.state, State::Start) {
+            if self.atoms > 0 {
+                return Err(MacroError::new(span, "Single atom is expected"));
+            }
+            self.atoms += 1;
+        }
+
+        if matches!(token, Token::Int(_) | Token::Float(_) | Token::Str(_) | Token::Bool(_) | Token::Gnd(_))
+            && !self.output.supports_grounded() {
+                return Err(MacroError::new(span, "Grounded atoms cannot be instantiated as const"));
         }
 
         if matches!(self.state, State::Expression(_))
@@ -512,9 +872,21 @@ impl<P: Printer> MettaConverter<P> {
         match token {
             Token::Symbol(s) => self.output.symbol(&s),
             Token::Variable(v) => self.output.variable(&v),
-            Token::Int(s) => self.output.integer(s.parse::<i64>().unwrap()),
-            Token::Float(s) => self.output.float(s.parse::<f64>().unwrap()),
-            Token::Str(s) => self.output.str(&s[1..s.len() - 1]),
+            Token::Int(s) => {
+                let n = s.parse::<i64>()
+                    .map_err(|e| MacroError::new(span, format!("Could not parse integer: '{}', {}", s, e)))?;
+                self.output.integer(n)
+            },
+            Token::Float(s) => {
+                let f = s.parse::<f64>()
+                    .map_err(|e| MacroError::new(span, format!("Could not parse float: '{}', {}", s, e)))?;
+                self.output.float(f)
+            },
+            Token::Str(s) => {
+                let lit = litrs::StringLit::parse(s.clone())
+                    .map_err(|e| MacroError::new(span, format!("Failed to parse literal: {}", e)))?;
+                self.output.str(lit.value())
+            },
             Token::Bool(s) => self.output.bool(&s),
             Token::Gnd(g) => self.output.gnd(g),
 
@@ -529,7 +901,7 @@ impl<P: Printer> MettaConverter<P> {
             },
             Token::ExprEnd => {
                 next_state = match self.state {
-                    State::Start => panic!("Unexpected end of expression"),
+                    State::Start => return Err(MacroError::new(span, "Unexpected end of expression")),
                     State::ExprStart(1) => State::Start,
                     State::ExprStart(n) => State::Expression(n - 1),
                     State::Expression(1) => State::Start,
@@ -548,5 +920,6 @@ impl<P: Printer> MettaConverter<P> {
             }
         }
         self.state = next_state;
+        Ok(())
     }
 }
@@ -66,5 +66,52 @@ fn fill(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, fill);
+fn edges(secondary: bool) -> AtomIndex<NoDuplication> {
+    let mut idx = AtomIndex::<NoDuplication>::new();
+    if secondary {
+        idx.add_secondary_index(1);
+        idx.add_secondary_index(2);
+    }
+    DataGenerator::new(2, 'a'..'z')
+        .map(|pair| Atom::expr([Atom::sym("Edge"), pair, Atom::sym("b")]))
+        .for_each(|atom| idx.insert(atom));
+    DataGenerator::new(2, 'a'..'z')
+        .for_each(|pair| idx.insert(Atom::expr([Atom::sym("Edge"), Atom::sym("a"), pair])));
+    idx
+}
+
+fn query_by_position(c: &mut Criterion) {
+    let query = expr!("Edge" x ("c" "d"));
+    for (name, secondary) in [("query by last element", false), ("query by last element secondary index", true)] {
+        let idx = edges(secondary);
+        c.bench_function(name, |b| b.iter(|| {
+            assert_eq!(idx.query(&query).count(), 1);
+        }));
+    }
+    let query = expr!(r ("c" "d") "b");
+    for (name, secondary) in [("query by middle element", false), ("query by middle element secondary index", true)] {
+        let idx = edges(secondary);
+        c.bench_function(name, |b| b.iter(|| {
+            assert_eq!(idx.query(&query).count(), 1);
+        }));
+    }
+}
+
+fn insert_with_secondary_index(c: &mut Criterion) {
+    c.bench_function("fill 100 secondary index", |b| {
+        b.iter_batched(|| {
+            let gen = DataGenerator::new(2, 'a'..'k');
+            let mut idx = AtomIndex::<NoDuplication>::new();
+            idx.add_secondary_index(1);
+            (gen, idx)
+        },
+        |(gen, mut idx)| {
+            gen.for_each(|a| idx.insert(a));
+            idx
+        },
+        BatchSize::SmallInput)
+    });
+}
+
+criterion_group!(benches, fill, query_by_position, insert_with_secondary_index);
 criterion_main!(benches);
@@ -0,0 +1,243 @@
+//! Aggregation of the query results. [Aggregate] consumes the query
+//! results one by one and keeps only the state of the aggregate, thus the
+//! results are not collected by the aggregation itself. Spaces can compute
+//! aggregates using their indexes, see
+//! [Space::aggregate](crate::Space::aggregate).
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::Bindings;
+use hyperon_atom::gnd::number::Number;
+
+use std::collections::{HashMap, HashSet};
+
+/// Aggregate function over the query results.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aggregate {
+    /// Number of results.
+    Count,
+    /// Sum of the numeric values of the variable.
+    Sum(VariableAtom),
+    /// Minimal numeric value of the variable.
+    Min(VariableAtom),
+    /// Maximal numeric value of the variable.
+    Max(VariableAtom),
+    /// Expression of the distinct values of the variable in order of their
+    /// first appearance.
+    Distinct(VariableAtom),
+}
+
+impl Aggregate {
+    /// Constructs aggregate from its MeTTa representation: `count`,
+    /// `(sum $x)`, `(min $x)`, `(max $x)` or `(distinct $x)`. Functions are
+    /// matched by name because some of the names can be parsed as grounded
+    /// operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_space::aggregate::Aggregate;
+    ///
+    /// assert_eq!(Aggregate::from_atom(&sym!("count")), Ok(Aggregate::Count));
+    /// assert_eq!(Aggregate::from_atom(&expr!("sum" x)), Ok(Aggregate::Sum(VariableAtom::new("x"))));
+    /// assert!(Aggregate::from_atom(&expr!("sum" "x")).is_err());
+    /// ```
+    pub fn from_atom(atom: &Atom) -> Result<Self, String> {
+        let error = || format!("Unexpected aggregate: {}, expected count, (sum $x), (min $x), (max $x) or (distinct $x)", atom);
+        match atom {
+            Atom::Symbol(_) | Atom::Grounded(_) if atom.to_string() == "count" => Ok(Self::Count),
+            Atom::Expression(expr) => match expr.children().as_slice() {
+                [op] if op.to_string() == "count" => Ok(Self::Count),
+                [op, Atom::Variable(var)] => match op.to_string().as_str() {
+                    "sum" => Ok(Self::Sum(var.clone())),
+                    "min" => Ok(Self::Min(var.clone())),
+                    "max" => Ok(Self::Max(var.clone())),
+                    "distinct" => Ok(Self::Distinct(var.clone())),
+                    _ => Err(error()),
+                },
+                _ => Err(error()),
+            },
+            _ => Err(error()),
+        }
+    }
+
+    /// Computes aggregate over `results`. Results where variable is not
+    /// bound are counted but skipped by other aggregates. Returns `None` when
+    /// `min` or `max` has no values to compare.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_space::aggregate::Aggregate;
+    ///
+    /// let results = vec![bind!{x: expr!({Number::Integer(1)})}, bind!{x: expr!({Number::Integer(2)})}];
+    ///
+    /// assert_eq!(Aggregate::Sum(VariableAtom::new("x")).apply(results), Ok(Some(expr!({Number::Integer(3)}))));
+    /// ```
+    pub fn apply<I: IntoIterator<Item=Bindings>>(&self, results: I) -> Result<Option<Atom>, String> {
+        let mut acc = Accumulator::new(self);
+        for bindings in results {
+            acc.push(&bindings)?;
+        }
+        Ok(acc.finish())
+    }
+}
+
+/// Groups `results` by the value of the `key` and computes `aggregate` for
+/// each group. `key` is an atom which variables are replaced by their values.
+/// Returns pairs of key and aggregate value in order of the first appearance
+/// of the key. Groups without aggregate value are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::gnd::number::Number;
+/// use hyperon_space::aggregate::{Aggregate, group_by};
+///
+/// let results = vec![bind!{x: sym!("A")}, bind!{x: sym!("B")}, bind!{x: sym!("A")}];
+///
+/// assert_eq!(group_by(results, &expr!(x), &Aggregate::Count), Ok(vec![
+///     (sym!("A"), expr!({Number::Integer(2)})),
+///     (sym!("B"), expr!({Number::Integer(1)})),
+/// ]));
+/// ```
+pub fn group_by<I>(results: I, key: &Atom, aggregate: &Aggregate) -> Result<Vec<(Atom, Atom)>, String>
+    where I: IntoIterator<Item=Bindings>
+{
+    let mut groups: Vec<(Atom, Accumulator)> = Vec::new();
+    let mut positions: HashMap<Atom, usize> = HashMap::new();
+    for bindings in results {
+        let value = matcher::apply_bindings_to_atom_move(key.clone(), &bindings);
+        let position = *positions.entry(value).or_insert_with_key(|value| {
+            groups.push((value.clone(), Accumulator::new(aggregate)));
+            groups.len() - 1
+        });
+        groups[position].1.push(&bindings)?;
+    }
+    Ok(groups.into_iter()
+        .filter_map(|(key, acc)| acc.finish().map(|value| (key, value)))
+        .collect())
+}
+
+/// State of the aggregate computation.
+enum Accumulator<'a> {
+    Count(usize),
+    Sum(&'a VariableAtom, Number),
+    Min(&'a VariableAtom, Option<(Number, Atom)>),
+    Max(&'a VariableAtom, Option<(Number, Atom)>),
+    Distinct(&'a VariableAtom, Vec<Atom>, HashSet<Atom>),
+}
+
+impl<'a> Accumulator<'a> {
+    fn new(aggregate: &'a Aggregate) -> Self {
+        match aggregate {
+            Aggregate::Count => Self::Count(0),
+            Aggregate::Sum(var) => Self::Sum(var, Number::Integer(0)),
+            Aggregate::Min(var) => Self::Min(var, None),
+            Aggregate::Max(var) => Self::Max(var, None),
+            Aggregate::Distinct(var) => Self::Distinct(var, Vec::new(), HashSet::new()),
+        }
+    }
+
+    fn push(&mut self, bindings: &Bindings) -> Result<(), String> {
+        match self {
+            Self::Count(count) => *count += 1,
+            Self::Sum(var, sum) => if let Some(value) = bindings.resolve(*var) {
+                *sum = add(sum.clone(), number(*var, &value)?)?;
+            },
+            Self::Min(var, min) => if let Some(value) = bindings.resolve(*var) {
+                let n = number(*var, &value)?;
+                if min.as_ref().map_or(true, |(min, _)| less(&n, min)) {
+                    *min = Some((n, value));
+                }
+            },
+            Self::Max(var, max) => if let Some(value) = bindings.resolve(*var) {
+                let n = number(*var, &value)?;
+                if max.as_ref().map_or(true, |(max, _)| less(max, &n)) {
+                    *max = Some((n, value));
+                }
+            },
+            Self::Distinct(var, values, seen) => if let Some(value) = bindings.resolve(*var) {
+                if seen.insert(value.clone()) {
+                    values.push(value);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Option<Atom> {
+        match self {
+            Self::Count(count) => Some(Atom::gnd(Number::Integer(count as i64))),
+            Self::Sum(_var, sum) => Some(Atom::gnd(sum)),
+            Self::Min(_var, min) => min.map(|(_n, atom)| atom),
+            Self::Max(_var, max) => max.map(|(_n, atom)| atom),
+            Self::Distinct(_var, values, _seen) => Some(Atom::expr(values)),
+        }
+    }
+}
+
+fn number(var: &VariableAtom, value: &Atom) -> Result<Number, String> {
+    Number::from_atom(value)
+        .ok_or_else(|| format!("Number is expected as a value of {}, found: {}", var, value))
+}
+
+fn add(a: Number, b: Number) -> Result<Number, String> {
+    match Number::promote(a, b) {
+        (Number::Integer(a), Number::Integer(b)) => a.checked_add(b)
+            .map(Number::Integer).ok_or_else(|| "Integer overflow".to_string()),
+        (Number::Float(a), Number::Float(b)) => Ok(Number::Float(a + b)),
+        _ => unreachable!(),
+    }
+}
+
+fn less(a: &Number, b: &Number) -> bool {
+    match Number::promote(a.clone(), b.clone()) {
+        (Number::Integer(a), Number::Integer(b)) => a < b,
+        (Number::Float(a), Number::Float(b)) => a < b,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn num(n: i64) -> Atom {
+        Atom::gnd(Number::Integer(n))
+    }
+
+    #[test]
+    fn aggregate_min_max_distinct() {
+        let results = || vec![bind!{x: num(3), y: sym!("A")}, bind!{x: num(1), y: sym!("B")},
+            bind!{x: Atom::gnd(Number::Float(2.5)), y: sym!("A")}, bind!{y: sym!("C")}];
+        let x = VariableAtom::new("x");
+
+        assert_eq!(Aggregate::Count.apply(results()), Ok(Some(num(4))));
+        assert_eq!(Aggregate::Min(x.clone()).apply(results()), Ok(Some(num(1))));
+        assert_eq!(Aggregate::Max(x.clone()).apply(results()), Ok(Some(num(3))));
+        assert_eq!(Aggregate::Sum(x.clone()).apply(results()), Ok(Some(Atom::gnd(Number::Float(6.5)))));
+        assert_eq!(Aggregate::Distinct(VariableAtom::new("y")).apply(results()),
+            Ok(Some(expr!("A" "B" "C"))));
+        assert_eq!(Aggregate::Max(x).apply(Vec::new()), Ok(None));
+    }
+
+    #[test]
+    fn aggregate_sum_of_non_number() {
+        let results = vec![bind!{x: sym!("A")}];
+        assert_eq!(Aggregate::Sum(VariableAtom::new("x")).apply(results),
+            Err("Number is expected as a value of $x, found: A".into()));
+    }
+
+    #[test]
+    fn aggregate_group_by() {
+        let results = vec![bind!{k: sym!("A"), v: num(1)}, bind!{k: sym!("B"), v: num(2)},
+            bind!{k: sym!("A"), v: num(3)}];
+
+        assert_eq!(group_by(results, &expr!(k), &Aggregate::Sum(VariableAtom::new("v"))),
+            Ok(vec![(sym!("A"), num(4)), (sym!("B"), num(2))]));
+    }
+}
@@ -1,11 +1,13 @@
 pub mod storage;
 pub mod trie;
 
-pub use trie::{ALLOW_DUPLICATION, NO_DUPLICATION, DuplicationStrategy, AllowDuplication, NoDuplication};
+pub use trie::{ALLOW_DUPLICATION, NO_DUPLICATION, COUNT_DUPLICATION, DuplicationStrategy,
+    AllowDuplication, NoDuplication, CountDuplication, MemoryStats};
 use trie::*;
 
 use hyperon_atom::*;
-use hyperon_atom::matcher::Bindings;
+use hyperon_atom::matcher::{Bindings, match_atoms};
+use hyperon_atom::gnd::number::Number;
 use hyperon_macros::metta_const;
 
 use std::fmt::{Debug, Display};
@@ -155,9 +157,76 @@ impl<'a> Iterator for AtomIter<'a> {
 pub type QueryResult = Box<dyn Iterator<Item=Bindings>>;
 
 /// Atom index implementation, parameterized by [DuplicationStrategy].
+/// Index is a persistent data structure: clone is O(1) and the copy shares
+/// the unmodified part of the index with the original one.
+///
+/// Atoms are kept in a prefix trie thus queries which start from variables
+/// require scanning. Secondary indexes on the positions of the expression
+/// elements can be added to speed up such queries, see
+/// [AtomIndex::add_secondary_index].
+///
+/// Each atom can have a numeric weight attached, see
+/// [AtomIndex::set_weight]. Weight is forgotten when the last copy of the
+/// atom is removed.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct AtomIndex<D: DuplicationStrategy = NoDuplication> {
     trie: AtomTrie<D>,
+    secondary: Vec<SecondaryIndex<D>>,
+    weights: im::HashMap<Atom, Atom>,
+}
+
+/// Secondary index keeps expressions with an element on the `position` moved
+/// to the beginning of the expression. Expressions which are shorter are not
+/// kept in the secondary index.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+struct SecondaryIndex<D: DuplicationStrategy> {
+    position: usize,
+    trie: AtomTrie<D>,
+}
+
+impl<D: DuplicationStrategy> SecondaryIndex<D> {
+    /// Returns expression with the element on the `position` moved to the
+    /// beginning or `None` if atom is not an expression or it is too short.
+    fn permute(&self, atom: &Atom) -> Option<Atom> {
+        match atom {
+            Atom::Expression(expr) if expr.children().len() > self.position => {
+                let mut children = expr.children().clone();
+                let element = children.remove(self.position);
+                children.insert(0, element);
+                Some(Atom::expr(children))
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns permuted query if secondary index can be used to execute the
+    /// query. It is possible when the element on the `position` is not
+    /// a variable.
+    fn permute_query(&self, query: &Atom) -> Option<Atom> {
+        match query {
+            Atom::Expression(expr) => match expr.children().get(self.position) {
+                Some(Atom::Variable(_)) | None => None,
+                Some(_) => self.permute(query),
+            },
+            _ => None,
+        }
+    }
+}
+
+fn insert_key(atom: Atom) -> impl Iterator<Item=InsertKey> {
+    AtomIter::from_atom(atom).map(|token| match token {
+        AtomToken::StartExpr(_, size) => InsertKey::StartExpr(size),
+        AtomToken::Atom(Cow::Owned(atom)) => InsertKey::Atom(atom),
+        _ => panic!("Only owned atoms are expected to be inserted"),
+    })
+}
+
+fn query_key(atom: &Atom) -> impl Iterator<Item=QueryKey<'_>> + Debug + Clone {
+    AtomIter::from_ref(atom).map(|token| match token {
+        AtomToken::StartExpr(Some(atom), size) => QueryKey::StartExpr(atom, size),
+        AtomToken::Atom(Cow::Borrowed(atom)) => QueryKey::Atom(atom),
+        _ => panic!("Only borrowed atoms are expected to be queried"),
+    })
 }
 
 impl AtomIndex {
@@ -173,41 +242,331 @@ impl<D: DuplicationStrategy> AtomIndex<D> {
         Default::default()
     }
 
+    /// Add secondary index on the `position` of the expression elements.
+    /// Index is filled by atoms which are already in the index and kept up
+    /// to date on each modification. Queries which have a non-variable
+    /// element on the `position` use the secondary index when it is
+    /// estimated to be more selective than the main one. Position `0` is
+    /// the head of the expression which is already indexed by the main trie.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::matcher::Bindings;
+    /// use hyperon_space::index::AtomIndex;
+    ///
+    /// let mut index = AtomIndex::new();
+    /// index.insert(expr!("Edge" "A" "B"));
+    /// index.insert(expr!("Edge" "B" "C"));
+    /// index.add_secondary_index(2);
+    ///
+    /// let result: Vec<Bindings> = index.query(&expr!("Edge" x "C")).collect();
+    /// assert_eq!(result, vec![bind!{x: sym!("B")}]);
+    /// ```
+    pub fn add_secondary_index(&mut self, position: usize) {
+        if position == 0 || self.secondary.iter().any(|index| index.position == position) {
+            return;
+        }
+        let mut index = SecondaryIndex{ position, trie: AtomTrie::default() };
+        let permuted: Vec<Atom> = self.trie.unpack_atoms()
+            .filter_map(|atom| index.permute(&atom))
+            .collect();
+        index.trie.insert_all(permuted.into_iter().map(insert_key));
+        self.secondary.push(index);
+    }
+
+    /// Returns positions of the secondary indexes.
+    pub fn secondary_indexes(&self) -> Vec<usize> {
+        self.secondary.iter().map(|index| index.position).collect()
+    }
+
     /// Insert atom into index.
     pub fn insert(&mut self, atom: Atom) {
-        let key = AtomIter::from_atom(atom)
-            .map(|token| Self::atom_token_to_insert_index_key(token));
-        self.trie.insert(key)
+        for index in &mut self.secondary {
+            if let Some(atom) = index.permute(&atom) {
+                index.trie.insert(insert_key(atom));
+            }
+        }
+        self.trie.insert(insert_key(atom))
     }
 
-    fn atom_token_to_insert_index_key<'a>(token: AtomToken<'a>) -> InsertKey {
-        match token {
-            AtomToken::StartExpr(_, size) => InsertKey::StartExpr(size),
-            AtomToken::Atom(Cow::Owned(atom)) => InsertKey::Atom(atom),
-            _ => panic!("Only owned atoms are expected to be inserted"),
+    /// Insert all `atoms` into index. Atoms are grouped by the common prefix
+    /// before insertion, thus it is faster than inserting atoms one by one
+    /// and it should be preferred to build a large index, see
+    /// [AtomTrie::insert_all].
+    pub fn insert_all<I: IntoIterator<Item=Atom>>(&mut self, atoms: I) {
+        let atoms: Vec<Atom> = atoms.into_iter().collect();
+        for index in &mut self.secondary {
+            let permuted: Vec<Atom> = atoms.iter().filter_map(|atom| index.permute(atom)).collect();
+            index.trie.insert_all(permuted.into_iter().map(insert_key));
+        }
+        self.trie.insert_all(atoms.into_iter().map(insert_key))
+    }
+
+    /// Returns secondary index and permuted query if secondary index is
+    /// estimated to be more selective than the main index for the `query`.
+    fn select_index(&self, query: &Atom) -> Option<(&AtomTrie<D>, Atom)> {
+        // Atoms which are not expressions, for instance variables, can be
+        // matched with any expression but they are not kept in the
+        // secondary indexes.
+        if self.secondary.is_empty() || self.trie.has_root_unification_keys() {
+            return None;
+        }
+        let mut best: Option<(&AtomTrie<D>, Atom)> = None;
+        let mut limit = usize::MAX;
+        for index in &self.secondary {
+            if let Some(permuted) = index.permute_query(query) {
+                let estimation = index.trie.estimate(query_key(&permuted), limit);
+                if estimation < limit || best.is_none() {
+                    limit = estimation;
+                    best = Some((&index.trie, permuted));
+                }
+            }
+        }
+        if best.is_some() && self.trie.estimate(query_key(query), limit.saturating_add(1)) > limit {
+            best
+        } else {
+            None
         }
     }
 
     /// Query atoms which can be unified with `atom` from index.
     pub fn query(&self, atom: &Atom) -> QueryResult {
-        let key = AtomIter::from_ref(&atom)
-            .map(|token| Self::atom_token_to_query_index_key(token));
-        Box::new(self.trie.query(key).into_iter())
+        let result = match self.select_index(atom) {
+            Some((trie, permuted)) => trie.query(query_key(&permuted)),
+            None => self.trie.query(query_key(atom)),
+        };
+        Box::new(result.into_iter())
+    }
+
+    /// Query atoms which can be unified with `atom` and bind `count`
+    /// variable to the number of times each matched atom is kept in the
+    /// index. Single result is returned for each distinct matched atom. It
+    /// is mostly useful with [CountDuplication] strategy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_atom::matcher::Bindings;
+    /// use hyperon_space::index::{AtomIndex, COUNT_DUPLICATION};
+    ///
+    /// let mut index = AtomIndex::with_strategy(COUNT_DUPLICATION);
+    /// index.insert(expr!("seen" "A"));
+    /// index.insert(expr!("seen" "A"));
+    ///
+    /// let result: Vec<Bindings> = index.query_with_count(&expr!("seen" x), &VariableAtom::new("n")).collect();
+    /// assert_eq!(result, vec![bind!{x: sym!("A"), n: expr!({Number::Integer(2)})}]);
+    /// ```
+    pub fn query_with_count(&self, atom: &Atom, count: &VariableAtom) -> QueryResult {
+        let result = match self.select_index(atom) {
+            Some((trie, permuted)) => trie.query_with_count(query_key(&permuted), count),
+            None => self.trie.query_with_count(query_key(atom), count),
+        };
+        Box::new(result.into_iter())
     }
 
-    fn atom_token_to_query_index_key<'a>(token: AtomToken<'a>) -> QueryKey<'a> {
-        match token {
-            AtomToken::StartExpr(Some(atom), size) => QueryKey::StartExpr(atom, size),
-            AtomToken::Atom(Cow::Borrowed(atom)) => QueryKey::Atom(atom),
-            _ => panic!("Only borrowed atoms are expected to be queried"),
+    /// Returns number of times `atom` is kept in the index. Atom is matched
+    /// by equality.
+    pub fn multiplicity(&self, atom: &Atom) -> usize {
+        self.trie.multiplicity(query_key(atom))
+    }
+
+    /// Inserts `atom` into index `count` times. Counter of the atom is
+    /// updated once, see [AtomTrie::insert_count].
+    pub fn insert_count(&mut self, atom: Atom, count: usize) {
+        for index in &mut self.secondary {
+            if let Some(atom) = index.permute(&atom) {
+                index.trie.insert_count(insert_key(atom), count);
+            }
         }
+        self.trie.insert_count(insert_key(atom), count)
+    }
+
+    /// Decrements counter of the `atom` by `count`. Atom is removed when
+    /// its counter becomes zero. Returns the number the counter is actually
+    /// decremented by.
+    pub fn remove_count(&mut self, atom: &Atom, count: usize) -> usize {
+        let removed = self.trie.remove_count(query_key(atom), count);
+        if removed > 0 {
+            for index in &mut self.secondary {
+                if let Some(atom) = index.permute(atom) {
+                    index.trie.remove_count(query_key(&atom), removed);
+                }
+            }
+            if !self.weights.is_empty() && self.multiplicity(atom) == 0 {
+                self.weights.remove(atom);
+            }
+        }
+        removed
     }
 
     /// Remove specific atom from index.
     pub fn remove(&mut self, atom: &Atom) -> bool {
-        let key = AtomIter::from_ref(&atom)
-            .map(|token| Self::atom_token_to_query_index_key(token));
-        self.trie.remove(key)
+        self.remove_count(atom, 1) > 0
+    }
+
+    /// Attaches numeric `weight` to the `atom`. Weight is shared by all
+    /// copies of the atom and replaces the previous one. Returns `false`
+    /// if `atom` is not in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_space::index::AtomIndex;
+    ///
+    /// let mut index = AtomIndex::new();
+    /// index.insert(expr!("seen" "A"));
+    /// index.insert(expr!("seen" "B"));
+    /// index.set_weight(&expr!("seen" "A"), Number::Float(0.5));
+    ///
+    /// assert_eq!(index.weight(&expr!("seen" "A")), Some(Number::Float(0.5)));
+    /// assert_eq!(index.weight(&expr!("seen" "B")), None);
+    /// ```
+    pub fn set_weight(&mut self, atom: &Atom, weight: Number) -> bool {
+        if self.multiplicity(atom) == 0 {
+            return false;
+        }
+        self.weights.insert(atom.clone(), Atom::gnd(weight));
+        true
+    }
+
+    /// Returns weight of the `atom` or `None` if weight is not set. Atom is
+    /// matched by equality.
+    pub fn weight(&self, atom: &Atom) -> Option<Number> {
+        self.weights.get(atom).map(|weight| Number::try_from(weight)
+            .expect("Weight is expected to be a number"))
+    }
+
+    /// Query atoms which can be unified with `pattern` and bind `weight`
+    /// variable to the weight of each matched atom. Atoms without weight
+    /// are not returned. `weight` variable should not be a part of the
+    /// pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_atom::matcher::Bindings;
+    /// use hyperon_space::index::AtomIndex;
+    ///
+    /// let mut index = AtomIndex::new();
+    /// index.insert(expr!("seen" "A"));
+    /// index.insert(expr!("seen" "B"));
+    /// index.set_weight(&expr!("seen" "A"), Number::Integer(3));
+    ///
+    /// let result: Vec<Bindings> = index.query_with_weight(&expr!("seen" x), &VariableAtom::new("w")).collect();
+    /// assert_eq!(result, vec![bind!{x: sym!("A"), w: expr!({Number::Integer(3)})}]);
+    /// ```
+    pub fn query_with_weight(&self, pattern: &Atom, weight: &VariableAtom) -> QueryResult {
+        let mut result = Vec::new();
+        if !self.weights.is_empty() {
+            for (atom, _count) in self.trie.match_candidates(query_key(pattern)) {
+                let value = match self.weights.get(&atom) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let atom = make_variables_unique(atom);
+                result.extend(match_atoms(pattern, &atom)
+                    .filter_map(|bindings| bindings.add_var_binding(weight, value).ok()));
+            }
+        }
+        Box::new(result.into_iter())
+    }
+
+    /// Remove all atoms which can be unified with `pattern` from index and
+    /// return them. Candidates are collected from the trie using `pattern`
+    /// as a key, see [AtomTrie::match_candidates], and then matched with
+    /// `pattern` as a whole.
+    pub fn remove_matching(&mut self, pattern: &Atom) -> Vec<Atom> {
+        let matched: Vec<(Atom, usize)> = self.trie.match_candidates(query_key(pattern))
+            .into_iter()
+            .filter(|(atom, _count)| match_atoms(pattern, atom).next().is_some())
+            .collect();
+        let mut removed = Vec::new();
+        for (atom, count) in matched {
+            let count = self.remove_count(&atom, count);
+            removed.extend(std::iter::repeat_n(atom, count));
+        }
+        removed
+    }
+
+    /// Estimate number of atoms which can be matched with `pattern`, see
+    /// [AtomTrie::estimate]. Result is an upper bound of the number of
+    /// matching atoms or `limit` whatever is less.
+    pub fn estimate(&self, pattern: &Atom, limit: usize) -> usize {
+        match self.select_index(pattern) {
+            Some((trie, permuted)) => trie.estimate(query_key(&permuted), limit),
+            None => self.trie.estimate(query_key(pattern), limit),
+        }
+    }
+
+    /// Count atoms which can be matched with `pattern` using index only, see
+    /// [AtomTrie::count]. Returns `None` if atoms cannot be counted without
+    /// matching.
+    pub fn count(&self, pattern: &Atom) -> Option<usize> {
+        self.trie.count(query_key(pattern)).or_else(|| {
+            if self.trie.has_root_unification_keys() {
+                return None;
+            }
+            self.secondary.iter().find_map(|index| {
+                index.permute_query(pattern)
+                    .and_then(|permuted| index.trie.count(query_key(&permuted)))
+            })
+        })
+    }
+
+    /// Returns memory usage report of the index. Nodes, keys and bytes of
+    /// the secondary indexes are included into the report while atoms are
+    /// counted only once.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = self.trie.memory_stats();
+        for index in &self.secondary {
+            let secondary = index.trie.memory_stats();
+            stats.nodes += secondary.nodes;
+            stats.free_nodes += secondary.free_nodes;
+            stats.hashable_keys += secondary.hashable_keys;
+            stats.other_keys += secondary.other_keys;
+            stats.grounded_keys += secondary.grounded_keys;
+            stats.storage_bytes += secondary.storage_bytes;
+        }
+        stats
+    }
+
+    /// Rebuilds the index from scratch to release node slots and keys which
+    /// are left after the atoms are removed. After compaction ids of the
+    /// nodes and keys are dense. Secondary indexes are rebuilt on the same
+    /// positions, weights of the atoms are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_space::index::AtomIndex;
+    ///
+    /// let mut index = AtomIndex::new();
+    /// index.insert(expr!("A" "B"));
+    /// index.insert(expr!("C" "D"));
+    /// index.remove(&expr!("A" "B"));
+    /// assert_eq!(index.memory_stats().hashable_keys, 4);
+    ///
+    /// index.compact();
+    /// assert_eq!(index.memory_stats().hashable_keys, 2);
+    /// assert_eq!(index.memory_stats().free_nodes, 0);
+    /// ```
+    pub fn compact(&mut self) {
+        let mut compacted = Self::default();
+        for index in &self.secondary {
+            compacted.add_secondary_index(index.position);
+        }
+        compacted.insert_all(self.iter().map(Cow::into_owned));
+        compacted.weights = std::mem::take(&mut self.weights);
+        *self = compacted;
     }
 
     /// Iterate via atoms in index.
@@ -221,6 +580,12 @@ impl<D: DuplicationStrategy> AtomIndex<D> {
     }
 }
 
+impl<D: DuplicationStrategy> crate::SpaceStatistics for AtomIndex<D> {
+    fn estimate_matches(&self, pattern: &Atom, limit: usize) -> usize {
+        self.estimate(pattern, limit)
+    }
+}
+
 impl<D: DuplicationStrategy + Display> Display for AtomIndex<D> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         Display::fmt(&self.trie, f)
@@ -233,6 +598,8 @@ mod test {
 
     use hyperon_atom::matcher::*;
     use hyperon_atom::{expr, sym, bind};
+    use hyperon_atom::gnd::number::{Number, NumberRange};
+    use hyperon_atom::gnd::str::{Str, StrPrefix};
     use hyperon_common::assert_eq_no_order;
     use std::fmt::{Debug, Display, Formatter};
 
@@ -645,6 +1012,159 @@ mod test {
         }
     }
 
+    #[test]
+    fn atom_index_insert_all_groups_atoms() {
+        let atoms = vec![expr!("A" "B"), expr!("A" ("C" x)), expr!("A" "B"), expr!("A" {Number::Integer(1)}),
+            expr!("A" {Number::Integer(2)}), expr!("B" ("C" "D")), expr!("A"), expr!(x "E"), sym!("A")];
+        let mut bulk = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        bulk.insert(expr!("A" "D"));
+        bulk.insert_all(atoms.clone());
+        let mut single = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        single.insert(expr!("A" "D"));
+        atoms.into_iter().for_each(|atom| single.insert(atom));
+
+        assert_eq_no_order!(get_atoms(&bulk), get_atoms(&single));
+        assert_eq!(bulk.memory_stats().nodes, single.memory_stats().nodes);
+        assert_eq!(bulk.multiplicity(&expr!("A" "B")), 2);
+        assert_eq_bind_no_order!(bulk.query(&expr!("B" y)), vec![bind!{y: expr!("C" "D")}]);
+        assert_eq_bind_no_order!(bulk.query(&expr!("A" {NumberRange::new(Number::Integer(2), Number::Integer(3))})),
+            vec![bind!{}]);
+    }
+
+    #[test]
+    fn atom_index_remove_matching() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B"), expr!("A" "B"), expr!("A" ("C")), expr!("B" "A")]);
+
+        assert_eq_no_order!(index.remove_matching(&expr!("A" x)),
+            vec![expr!("A" "B"), expr!("A" "B"), expr!("A" ("C"))]);
+        assert!(index.remove_matching(&expr!("C" x)).is_empty());
+        assert_eq_no_order!(get_atoms(&index), vec![expr!("B" "A")]);
+    }
+
+    #[test]
+    fn atom_index_weight() {
+        let mut index = AtomIndex::with_strategy(COUNT_DUPLICATION);
+        index.insert_count(expr!("seen" "A"), 2);
+        index.insert(expr!("seen" x));
+        let w = VariableAtom::new("w");
+
+        assert!(!index.set_weight(&expr!("seen" "B"), Number::Integer(1)));
+        assert!(index.set_weight(&expr!("seen" "A"), Number::Float(0.5)));
+        assert!(index.set_weight(&expr!("seen" x), Number::Integer(1)));
+        assert_eq_bind_no_order!(index.query_with_weight(&expr!("seen" "A"), &w).map(plain_vars),
+            vec![bind!{w: expr!({Number::Float(0.5)})}, bind!{x: sym!("A"), w: expr!({Number::Integer(1)})}]);
+
+        assert_eq!(index.remove_count(&expr!("seen" "A"), 1), 1);
+        assert_eq!(index.weight(&expr!("seen" "A")), Some(Number::Float(0.5)));
+        assert_eq!(index.remove_count(&expr!("seen" "A"), 1), 1);
+        assert_eq!(index.weight(&expr!("seen" "A")), None);
+        index.insert(expr!("seen" "A"));
+        assert_eq!(index.weight(&expr!("seen" "A")), None);
+    }
+
+    #[test]
+    fn atom_index_weight_survives_compact() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![expr!("seen" "A"), expr!("seen" "B")]);
+        assert!(index.set_weight(&expr!("seen" "A"), Number::Float(0.5)));
+        index.remove(&expr!("seen" "B"));
+
+        index.compact();
+
+        assert_eq!(index.weight(&expr!("seen" "A")), Some(Number::Float(0.5)));
+        assert_eq!(index.weight(&expr!("seen" "B")), None);
+    }
+
+    #[test]
+    fn atom_index_remove_matching_checks_whole_atom() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![expr!("A" x "B"), expr!("A" "B" "C"), expr!("A" ("B") "C"),
+            expr!("A" "C" "C"), expr!("B" {Number::Integer(1)} "C"), expr!("B" {Number::Integer(2)} "C"),
+            expr!("C" {MatchAsX{}})]);
+
+        assert_eq_no_order!(index.remove_matching(&expr!("A" y y)),
+            vec![expr!("A" x "B"), expr!("A" "C" "C")]);
+        assert_eq_no_order!(index.remove_matching(&expr!("A" ("B") z)),
+            vec![expr!("A" ("B") "C")]);
+        assert_eq_no_order!(index.remove_matching(&expr!("B" {NumberRange::new(Number::Integer(2), Number::Integer(3))} "C")),
+            vec![expr!("B" {Number::Integer(2)} "C")]);
+        assert_eq_no_order!(index.remove_matching(&expr!("C" "D")),
+            vec![expr!("C" {MatchAsX{}})]);
+        assert_eq_no_order!(get_atoms(&index), vec![expr!("A" "B" "C"), expr!("B" {Number::Integer(1)} "C")]);
+    }
+
+    #[test]
+    fn atom_index_estimate() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B"), expr!("A" "B"), expr!("A" "C"),
+            expr!("A" ("D")), expr!("B" "A"), expr!("E")]);
+
+        assert_eq!(index.estimate(&expr!("A" "B"), usize::MAX), 2);
+        assert_eq!(index.estimate(&expr!("A" x), usize::MAX), 4);
+        assert_eq!(index.estimate(&expr!("A" x), 3), 3);
+        assert_eq!(index.estimate(&expr!(x "A"), usize::MAX), 5);
+        assert_eq!(index.estimate(&expr!("C" x), usize::MAX), 0);
+        assert_eq!(index.estimate(&expr!("F"), usize::MAX), 0);
+        assert_eq!(index.estimate(&expr!(x), usize::MAX), 6);
+    }
+
+    #[test]
+    fn atom_index_count() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B"), expr!("A" "B"), expr!("A" ("C")),
+            expr!("B" "A"), expr!("B" x)]);
+
+        assert_eq!(index.count(&expr!("A" "B")), Some(2));
+        assert_eq!(index.count(&expr!("A" x)), Some(3));
+        assert_eq!(index.count(&expr!(x y)), Some(5));
+        assert_eq!(index.count(&expr!("C" x)), Some(0));
+        assert_eq!(index.count(&expr!(x x)), None);
+        assert_eq!(index.count(&expr!(x "A")), None);
+        assert_eq!(index.count(&expr!("B" "A")), None);
+    }
+
+    #[test]
+    fn atom_index_secondary_index() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("Edge" "A" "B"), expr!("Edge" "B" "C"),
+            expr!("Edge" "C" "C"), expr!("Edge" "A" "B"), expr!("Edge" "D" "C"), expr!("A")]);
+        index.add_secondary_index(2);
+        index.add_secondary_index(2);
+        assert_eq!(index.secondary_indexes(), vec![2]);
+
+        assert_eq_bind_no_order!(index.query(&expr!("Edge" x "C")),
+            vec![bind!{x: sym!("B")}, bind!{x: sym!("C")}, bind!{x: sym!("D")}]);
+        assert_eq_bind_no_order!(index.query(&expr!(r x "B")),
+            vec![bind!{r: sym!("Edge"), x: sym!("A")}, bind!{r: sym!("Edge"), x: sym!("A")}]);
+        assert_eq!(index.count(&expr!(r x "B")), Some(2));
+        assert!(index.estimate(&expr!(r x "B"), usize::MAX) < index.estimate(&expr!(r x y), usize::MAX));
+
+        index.remove(&expr!("Edge" "A" "B"));
+        index.insert(expr!("Edge" "D" "B"));
+        assert_eq_bind_no_order!(index.query(&expr!(r x "B")),
+            vec![bind!{r: sym!("Edge"), x: sym!("A")}, bind!{r: sym!("Edge"), x: sym!("D")}]);
+    }
+
+    #[test]
+    fn atom_index_secondary_index_with_variable_atom() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![expr!("Edge" "A" "B"), expr!(x)]);
+        index.add_secondary_index(2);
+
+        assert_eq_bind_no_order!(index.query(&expr!(r "A" "B")),
+            vec![bind!{r: sym!("Edge")}, bind!{x: expr!(r "A" "B")}]);
+    }
+
+    #[test]
+    fn atom_index_estimate_unifiable_entries() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![expr!("A" x), expr!("A" "B"), expr!("B" "C")]);
+
+        assert_eq!(index.estimate(&expr!("A" "C"), usize::MAX), 2);
+        assert_eq!(index.estimate(&expr!("B" "B"), usize::MAX), 0);
+    }
+
     #[test]
     fn atom_index_query_matchable() {
         let mut index = AtomIndex::new();
@@ -664,4 +1184,106 @@ mod test {
         let actual: Vec<_> = index.query(&expr!("A" "B" "C")).collect();
         assert_eq_no_order!(actual, vec![bind!{ x: expr!("A" "B" "C") }]);
     }
+
+    #[test]
+    fn atom_index_memory_stats() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B"), expr!("A" "B"), expr!("A" x), expr!("n" {Num(1)})]);
+
+        let stats = index.memory_stats();
+        assert_eq!(stats.atoms, 4);
+        assert_eq!(stats.distinct_atoms, 3);
+        assert_eq!(stats.grounded_keys, 1);
+        assert_eq!(stats.free_nodes, 0);
+        assert_eq!(stats.duplicate_ratio(), 0.25);
+        assert!(stats.storage_bytes > 0);
+    }
+
+    #[test]
+    fn atom_index_compact() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B" "C"), expr!("A" "B" "C"), expr!("D" "E" "F")]);
+        index.add_secondary_index(2);
+        index.remove(&expr!("D" "E" "F"));
+        assert!(index.memory_stats().free_nodes > 0);
+
+        index.compact();
+
+        let stats = index.memory_stats();
+        assert_eq!(stats.free_nodes, 0);
+        assert_eq!(stats.atoms, 2);
+        assert_eq!(index.secondary_indexes(), vec![2]);
+        assert_eq_no_order!(index.iter().map(Cow::into_owned).collect::<Vec<_>>(),
+            vec![expr!("A" "B" "C"), expr!("A" "B" "C")]);
+        assert_eq_bind_no_order!(index.query(&expr!(x "B" "C")),
+            vec![bind!{x: sym!("A")}, bind!{x: sym!("A")}]);
+    }
+
+    #[test]
+    fn atom_index_count_duplication() {
+        let mut index = AtomIndex::with_strategy(COUNT_DUPLICATION);
+        index.insert_count(expr!("seen" "A"), 3);
+        index.insert(expr!("seen" "B"));
+        let n = VariableAtom::new("n");
+
+        assert_eq_bind_no_order!(index.query(&expr!("seen" x)),
+            vec![bind!{x: sym!("A")}, bind!{x: sym!("B")}]);
+        assert_eq_bind_no_order!(index.query_with_count(&expr!("seen" x), &n),
+            vec![bind!{x: sym!("A"), n: expr!({Number::Integer(3)})},
+                bind!{x: sym!("B"), n: expr!({Number::Integer(1)})}]);
+        assert_eq!(index.count(&expr!("seen" x)), Some(2));
+        assert_eq!(index.multiplicity(&expr!("seen" "A")), 3);
+
+        assert_eq!(index.remove_count(&expr!("seen" "A"), 2), 2);
+        assert_eq!(index.multiplicity(&expr!("seen" "A")), 1);
+        assert_eq!(index.remove_count(&expr!("seen" "A"), 2), 1);
+        assert_eq!(index.multiplicity(&expr!("seen" "A")), 0);
+        assert_eq_bind_no_order!(index.query(&expr!("seen" x)), vec![bind!{x: sym!("B")}]);
+    }
+
+    #[test]
+    fn atom_index_query_with_count_allow_duplication() {
+        let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+        index.insert_all(vec![expr!("A" "B"), expr!("A" "B"), expr!(x "C")]);
+        let n = VariableAtom::new("n");
+
+        assert_eq!(index.query(&expr!("A" y)).count(), 3);
+        assert_eq_bind_no_order!(index.query_with_count(&expr!("A" y), &n),
+            vec![bind!{y: sym!("B"), n: expr!({Number::Integer(2)})},
+                bind!{x: sym!("A"), y: sym!("C"), n: expr!({Number::Integer(1)})}]);
+    }
+
+    #[test]
+    fn atom_index_query_number_range() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![
+            expr!("age" "Ann" {Number::Integer(9)}),
+            expr!("age" "Bob" {Number::Integer(15)}),
+            expr!("age" "Tom" {Number::Float(20.0)}),
+            expr!("age" "Sam" "unknown"),
+            expr!("age" "Liz" x),
+        ]);
+        let range = NumberRange::new(Number::Integer(10), Number::Integer(20));
+
+        assert_eq_bind_no_order!(index.query(&expr!("age" y {range.clone()})), vec![
+            bind!{y: sym!("Bob")}, bind!{y: sym!("Tom")}, bind!{y: sym!("Liz"), x: Atom::gnd(range.clone())}]);
+
+        index.remove(&expr!("age" "Bob" {Number::Integer(15)}));
+        assert_eq_bind_no_order!(index.query(&expr!("age" y {range.clone()})), vec![
+            bind!{y: sym!("Tom")}, bind!{y: sym!("Liz"), x: Atom::gnd(range.clone())}]);
+    }
+
+    #[test]
+    fn atom_index_query_str_prefix() {
+        let mut index = AtomIndex::new();
+        index.insert_all(vec![
+            expr!("url" "A" {Str::from_str("http://a.org")}),
+            expr!("url" "B" {Str::from_str("https://b.org")}),
+            expr!("url" "C" {Str::from_str("http:")}),
+            expr!("url" "D" "http://d.org"),
+        ]);
+        let prefix = StrPrefix::new("http://");
+
+        assert_eq_bind_no_order!(index.query(&expr!("url" y {prefix.clone()})), vec![bind!{y: sym!("A")}]);
+    }
 }
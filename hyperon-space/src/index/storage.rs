@@ -2,16 +2,16 @@ use hyperon_atom::*;
 use hyperon_atom::serial::NullSerializer;
 use hyperon_common::collections::write_mapping;
 
-use bimap::BiMap;
 use std::hash::{Hash, Hasher};
 use std::hash::DefaultHasher;
 use std::fmt::{Debug, Display, Formatter};
 
-/// Storage for a hashable atoms.
+/// Storage for a hashable atoms. Storage is a persistent data structure:
+/// clone is O(1) and the copy shares the atoms with the original one.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct AtomStorage {
-    next_id: usize,
-    atoms: BiMap<HashableAtom, usize>,
+    ids: im::HashMap<HashableAtom, usize>,
+    atoms: im::Vector<Atom>,
 }
 
 impl AtomStorage {
@@ -43,32 +43,26 @@ impl AtomStorage {
     }
 
     fn insert_internal(&mut self, atom: Atom) -> usize {
-        match self.atoms.get_by_left(&HashableAtom::Query(&atom)) {
+        match self.ids.get(&HashableAtom::Query(&atom)) {
             Some(id) => *id,
             None => {
-                let id = self.next_id();
-                self.atoms.insert(HashableAtom::Store(atom), id);
+                let id = self.atoms.len();
+                self.atoms.push_back(atom.clone());
+                self.ids.insert(HashableAtom::Store(atom), id);
                 id
             },
         }
     }
 
-    #[inline]
-    fn next_id(&mut self) -> usize {
-        let id = self.next_id;
-        self.next_id = self.next_id + 1;
-        id
-    }
-
     /// Gets atom from the storage if any.
     pub fn get_atom(&self, id: usize) -> Option<&Atom> {
-        self.atoms.get_by_right(&id).map(|h| h.as_atom())
+        self.atoms.get(id)
     }
 
     /// Gets id of the atom in the storage if any.
     pub fn get_id(&self, atom: &Atom) -> Option<usize> {
         if Self::is_hashable(atom) {
-            self.atoms.get_by_left(&HashableAtom::Query(atom)).map(|id| *id)
+            self.ids.get(&HashableAtom::Query(atom)).map(|id| *id)
         } else {
             None
         }
@@ -86,16 +80,18 @@ impl AtomStorage {
 
     /// Returns number of atoms in the storage.
     pub fn count(&self) -> usize {
-        self.atoms.left_values().count()
+        self.atoms.len()
+    }
+
+    /// Iterates over atoms in the storage.
+    pub fn iter(&self) -> impl Iterator<Item=&Atom> {
+        self.atoms.iter()
     }
 }
 
 impl Display for AtomStorage {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        let mut sorted: Vec<_> = self.atoms.iter()
-            .map(|(atom, id)| (id, atom)).collect();
-        sorted.sort_unstable_by_key(|(&id, _)| id);
-        write_mapping(f, sorted.into_iter())
+        write_mapping(f, self.atoms.iter().enumerate())
     }
 }
 
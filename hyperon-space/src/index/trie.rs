@@ -2,11 +2,10 @@ use super::storage::AtomStorage as HashableStorage;
 
 use hyperon_atom::*;
 use hyperon_atom::matcher::*;
+use hyperon_atom::gnd::number::Number;
 use hyperon_common::CachingMapper;
-use hyperon_common::holeyvec::HoleyVec;
 
-use std::hash::Hash;
-use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
 use std::fmt::{Debug, Formatter, Display};
 use std::borrow::Cow;
 
@@ -18,9 +17,29 @@ pub trait DuplicationStrategyImplementor {
 
 /// Duplication strategy type.
 // TODO: modify duplication strategy to be able represent TrieKey::Leaf differently
-pub trait DuplicationStrategy: Default {
+pub trait DuplicationStrategy: Default + Clone {
     fn add_atom(leaf: &mut dyn DuplicationStrategyImplementor);
     fn remove_atom(leaf: &mut dyn DuplicationStrategyImplementor);
+    /// Adds atom `count` times.
+    fn add_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) {
+        for _ in 0..count {
+            Self::add_atom(leaf);
+        }
+    }
+    /// Removes atom up to `count` times. Returns the number of times atom
+    /// is actually removed.
+    fn remove_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) -> usize {
+        let removed = count.min(*leaf.dup_counter_mut());
+        for _ in 0..removed {
+            Self::remove_atom(leaf);
+        }
+        removed
+    }
+    /// Returns number of query results for the atom which counter is equal
+    /// to `count`.
+    fn result_count(count: usize) -> usize {
+        count
+    }
 }
 
 /// Duplication strategy which forbids duplication.
@@ -35,6 +54,17 @@ impl DuplicationStrategy for NoDuplication {
         let count = leaf.dup_counter_mut();
         *count = 0;
     }
+    fn add_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) {
+        if count > 0 {
+            Self::add_atom(leaf);
+        }
+    }
+    fn remove_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) -> usize {
+        let counter = leaf.dup_counter_mut();
+        let removed = count.min(*counter);
+        *counter -= removed;
+        removed
+    }
 }
 
 impl Display for NoDuplication {
@@ -55,6 +85,15 @@ impl DuplicationStrategy for AllowDuplication {
         let count = leaf.dup_counter_mut();
         *count -= 1;
     }
+    fn add_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) {
+        *leaf.dup_counter_mut() += count;
+    }
+    fn remove_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) -> usize {
+        let counter = leaf.dup_counter_mut();
+        let removed = count.min(*counter);
+        *counter -= removed;
+        removed
+    }
 }
 
 impl Display for AllowDuplication {
@@ -63,8 +102,46 @@ impl Display for AllowDuplication {
     }
 }
 
+/// Duplication strategy which keeps atoms as a multiset: each distinct atom
+/// is stored once together with the number of times it was added. Removing
+/// the atom decrements its counter and atom is removed when counter becomes
+/// zero. Query returns a single result per distinct atom, use
+/// [AtomTrie::query_with_count] to get the counter as a part of the result.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct CountDuplication {}
+impl DuplicationStrategy for CountDuplication {
+    fn add_atom(leaf: &mut dyn DuplicationStrategyImplementor) {
+        let count = leaf.dup_counter_mut();
+        *count += 1;
+    }
+    fn remove_atom(leaf: &mut dyn DuplicationStrategyImplementor) {
+        let count = leaf.dup_counter_mut();
+        *count -= 1;
+    }
+    fn add_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) {
+        *leaf.dup_counter_mut() += count;
+    }
+    fn remove_atoms(leaf: &mut dyn DuplicationStrategyImplementor, count: usize) -> usize {
+        let counter = leaf.dup_counter_mut();
+        let removed = count.min(*counter);
+        *counter -= removed;
+        removed
+    }
+    fn result_count(count: usize) -> usize {
+        count.min(1)
+    }
+}
+
+impl Display for CountDuplication {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "CountDuplication")
+    }
+}
+
 /// [AllowDuplication] strategy instance.
 pub const ALLOW_DUPLICATION: AllowDuplication = AllowDuplication{};
+/// [CountDuplication] strategy instance.
+pub const COUNT_DUPLICATION: CountDuplication = CountDuplication{};
 /// [NoDuplication] strategy instance.
 pub const NO_DUPLICATION: NoDuplication = NoDuplication{};
 
@@ -110,7 +187,7 @@ impl QueryKey<'_> {
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 struct TrieKeyStorage {
     hashable: HashableStorage,
-    vec: HoleyVec<Atom>,
+    vec: PersistentHoleyVec<Atom>,
 }
 
 impl TrieKeyStorage {
@@ -177,7 +254,7 @@ impl TrieKeyStorage {
     pub unsafe fn get_atom_unchecked(&self, key: TrieKey) -> &Atom {
         match key.store() {
             TrieKeyStore::Hash => self.hashable.get_atom(key.value()).unwrap(),
-            TrieKeyStore::Index => self.vec.get_unchecked(key.value()),
+            TrieKeyStore::Index => &self.vec[key.value()],
         }
     }
 
@@ -196,24 +273,124 @@ impl TrieKeyStorage {
 /// Index of the trie node inside collection of the nodes.
 type NodeId = usize;
 
+/// Vector with holes which is a persistent data structure: clone is O(1) and
+/// the copy shares unmodified items with the original one. Indexes of the
+/// removed items are reused by the next insertions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PersistentHoleyVec<T: Clone> {
+    items: im::Vector<Option<T>>,
+    holes: im::Vector<usize>,
+}
+
+impl<T: Clone> Default for PersistentHoleyVec<T> {
+    fn default() -> Self {
+        Self{ items: im::Vector::new(), holes: im::Vector::new() }
+    }
+}
+
+impl<T: Clone> PersistentHoleyVec<T> {
+    fn push(&mut self, value: T) -> usize {
+        match self.holes.pop_back() {
+            Some(index) => {
+                self.items.set(index, Some(value));
+                index
+            },
+            None => {
+                self.items.push_back(Some(value));
+                self.items.len() - 1
+            },
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        let value = self.items.set(index, None).expect("Item is already removed");
+        self.holes.push_back(index);
+        value
+    }
+}
+
+impl<T: Clone> Index<usize> for PersistentHoleyVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.items[index].as_ref().expect("Item is removed")
+    }
+}
+
+impl<T: Clone> IndexMut<usize> for PersistentHoleyVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.items[index].as_mut().expect("Item is removed")
+    }
+}
+
+/// Returns [IndexRange] of the atoms which can be matched by `atom`, see
+/// [CustomIndex::index_range].
+fn index_range(atom: &Atom) -> Option<IndexRange> {
+    match atom {
+        Atom::Grounded(gnd) => gnd.as_grounded().as_index().and_then(|index| index.index_range()),
+        _ => None,
+    }
+}
+
+/// Memory usage report of the [AtomTrie].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Number of atoms including duplicates.
+    pub atoms: usize,
+    /// Number of distinct atoms.
+    pub distinct_atoms: usize,
+    /// Number of trie nodes in use.
+    pub nodes: usize,
+    /// Number of node slots which are freed by removal and not reused yet.
+    pub free_nodes: usize,
+    /// Number of keys in the storage of hashable atoms. Hashable keys are
+    /// not removed with atoms, thus it can include unused keys.
+    pub hashable_keys: usize,
+    /// Number of keys in the storage of non-hashable atoms.
+    pub other_keys: usize,
+    /// Number of grounded atoms among the keys.
+    pub grounded_keys: usize,
+    /// Approximate number of bytes used by the trie containers. Heap memory
+    /// owned by the atoms themselves is not included.
+    pub storage_bytes: usize,
+}
+
+impl MemoryStats {
+    /// Returns the share of the duplicated atoms: `0` when all atoms are
+    /// distinct.
+    pub fn duplicate_ratio(&self) -> f64 {
+        if self.atoms == 0 {
+            0.0
+        } else {
+            (self.atoms - self.distinct_atoms) as f64 / self.atoms as f64
+        }
+    }
+}
+
 /// Trie to keep and query atoms parameterized by [DuplicationStrategy].
+/// Trie is a persistent data structure: clone is O(1) and the copy shares
+/// unmodified nodes with the original one.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AtomTrie<D: DuplicationStrategy = NoDuplication>{
     keys: TrieKeyStorage,
-    nodes: HoleyVec<TrieNode>,
-    index: HashMap<(NodeId, TrieKey), NodeId>,
+    nodes: PersistentHoleyVec<TrieNode>,
+    index: im::HashMap<(NodeId, TrieKey), NodeId>,
+    /// Keys of the grounded atoms which provide [IndexKey] ordered by the
+    /// index key for each node, see [CustomIndex].
+    ordered: im::HashMap<NodeId, im::OrdMap<IndexKey, Vec<TrieKey>>>,
     root: NodeId,
     _phantom: std::marker::PhantomData<D>,
 }
 
 impl<D: DuplicationStrategy> Default for AtomTrie<D> {
     fn default() -> Self {
-        let mut nodes = HoleyVec::new();
+        let mut nodes = PersistentHoleyVec::default();
         let root = nodes.push(Default::default());
         Self {
             keys: Default::default(),
             nodes,
-            index: HashMap::new(),
+            index: im::HashMap::new(),
+            ordered: im::HashMap::new(),
             root,
             _phantom: Default::default(),
         }
@@ -230,41 +407,109 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
     /// Insert list of [InsertKey] into the trie.
     #[inline]
     pub fn insert<I: Iterator<Item=InsertKey>>(&mut self, key: I) {
-        self.insert_internal(self.root, key)
+        self.insert_internal(self.root, key, 1)
     }
 
-    fn insert_internal<I: Iterator<Item=InsertKey>>(&mut self, node_id: NodeId, mut key: I) {
+    /// Insert list of [InsertKey] into the trie `count` times. The counter
+    /// of the leaf is updated once according to the [DuplicationStrategy].
+    #[inline]
+    pub fn insert_count<I: Iterator<Item=InsertKey>>(&mut self, key: I, count: usize) {
+        if count > 0 {
+            self.insert_internal(self.root, key, count)
+        }
+    }
+
+    fn insert_internal<I: Iterator<Item=InsertKey>>(&mut self, node_id: NodeId, mut key: I, count: usize) {
         match key.next() {
             Some(head) => {
                 let head = self.keys.insert_key(head);
                 match self.index.get(&(node_id, head)) {
-                    Some(child_id) => self.insert_internal(*child_id, key),
+                    Some(child_id) => self.insert_internal(*child_id, key, count),
                     None => {
-                        let child_id = self.new_branch(key);
+                        let child_id = self.new_branch(key, count);
                         self.nodes[node_id].push(head);
                         self.index.insert((node_id, head), child_id);
+                        if let Some(index_key) = self.index_key(head) {
+                            self.ordered.entry(node_id).or_insert_with(Default::default)
+                                .entry(index_key).or_insert_with(Vec::new).push(head);
+                        }
                     },
                 }
             },
-            None => D::add_atom(&mut self.nodes[node_id]),
+            None => D::add_atoms(&mut self.nodes[node_id], count),
         }
     }
 
-    fn new_branch<'a, I: Iterator<Item=InsertKey>>(&mut self, key: I) -> NodeId {
+    fn new_branch<I: Iterator<Item=InsertKey>>(&mut self, key: I, count: usize) -> NodeId {
         let child_id = self.nodes.push(Default::default());
-        self.insert_internal(child_id, key);
+        self.insert_internal(child_id, key, count);
         child_id
     }
 
+    /// Insert many lists of [InsertKey] into the trie. Lists are sorted and
+    /// grouped by the common prefix, thus each node on the path is looked up
+    /// or created once per group instead of once per list.
+    pub fn insert_all<I, K>(&mut self, keys: I)
+        where I: IntoIterator<Item=K>, K: Iterator<Item=InsertKey>
+    {
+        let mut keys: Vec<Vec<TrieKey>> = keys.into_iter()
+            .map(|key| key.map(|key| self.keys.insert_key(key)).collect())
+            .collect();
+        keys.sort_unstable();
+        self.insert_group(self.root, &keys, 0);
+    }
+
+    /// Insert sorted `keys` which have common prefix of `depth` length into
+    /// the node which represents the prefix.
+    fn insert_group(&mut self, node_id: NodeId, keys: &[Vec<TrieKey>], depth: usize) {
+        // Shorter keys go first after sorting
+        let ended = keys.iter().take_while(|key| key.len() == depth).count();
+        if ended > 0 {
+            D::add_atoms(&mut self.nodes[node_id], ended);
+        }
+        let mut rest = &keys[ended..];
+        while let Some(first) = rest.first() {
+            let head = first[depth];
+            let len = rest.iter().take_while(|key| key[depth] == head).count();
+            let (group, tail) = rest.split_at(len);
+            let child_id = match self.index.get(&(node_id, head)) {
+                Some(&child_id) => child_id,
+                None => {
+                    let child_id = self.nodes.push(Default::default());
+                    self.nodes[node_id].push(head);
+                    self.index.insert((node_id, head), child_id);
+                    if let Some(index_key) = self.index_key(head) {
+                        self.ordered.entry(node_id).or_insert_with(Default::default)
+                            .entry(index_key).or_insert_with(Vec::new).push(head);
+                    }
+                    child_id
+                },
+            };
+            self.insert_group(child_id, group, depth + 1);
+            rest = tail;
+        }
+    }
+
     /// Query trie using list of the [QueryKey] instances.
     #[inline]
     pub fn query<'a, I: Debug + Clone + Iterator<Item=QueryKey<'a>>>(&self, key: I) -> BindingsSet {
         let mut mapper = CachingMapper::new(|v: &VariableAtom| v.clone().make_unique());
-        self.query_internal(self.root, key, &mut mapper)
+        self.query_internal(self.root, key, None, &mut mapper)
+    }
+
+    /// Query trie using list of the [QueryKey] instances and bind `count`
+    /// variable to the counter of each matched atom. Single result is
+    /// returned for each matched atom regardless of its counter. `count`
+    /// variable should not be a part of the query.
+    pub fn query_with_count<'a, I>(&self, key: I, count: &VariableAtom) -> BindingsSet
+        where I: Debug + Clone + Iterator<Item=QueryKey<'a>>
+    {
+        let mut mapper = CachingMapper::new(|v: &VariableAtom| v.clone().make_unique());
+        self.query_internal(self.root, key, Some(count), &mut mapper)
     }
 
     // TODO: write an algorithm which returns an iterator instead of collected result
-    fn query_internal<'a, I, M>(&self, node_id: NodeId, mut key: I,
+    fn query_internal<'a, I, M>(&self, node_id: NodeId, mut key: I, count: Option<&VariableAtom>,
         mapper: &mut CachingMapper<VariableAtom, VariableAtom, M>) -> BindingsSet
         where
             I: Debug + Clone + Iterator<Item=QueryKey<'a>>,
@@ -274,20 +519,28 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
             Some(head) => {
                 match self.keys.query_key(&head) {
                     (AtomMatchMode::Equality, head, atom) =>
-                        self.match_key_by_equality(node_id, atom, head, key, mapper),
-                    (AtomMatchMode::Unification, _head, Some(atom)) =>
-                        self.match_key_by_unification(node_id, atom, key, mapper),
+                        self.match_key_by_equality(node_id, atom, head, key, count, mapper),
+                    (AtomMatchMode::Unification, _head, Some(atom)) => match index_range(atom) {
+                        Some(range) => self.match_key_by_range(node_id, atom, range, key, count, mapper),
+                        None => self.match_key_by_unification(node_id, atom, key, count, mapper),
+                    },
                     (AtomMatchMode::Unification, _head, None) => unreachable!(),
                 }
             },
             None => {
-                BindingsSet::count(self.nodes[node_id].leaf_counter())
+                let leaf_count = self.nodes[node_id].leaf_counter();
+                match count {
+                    None => BindingsSet::count(D::result_count(leaf_count)),
+                    Some(_) if leaf_count == 0 => BindingsSet::empty(),
+                    Some(var) => BindingsSet::from(Bindings::from(vec![
+                        (var.clone(), Atom::gnd(Number::Integer(leaf_count as i64)))])),
+                }
             }
         }
     }
 
     fn match_key_by_equality<'a, I, M>(&self, node_id: NodeId,
-        atom: Option<&'a Atom>, key: Option<TrieKey>, mut tail: I,
+        atom: Option<&'a Atom>, key: Option<TrieKey>, mut tail: I, count: Option<&VariableAtom>,
         mapper: &mut CachingMapper<VariableAtom, VariableAtom, M>) -> BindingsSet
         where
             I: Debug + Clone + Iterator<Item=QueryKey<'a>>,
@@ -300,7 +553,7 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
         
         if let Some(equality_key) = key {
             if let Some(&child_id) = self.index.get(&(node_id, equality_key)) {
-                result.extend(self.query_internal(child_id, tail.clone(), mapper))
+                result.extend(self.query_internal(child_id, tail.clone(), count, mapper))
             }
         } else {
             // match equality nonhashable key (see TrieKeyStorage::add_atom)
@@ -312,7 +565,7 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
                 for (entry, key) in it {
                     if entry == query {
                         let child_id = *self.index.get(&(node_id, key)).unwrap();
-                        result.extend(self.query_internal(child_id, tail.clone(), mapper));
+                        result.extend(self.query_internal(child_id, tail.clone(), count, mapper));
                     }
                 }
             }
@@ -330,18 +583,75 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
                     (entry, child_id)
                 });
             for (entry, child_id) in it {
-                let mut unify_res = self.unify_entry(entry, query, child_id, tail.clone(), mapper);
+                let mut unify_res = self.unify_entry(entry, query, child_id, tail.clone(), count, mapper);
                 result.extend(unify_res.drain(..));
             }
         }
         result
     }
 
-    fn unify_entry<'a, I, M>(&self, entry: &Atom, key: &Atom, child_id: NodeId, tail: I,
+    /// Matches `atom` which provides [IndexRange] with the entries of the
+    /// node which keys are inside the range and with the entries which are
+    /// matched by unification. Other entries cannot be matched by `atom`
+    /// according to the [CustomIndex] contract.
+    fn match_key_by_range<'a, I, M>(&self, node_id: NodeId,
+        atom: &Atom, range: IndexRange, tail: I, count: Option<&VariableAtom>,
         mapper: &mut CachingMapper<VariableAtom, VariableAtom, M>) -> BindingsSet
         where
             I: Debug + Clone + Iterator<Item=QueryKey<'a>>,
             M: Fn(&VariableAtom)->VariableAtom
+    {
+        let mut result = BindingsSet::empty();
+        let ordered = self.ordered.get(&node_id).filter(|_| !range.is_empty());
+        let in_range = ordered.into_iter()
+            .flat_map(|ordered| ordered.range(range.clone()))
+            .flat_map(|(_index_key, keys)| keys.iter().copied());
+        let unifiable = self.nodes[node_id].iter_match(AtomMatchMode::Unification)
+            .map(|(_index, key)| key);
+        for key in in_range.chain(unifiable) {
+            let entry = unsafe{ self.keys.get_atom_unchecked(key) };
+            let child_id = *self.index.get(&(node_id, key)).unwrap();
+            let mut unify_res = self.unify_entry(entry, atom, child_id, tail.clone(), count, mapper);
+            result.extend(unify_res.drain(..));
+        }
+        result
+    }
+
+    /// Returns [IndexKey] of the atom represented by `key` if the atom
+    /// is matched by equality and provides index key.
+    fn index_key(&self, key: TrieKey) -> Option<IndexKey> {
+        if key.is_start_expr() || key.match_mode() != AtomMatchMode::Equality {
+            return None;
+        }
+        match unsafe{ self.keys.get_atom_unchecked(key) } {
+            Atom::Grounded(gnd) => gnd.as_grounded().as_index().and_then(|index| index.index_key()),
+            _ => None,
+        }
+    }
+
+    fn remove_ordered_key(&mut self, node_id: NodeId, key: TrieKey) {
+        let index_key = match self.index_key(key) {
+            Some(index_key) => index_key,
+            None => return,
+        };
+        if let Some(ordered) = self.ordered.get_mut(&node_id) {
+            if let Some(keys) = ordered.get_mut(&index_key) {
+                keys.retain(|k| *k != key);
+                if keys.is_empty() {
+                    ordered.remove(&index_key);
+                }
+            }
+            if ordered.is_empty() {
+                self.ordered.remove(&node_id);
+            }
+        }
+    }
+
+    fn unify_entry<'a, I, M>(&self, entry: &Atom, key: &Atom, child_id: NodeId, tail: I,
+        count: Option<&VariableAtom>, mapper: &mut CachingMapper<VariableAtom, VariableAtom, M>) -> BindingsSet
+        where
+            I: Debug + Clone + Iterator<Item=QueryKey<'a>>,
+            M: Fn(&VariableAtom)->VariableAtom
     {
         let mut result = BindingsSet::empty();
         let mut entry = entry.clone();
@@ -352,14 +662,14 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
         if result.is_empty() {
             result
         } else {
-            let tail_result = self.query_internal(child_id, tail, mapper);
+            let tail_result = self.query_internal(child_id, tail, count, mapper);
             // TODO: we could move BindingsSet into merge instead of passing by reference
             result.merge(&tail_result).into_iter().filter(|b| !b.has_loops()).collect()
         }
     }
 
     fn match_key_by_unification<'a, I, M>(&self, node_id: NodeId,
-        atom: &Atom, tail: I,
+        atom: &Atom, tail: I, count: Option<&VariableAtom>,
         mapper: &mut CachingMapper<VariableAtom, VariableAtom, M>) -> BindingsSet
         where
             I: Debug + Clone + Iterator<Item=QueryKey<'a>>,
@@ -367,7 +677,7 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
     {
         let mut result = BindingsSet::empty();
         for (entry, child_id) in self.unpack_atoms_internal(node_id) {
-            let mut tail_result = self.unify_entry(&entry, atom, child_id, tail.clone(), mapper);
+            let mut tail_result = self.unify_entry(&entry, atom, child_id, tail.clone(), count, mapper);
             result.extend(tail_result.drain(..));
         }
         result
@@ -396,47 +706,285 @@ impl<D: DuplicationStrategy> AtomTrie<D> {
         self.remove_internal(self.root, key)
     }
 
-    pub fn remove_internal<'a, I>(&mut self, node_id: NodeId, mut key: I) -> bool
+    pub fn remove_internal<'a, I>(&mut self, node_id: NodeId, key: I) -> bool
+        where I: Iterator<Item=QueryKey<'a>>
+    {
+        self.remove_count_internal(node_id, key, 1) > 0
+    }
+
+    /// Remove specific list of [QueryKey] from the trie up to `count` times.
+    /// The counter of the leaf is updated once according to the
+    /// [DuplicationStrategy]. Returns the number of times the list is
+    /// actually removed.
+    #[inline]
+    pub fn remove_count<'a, I: Iterator<Item=QueryKey<'a>>>(&mut self, key: I, count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        self.remove_count_internal(self.root, key, count)
+    }
+
+    fn remove_count_internal<'a, I>(&mut self, node_id: NodeId, mut key: I, count: usize) -> usize
         where I: Iterator<Item=QueryKey<'a>>
     {
         match key.next() {
             Some(head) => {
-                let entry = match self.keys.query_key(&head) {
-                    (_match, None, None) => None,
-                    (match_mode, Some(key), _atom) => {
-                        match self.index.get(&(node_id, key)) {
-                            Some(child_id) => {
-                                self.nodes[node_id].iter_match(match_mode)
-                                    .find(|(_i, k)| *k == key)
-                                    .map(|(i, k)| (k, i, *child_id))
-                            },
-                            None => None,
-                        }
-                    },
-                    (match_mode, None, Some(atom)) => {
-                        self.nodes[node_id].iter_match(match_mode)
-                            .find(|(_i, k)| atom == unsafe{ self.keys.get_atom_unchecked(*k) })
-                            .map(|(i, k)| (k, i, *self.index.get(&(node_id, k)).unwrap()))
-                    },
-                };
-                match entry {
+                match self.find_child(node_id, &head) {
                     Some((child_key, index, child_id)) => {
-                        let removed = self.remove_internal(child_id, key);
-                        if removed && self.nodes[child_id].is_leaf() {
+                        let removed = self.remove_count_internal(child_id, key, count);
+                        if removed > 0 && self.nodes[child_id].is_leaf() {
+                            self.remove_ordered_key(node_id, child_key);
                             self.index.remove(&(node_id, child_key));
                             self.nodes[node_id].remove_key(child_key, index);
+                            self.nodes.remove(child_id);
                             self.keys.remove_key(child_key);
                         }
                         removed
                     },
-                    None => false,
+                    None => 0,
+                }
+            },
+            None => D::remove_atoms(&mut self.nodes[node_id], count),
+        }
+    }
+
+    /// Returns key, index of the key inside the node and id of the child
+    /// node which is matched by `head` using equality.
+    fn find_child(&self, node_id: NodeId, head: &QueryKey) -> Option<(TrieKey, usize, NodeId)> {
+        match self.keys.query_key(head) {
+            (_match, None, None) => None,
+            (match_mode, Some(key), _atom) => {
+                match self.index.get(&(node_id, key)) {
+                    Some(child_id) => {
+                        self.nodes[node_id].iter_match(match_mode)
+                            .find(|(_i, k)| *k == key)
+                            .map(|(i, k)| (k, i, *child_id))
+                    },
+                    None => None,
                 }
             },
+            (match_mode, None, Some(atom)) => {
+                self.nodes[node_id].iter_match(match_mode)
+                    .find(|(_i, k)| atom == unsafe{ self.keys.get_atom_unchecked(*k) })
+                    .map(|(i, k)| (k, i, *self.index.get(&(node_id, k)).unwrap()))
+            },
+        }
+    }
+
+    /// Returns counter of the atom represented by the list of [QueryKey].
+    /// Each key is matched by equality. Returns `0` if atom is not in the
+    /// trie.
+    pub fn multiplicity<'a, I: Iterator<Item=QueryKey<'a>>>(&self, key: I) -> usize {
+        let mut node_id = self.root;
+        for head in key {
+            match self.find_child(node_id, &head) {
+                Some((_key, _index, child_id)) => node_id = child_id,
+                None => return 0,
+            }
+        }
+        self.nodes[node_id].leaf_counter()
+    }
+
+    /// Estimate number of atoms which can be matched by the list of
+    /// [QueryKey]. Keys are followed while they can be matched by equality
+    /// only. When next key cannot be followed this way all atoms under the
+    /// current prefix are counted. Counting stops when `limit` is reached.
+    /// Returned value is an upper bound of the number of matching atoms or
+    /// `limit` whatever is less.
+    pub fn estimate<'a, I: Iterator<Item=QueryKey<'a>>>(&self, key: I, limit: usize) -> usize {
+        let mut node_id = self.root;
+        for head in key {
+            if self.nodes[node_id].has_unification_keys() {
+                return self.count_atoms(node_id, limit);
+            }
+            match self.keys.query_key(&head) {
+                (AtomMatchMode::Equality, Some(key), _atom) => {
+                    match self.index.get(&(node_id, key)) {
+                        Some(&child_id) => node_id = child_id,
+                        None => return 0,
+                    }
+                },
+                // symbols are always hashable, thus symbol without key
+                // is not in the trie
+                (AtomMatchMode::Equality, None, Some(Atom::Symbol(_))) => return 0,
+                _ => return self.count_atoms(node_id, limit),
+            }
+        }
+        self.nodes[node_id].leaf_counter().min(limit)
+    }
+
+    /// Count atoms which can be matched by the list of [QueryKey] without
+    /// matching them. It is possible when keys can be matched by equality
+    /// until the end of the list or until the rest of the list is a
+    /// sequence of distinct variables. Returns `None` when atoms cannot be
+    /// counted this way.
+    pub fn count<'a, I: Iterator<Item=QueryKey<'a>>>(&self, mut key: I) -> Option<usize> {
+        let mut node_id = self.root;
+        while let Some(head) = key.next() {
+            match self.keys.query_key(&head) {
+                (AtomMatchMode::Unification, _key, Some(Atom::Variable(var))) => {
+                    let mut vars = vec![var];
+                    for head in key.by_ref() {
+                        match head {
+                            QueryKey::Atom(Atom::Variable(var)) if !vars.contains(&var) => vars.push(var),
+                            _ => return None,
+                        }
+                    }
+                    return Some(self.count_atoms(node_id, usize::MAX));
+                },
+                _ if self.nodes[node_id].has_unification_keys() => return None,
+                (AtomMatchMode::Equality, Some(key), _atom) => {
+                    match self.index.get(&(node_id, key)) {
+                        Some(&child_id) => node_id = child_id,
+                        None => return Some(0),
+                    }
+                },
+                (AtomMatchMode::Equality, None, Some(Atom::Symbol(_))) => return Some(0),
+                _ => return None,
+            }
+        }
+        Some(D::result_count(self.nodes[node_id].leaf_counter()))
+    }
+
+    fn count_atoms(&self, node_id: NodeId, limit: usize) -> usize {
+        let mut count = 0;
+        let mut stack = vec![node_id];
+        while let Some(node_id) = stack.pop() {
+            match &self.nodes[node_id] {
+                TrieNode::Leaf(leaf_count) => {
+                    count += D::result_count(*leaf_count);
+                    if count >= limit {
+                        return limit;
+                    }
+                },
+                node => stack.extend(node.iter_all()
+                    .map(|(_index, key)| *self.index.get(&(node_id, key)).unwrap())),
+            }
+        }
+        count
+    }
+
+    /// Returns atoms of the trie which can be matched by the list of
+    /// [QueryKey] together with their counters. Each key is checked
+    /// separately, bindings of the different keys are not merged, thus
+    /// caller should match the whole atom to filter out false candidates.
+    pub fn match_candidates<'a, I>(&self, key: I) -> Vec<(Atom, usize)>
+        where I: Clone + Iterator<Item=QueryKey<'a>>
+    {
+        let mut result = Vec::new();
+        self.match_candidates_internal(self.root, key, &mut Vec::new(), &mut result);
+        result
+    }
+
+    fn match_candidates_internal<'a, 'b, I>(&'b self, node_id: NodeId, mut key: I,
+        path: &mut Vec<PathEntry<'b>>, result: &mut Vec<(Atom, usize)>)
+        where I: Clone + Iterator<Item=QueryKey<'a>>
+    {
+        let head = match key.next() {
+            Some(head) => head,
             None => {
-                D::remove_atom(&mut self.nodes[node_id]);
-                true
+                let count = self.nodes[node_id].leaf_counter();
+                if count > 0 {
+                    result.push((PathEntry::build_atom(path), count));
+                }
+                return;
+            },
+        };
+        let mut next = Vec::new();
+        match self.keys.query_key(&head) {
+            (AtomMatchMode::Equality, head_key, atom) => {
+                if let TrieNode::Leaf(_count) = self.nodes[node_id] {
+                    return;
+                }
+                match head_key {
+                    Some(head_key) if self.index.contains_key(&(node_id, head_key)) =>
+                        next.push((head_key, key.clone())),
+                    Some(_head_key) => {},
+                    // match equality nonhashable key (see TrieKeyStorage::add_atom)
+                    None => next.extend(self.nodes[node_id].iter_match(AtomMatchMode::Equality)
+                        .map(|(_index, k)| k)
+                        .filter(|&k| !k.is_start_expr() && atom == Some(unsafe{ self.keys.get_atom_unchecked(k) }))
+                        .map(|k| (k, key.clone()))),
+                }
+                if atom.is_some() {
+                    if let Some(expr_size) = head_key.and_then(|k| k.as_start_expr()) {
+                        QueryKey::skip_expression(&mut key, expr_size);
+                    }
+                    next.extend(self.nodes[node_id].iter_match(AtomMatchMode::Unification)
+                        .map(|(_index, k)| (k, key.clone())));
+                }
+            },
+            (AtomMatchMode::Unification, _head_key, Some(atom)) => match index_range(atom) {
+                Some(range) => {
+                    let ordered = self.ordered.get(&node_id).filter(|_| !range.is_empty());
+                    let in_range = ordered.into_iter()
+                        .flat_map(|ordered| ordered.range(range.clone()))
+                        .flat_map(|(_index_key, keys)| keys.iter().copied());
+                    let unifiable = self.nodes[node_id].iter_match(AtomMatchMode::Unification)
+                        .map(|(_index, k)| k);
+                    next.extend(in_range.chain(unifiable).map(|k| (k, key.clone())));
+                },
+                None => {
+                    for (entry, child_id) in self.unpack_atoms_internal(node_id) {
+                        path.push(PathEntry::Atom(entry));
+                        self.match_candidates_internal(child_id, key.clone(), path, result);
+                        path.pop();
+                    }
+                },
             },
+            (AtomMatchMode::Unification, _head_key, None) => unreachable!(),
         }
+        for (k, tail) in next {
+            let child_id = *self.index.get(&(node_id, k)).unwrap();
+            path.push(match k.as_start_expr() {
+                Some(size) => PathEntry::StartExpr(size),
+                None => PathEntry::Atom(Cow::Borrowed(unsafe{ self.keys.get_atom_unchecked(k) })),
+            });
+            self.match_candidates_internal(child_id, tail, path, result);
+            path.pop();
+        }
+    }
+
+    /// Returns memory usage report of the trie.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats::default();
+        let mut node_bytes = self.nodes.items.len() * std::mem::size_of::<Option<TrieNode>>();
+        for node in self.nodes.items.iter().flatten() {
+            stats.nodes += 1;
+            match node {
+                TrieNode::Leaf(count) if *count > 0 => {
+                    stats.atoms += count;
+                    stats.distinct_atoms += 1;
+                },
+                TrieNode::Many(keys) => node_bytes += std::mem::size_of::<TrieNodeKeys>()
+                    + keys.len() * std::mem::size_of::<TrieKey>(),
+                _ => {},
+            }
+        }
+        stats.free_nodes = self.nodes.holes.len();
+        stats.hashable_keys = self.keys.hashable.count();
+        stats.other_keys = self.keys.vec.items.iter().flatten().count();
+        stats.grounded_keys = self.keys.hashable.iter()
+            .chain(self.keys.vec.items.iter().flatten())
+            .filter(|atom| matches!(atom, Atom::Grounded(_)))
+            .count();
+        // Hashable atom is kept twice: in the vector of atoms and in the
+        // map of ids.
+        let key_bytes = stats.hashable_keys * (2 * std::mem::size_of::<Atom>() + std::mem::size_of::<usize>())
+            + self.keys.vec.items.len() * std::mem::size_of::<Option<Atom>>();
+        let index_bytes = self.index.len() * std::mem::size_of::<((NodeId, TrieKey), NodeId)>()
+            + self.ordered.values().map(|ordered| ordered.len()
+                * (std::mem::size_of::<IndexKey>() + std::mem::size_of::<Vec<TrieKey>>() + std::mem::size_of::<TrieKey>()))
+                .sum::<usize>();
+        let holes_bytes = (self.nodes.holes.len() + self.keys.vec.holes.len()) * std::mem::size_of::<usize>();
+        stats.storage_bytes = node_bytes + key_bytes + index_bytes + holes_bytes;
+        stats
+    }
+
+    /// Return `true` if trie contains atoms which can be matched with any
+    /// atom, for example variables.
+    pub fn has_root_unification_keys(&self) -> bool {
+        self.nodes[self.root].has_unification_keys()
     }
 
     /// Return `true` if trie is empty.
@@ -513,7 +1061,7 @@ const TK_MAX_EXPRESSION_SIZE: usize = 1 << 10;
 /// Compact representation of the atom from the trie. It represents each
 /// atom using single [usize] value. It keeps value of the key, key matching
 /// mode: equality or unification, key storage mode: hashable or indexed.
-#[derive(Hash, Copy, Clone, PartialEq, Eq)]
+#[derive(Hash, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct TrieKey(usize);
 
 impl TrieKey {
@@ -723,6 +1271,15 @@ impl TrieNode {
         }
     }
 
+    /// Return `true` if node has keys matched by unification.
+    fn has_unification_keys(&self) -> bool {
+        match self {
+            Self::Leaf(_) => false,
+            Self::Single(key) => key.match_mode() == AtomMatchMode::Unification,
+            Self::Many(keys) => !keys.unify.is_empty(),
+        }
+    }
+
     /// Iterate over all of keys in the list
     pub fn iter_all(&self) -> std::iter::Chain<TrieNodeIndexIter<'_>, TrieNodeIndexIter<'_>> {
         self.iter_match(AtomMatchMode::Unification)
@@ -782,6 +1339,42 @@ struct ExpressionBuilder {
     children: Vec<Atom>,
 }
 
+/// Entry of the path which is collected by [AtomTrie::match_candidates].
+enum PathEntry<'a> {
+    StartExpr(usize),
+    Atom(Cow<'a, Atom>),
+}
+
+impl PathEntry<'_> {
+    fn build_atom(path: &[PathEntry]) -> Atom {
+        let mut expr: Vec<ExpressionBuilder> = Vec::new();
+        for entry in path {
+            let mut atom = match entry {
+                PathEntry::StartExpr(size) if *size > 0 => {
+                    expr.push(ExpressionBuilder{ count: *size, children: Vec::new() });
+                    continue;
+                },
+                PathEntry::StartExpr(_size) => Atom::expr([]),
+                PathEntry::Atom(atom) => atom.as_ref().clone(),
+            };
+            loop {
+                match expr.last_mut() {
+                    None => return atom,
+                    Some(last) => {
+                        last.children.push(atom);
+                        last.count -= 1;
+                        if last.count > 0 {
+                            break;
+                        }
+                        atom = Atom::expr(expr.pop().unwrap().children);
+                    },
+                }
+            }
+        }
+        panic!("Path is expected to contain a whole atom")
+    }
+}
+
 struct TrieNodeAtomIter<'a, D: DuplicationStrategy> {
     trie: &'a AtomTrie<D>,
     node_id: NodeId,
@@ -2,16 +2,23 @@
 //! This module is intended to keep different space implementations.
 
 pub mod index;
+pub mod aggregate;
+pub mod reactive;
+pub mod view;
+pub mod merge;
 
 use std::fmt::Display;
 use std::rc::{Rc, Weak};
 use std::cell::{RefCell, Ref, RefMut};
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use hyperon_common::FlexRef;
 use hyperon_atom::*;
-use hyperon_atom::matcher::{BindingsSet, apply_bindings_to_atom_move};
-use hyperon_atom::subexpr::split_expr;
+use hyperon_atom::matcher::{BindingsSet, apply_bindings_to_atom_move, match_atoms};
+use hyperon_atom::gnd::bool::Bool;
+use aggregate::Aggregate;
+use index::MemoryStats;
 
 /// Symbol to concatenate queries to space.
 pub const COMMA_SYMBOL : Atom = sym!(",");
@@ -24,8 +31,15 @@ pub enum SpaceEvent {
     Add(Atom),
     /// Atom is removed from space.
     Remove(Atom),
+    /// Atom is removed from space the number of times at once. It is
+    /// emitted by spaces which keep atoms as a multiset, see
+    /// [index::CountDuplication].
+    RemoveCount(Atom, usize),
     /// First atom is replaced by the second one.
     Replace(Atom, Atom),
+    /// Number of modifications made by single bulk operation, see
+    /// [SpaceMut::add_all] for example.
+    Batch(Vec<SpaceEvent>),
 }
 
 /// Space modification event observer trait.
@@ -97,6 +111,9 @@ impl<T: SpaceObserver> From<Rc<RefCell<T>>> for SpaceObserverRef<T> {
 #[derive(Default)]
 pub struct SpaceCommon {
     pub observers: RefCell<Vec<Weak<RefCell<dyn SpaceObserver>>>>,
+    /// Events collected by the nested transactions which are in progress,
+    /// see [SpaceTransaction]. Flag is `true` when event can be reverted.
+    transactions: RefCell<Vec<Vec<(SpaceEvent, bool)>>>,
 }
 impl SpaceCommon {
     /// Registers space modifications `observer`. Observer is automatically deregistered when
@@ -110,7 +127,26 @@ impl SpaceCommon {
     }
 
     /// Notifies all registered observers about space modification `event`.
+    /// When transaction is in progress the event is kept until the
+    /// transaction is committed.
     pub fn notify_all_observers(&self, event: &SpaceEvent) {
+        self.notify_or_keep(event, true)
+    }
+
+    /// Notifies all registered observers about space modification `event`
+    /// which cannot be reverted, for instance removal of the expired atoms.
+    /// When transaction is in progress the event is kept until the
+    /// transaction is finished. Rolling back the transaction doesn't revert
+    /// the event, observers are notified about it anyway.
+    pub fn notify_all_observers_irreversible(&self, event: &SpaceEvent) {
+        self.notify_or_keep(event, false)
+    }
+
+    fn notify_or_keep(&self, event: &SpaceEvent, revertible: bool) {
+        if let Some(events) = self.transactions.borrow_mut().last_mut() {
+            events.push((event.clone(), revertible));
+            return;
+        }
         let mut cleanup = false;
         for observer in self.observers.borrow_mut().iter() {
             if let Some(observer) = observer.upgrade() {
@@ -123,6 +159,40 @@ impl SpaceCommon {
             self.observers.borrow_mut().retain(|w| w.strong_count() > 0);
         }
     }
+
+    /// Returns `true` if there is a transaction in progress.
+    pub fn in_transaction(&self) -> bool {
+        !self.transactions.borrow().is_empty()
+    }
+
+    fn begin_transaction(&self) {
+        self.transactions.borrow_mut().push(Vec::new());
+    }
+
+    fn take_transaction_events(&self) -> Vec<(SpaceEvent, bool)> {
+        self.transactions.borrow_mut().last_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn end_transaction(&self) -> Vec<(SpaceEvent, bool)> {
+        self.transactions.borrow_mut().pop().unwrap_or_default()
+    }
+
+    fn commit_transaction(&self) {
+        // Events of the nested transaction are passed to the outer one
+        for (event, revertible) in self.end_transaction() {
+            self.notify_or_keep(&event, revertible);
+        }
+    }
+
+    fn rollback_transaction(&self, irreversible: Vec<SpaceEvent>) {
+        // Reverting events are collected by the transaction and dropped
+        self.end_transaction();
+        for event in irreversible {
+            self.notify_all_observers_irreversible(&event);
+        }
+    }
 }
 
 impl Clone for SpaceCommon {
@@ -131,6 +201,7 @@ impl Clone for SpaceCommon {
             //We don't want to clone observers when a space is cloned, as that leads to a situation
             // where an observer can't know which space an event pertains to
             observers: RefCell::new(vec![]),
+            transactions: RefCell::new(vec![]),
         }
     }
 }
@@ -154,7 +225,8 @@ pub trait Space: std::fmt::Debug + std::fmt::Display {
     fn common(&self) -> FlexRef<'_, SpaceCommon>;
 
     /// Executes `query` on the space and returns variable bindings found.
-    /// Query may include sub-queries glued by [grounding::COMMA_SYMBOL] symbol. 
+    /// Query may include sub-queries glued by [grounding::COMMA_SYMBOL] symbol and
+    /// other query operators supported by [complex_query].
     /// Each [Bindings](crate::atom::matcher::Bindings) instance in the returned [BindingsSet]
     /// represents single result.
     ///
@@ -211,10 +283,56 @@ pub trait Space: std::fmt::Debug + std::fmt::Display {
     /// easily and should be reconstructed instead.
     fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()>;
 
+    /// Computes `aggregate` over results of the `query`. Default
+    /// implementation executes the query and aggregates the results one by
+    /// one. Space can override it to compute the value using its index.
+    /// Returns an error when values cannot be aggregated and `None` when
+    /// there is no value to return.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_space::Space;
+    /// use hyperon_space::aggregate::Aggregate;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C")]);
+    ///
+    /// assert_eq!(space.aggregate(&expr!("A" x), &Aggregate::Count), Ok(Some(Atom::gnd(Number::Integer(2)))));
+    /// ```
+    fn aggregate(&self, query: &Atom, aggregate: &Aggregate) -> Result<Option<Atom>, String> {
+        aggregate.apply(self.query(query))
+    }
+
+    /// Returns statistics which is used to plan execution of the queries
+    /// with sub-queries, see [complex_query_with_statistics]. This method is
+    /// optional. Return `None` if statistics is not available.
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        None
+    }
+
+    /// Returns memory usage report of the space storage which can be used
+    /// for monitoring. This method is optional. Return `None` if report is
+    /// not available.
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        None
+    }
+
     /// Returns an `&dyn `[Any](std::any::Any) for spaces where this is possible
     fn as_any(&self) -> &dyn std::any::Any;
 }
 
+/// Statistics about atoms of the space which is used to plan queries.
+pub trait SpaceStatistics {
+    /// Estimates number of atoms which can be matched with `pattern`.
+    /// Returned value should be an upper bound of the number of matching
+    /// atoms. Implementation may stop counting when `limit` is reached and
+    /// return `limit` in such case.
+    fn estimate_matches(&self, pattern: &Atom, limit: usize) -> usize;
+}
+
 /// Mutable space trait.
 pub trait SpaceMut: Space {
     /// Adds `atom` into space.
@@ -274,6 +392,77 @@ pub trait SpaceMut: Space {
     /// ```
     fn replace(&mut self, from: &Atom, to: Atom) -> bool;
 
+    /// Adds all `atoms` into space. Default implementation calls
+    /// [SpaceMut::add] for each atom. Implementations are encouraged to
+    /// notify observers by a single [SpaceEvent::Batch] event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::sym;
+    /// use hyperon_space::*;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::new();
+    ///
+    /// SpaceMut::add_all(&mut space, vec![sym!("A"), sym!("B")]);
+    ///
+    /// assert_eq!(space.atom_count(), Some(2));
+    /// ```
+    fn add_all(&mut self, atoms: Vec<Atom>) {
+        for atom in atoms {
+            self.add(atom);
+        }
+    }
+
+    /// Removes all `atoms` from space. Returns number of atoms removed.
+    /// Default implementation calls [SpaceMut::remove] for each atom.
+    fn remove_all(&mut self, atoms: &[Atom]) -> usize {
+        atoms.iter().filter(|atom| self.remove(atom)).count()
+    }
+
+    /// Removes all atoms which match `pattern` and returns them. Default
+    /// implementation visits the space to find matching atoms and calls
+    /// [SpaceMut::remove] for each of them. Nothing is removed when space
+    /// cannot be visited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::expr;
+    /// use hyperon_space::*;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C"), expr!("B" "C")]);
+    ///
+    /// let removed = SpaceMut::remove_matching(&mut space, &expr!("A" x));
+    ///
+    /// assert_eq!(removed.len(), 2);
+    /// assert_eq!(space.atom_count(), Some(1));
+    /// ```
+    fn remove_matching(&mut self, pattern: &Atom) -> Vec<Atom> {
+        let mut matched = Vec::new();
+        let _ = self.visit(&mut |atom: Cow<Atom>| {
+            if match_atoms(pattern, &atom).next().is_some() {
+                matched.push(atom.into_owned());
+            }
+        });
+        matched.retain(|atom| self.remove(atom));
+        matched
+    }
+
+    /// Compacts the space storage releasing memory which is left after the
+    /// atoms are removed, see [Space::memory_stats]. Atoms of the space are
+    /// not changed. Default implementation does nothing.
+    fn compact_memory(&mut self) {}
+
+    /// Adds `atom` which is removed from the space after `ttl` passes. This
+    /// method is optional. Returns an error if space doesn't support
+    /// expiring atoms.
+    fn add_with_ttl(&mut self, _atom: Atom, _ttl: std::time::Duration) -> Result<(), String> {
+        Err(format!("Space {} doesn't support expiring atoms", self))
+    }
+
     /// Returns an `&mut dyn `[Any](std::any::Any) for spaces where this is possible
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
 }
@@ -295,6 +484,138 @@ impl DynSpace {
     pub fn common(&self) -> FlexRef<'_, SpaceCommon> {
         FlexRef::from_ref_cell(Ref::map(self.0.borrow(), |space| space.common().into_simple()))
     }
+    /// Returns atoms added and removed in `other` space comparing to this one.
+    /// See [diff_spaces].
+    pub fn diff(&self, other: &DynSpace) -> Result<SpaceDiff, ()> {
+        diff_spaces(&*self.borrow(), &*other.borrow())
+    }
+    /// Starts a transaction on the space. See [SpaceTransaction].
+    pub fn begin_transaction(&self) -> SpaceTransaction {
+        self.common().begin_transaction();
+        SpaceTransaction{ space: self.clone(), finished: false }
+    }
+    /// Returns weak reference to the space which doesn't keep it alive.
+    pub fn downgrade(&self) -> WeakDynSpace {
+        WeakDynSpace(Rc::downgrade(&self.0))
+    }
+}
+
+/// Weak reference to the [DynSpace], see [DynSpace::downgrade].
+#[derive(Clone, Debug)]
+pub struct WeakDynSpace(Weak<RefCell<dyn SpaceMut>>);
+
+impl WeakDynSpace {
+    /// Returns the space or `None` if it is already dropped.
+    pub fn upgrade(&self) -> Option<DynSpace> {
+        self.0.upgrade().map(DynSpace)
+    }
+}
+
+/// Transaction started by [DynSpace::begin_transaction]. Observers of the
+/// space are notified about modifications made during the transaction only
+/// after it is committed. Transaction which is rolled back or dropped without
+/// committing reverts the modifications. Transactions can be nested.
+///
+/// Modifications are reverted using the [SpaceEvent]s emitted by the space,
+/// thus space implementation should notify observers about each change. Any
+/// modification of the space made while transaction is in progress is a part
+/// of the transaction, including ones made via other references to the space.
+/// Events emitted by [SpaceCommon::notify_all_observers_irreversible] are
+/// not reverted.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::sym;
+/// use hyperon_space::*;
+/// use hyperon::space::grounding::*;
+///
+/// let space = DynSpace::new(GroundingSpace::new());
+/// space.borrow_mut().add(sym!("A"));
+///
+/// let transaction = space.begin_transaction();
+/// transaction.add(sym!("B"));
+/// transaction.replace(&sym!("A"), sym!("C"));
+/// transaction.rollback();
+///
+/// assert_eq!(space.borrow().atom_count(), Some(1));
+/// assert!(!space.borrow().query(&sym!("A")).is_empty());
+/// ```
+pub struct SpaceTransaction {
+    space: DynSpace,
+    finished: bool,
+}
+
+impl SpaceTransaction {
+    /// Returns the space modified by the transaction.
+    pub fn space(&self) -> &DynSpace {
+        &self.space
+    }
+    /// Adds `atom` into the space.
+    pub fn add(&self, atom: Atom) {
+        self.space.borrow_mut().add(atom)
+    }
+    /// Removes `atom` from the space, returns `true` if atom was removed.
+    pub fn remove(&self, atom: &Atom) -> bool {
+        self.space.borrow_mut().remove(atom)
+    }
+    /// Replaces `from` atom by `to` atom, returns `true` if atom was replaced.
+    pub fn replace(&self, from: &Atom, to: Atom) -> bool {
+        self.space.borrow_mut().replace(from, to)
+    }
+    /// Commits the transaction and notifies observers about modifications.
+    pub fn commit(mut self) {
+        self.finished = true;
+        self.space.common().commit_transaction();
+    }
+    /// Reverts all modifications made during the transaction.
+    pub fn rollback(mut self) {
+        self.finished = true;
+        self.revert();
+    }
+
+    fn revert(&self) {
+        let events = self.space.common().take_transaction_events();
+        let mut irreversible = Vec::new();
+        {
+            let mut space = self.space.borrow_mut();
+            for (event, revertible) in events.into_iter().rev() {
+                if revertible {
+                    Self::revert_event(&mut *space, event);
+                } else {
+                    irreversible.push(event);
+                }
+            }
+        }
+        irreversible.reverse();
+        self.space.common().rollback_transaction(irreversible);
+    }
+
+    fn revert_event(space: &mut dyn SpaceMut, event: SpaceEvent) {
+        match event {
+            SpaceEvent::Add(atom) => { space.remove(&atom); },
+            SpaceEvent::Remove(atom) => space.add(atom),
+            SpaceEvent::RemoveCount(atom, count) => {
+                for _ in 0..count {
+                    space.add(atom.clone());
+                }
+            },
+            SpaceEvent::Replace(from, to) => { space.replace(&to, from); },
+            SpaceEvent::Batch(events) => {
+                for event in events.into_iter().rev() {
+                    Self::revert_event(space, event);
+                }
+            },
+        }
+    }
+}
+
+impl Drop for SpaceTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.revert();
+        }
+    }
 }
 
 impl<T: SpaceMut + 'static> From<T> for DynSpace {
@@ -337,32 +658,377 @@ impl CustomMatch for DynSpace {
     }
 }
 
+impl<'a> TryFrom<&'a Atom> for &'a DynSpace {
+    type Error = &'static str;
+    fn try_from(atom: &'a Atom) -> Result<Self, Self::Error> {
+        atom.as_gnd::<DynSpace>().ok_or("Atom is not a space")
+    }
+}
+
+impl gnd::op::MettaType for DynSpace {
+    fn metta_type() -> Atom {
+        ATOM_TYPE_SPACE
+    }
+}
+
+impl gnd::op::MettaType for &DynSpace {
+    fn metta_type() -> Atom {
+        ATOM_TYPE_SPACE
+    }
+}
+
+impl gnd::op::IntoOpResult for DynSpace {
+    fn into_op_result(self) -> Result<Vec<Atom>, ExecError> {
+        Ok(vec![Atom::gnd(self)])
+    }
+}
+
+/// Difference between the atoms of two spaces returned by [diff_spaces].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpaceDiff {
+    /// Atoms which are present in the new space only.
+    pub added: Vec<Atom>,
+    /// Atoms which are present in the old space only.
+    pub removed: Vec<Atom>,
+}
+
+impl SpaceDiff {
+    /// Returns `true` if spaces contain the same atoms.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Applies the difference to the `space`: removes [SpaceDiff::removed]
+    /// atoms and adds [SpaceDiff::added] atoms. Applying the difference
+    /// returned by `diff_spaces(old, new)` to the `old` space makes it equal
+    /// to the `new` one. Returns `false` if some of the removed atoms are not
+    /// found in the `space`.
+    pub fn apply<S: SpaceMut + ?Sized>(&self, space: &mut S) -> bool {
+        let mut all_removed = true;
+        for atom in &self.removed {
+            all_removed &= space.remove(atom);
+        }
+        for atom in &self.added {
+            space.add(atom.clone());
+        }
+        all_removed
+    }
+}
+
+impl Display for SpaceDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let removed = self.removed.iter().map(|atom| ('-', atom));
+        let added = self.added.iter().map(|atom| ('+', atom));
+        removed.chain(added).enumerate().try_for_each(|(i, (sign, atom))| {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} {}", sign, atom)
+        })
+    }
+}
+
+/// Compares atoms of the `old` and `new` spaces. Spaces are compared as
+/// multisets of atoms, thus duplicates are taken into account. Returns
+/// `Err(())` if one of the spaces cannot be visited.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::expr;
+/// use hyperon_space::{diff_spaces, SpaceDiff};
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let old = GroundingSpace::from_vec(vec![expr!("A"), expr!("B" "C")]);
+/// let new = GroundingSpace::from_vec(vec![expr!("B" "C"), expr!("D")]);
+///
+/// assert_eq!(diff_spaces(&old, &new), Ok(SpaceDiff{
+///     added: vec![expr!("D")],
+///     removed: vec![expr!("A")],
+/// }));
+/// ```
+pub fn diff_spaces<A, B>(old: &A, new: &B) -> Result<SpaceDiff, ()>
+    where A: Space + ?Sized, B: Space + ?Sized
+{
+    let mut old_atoms = Vec::new();
+    old.visit(&mut |atom: Cow<Atom>| old_atoms.push(atom.into_owned()))?;
+    let mut unmatched: HashMap<&Atom, usize> = HashMap::new();
+    for atom in &old_atoms {
+        *unmatched.entry(atom).or_default() += 1;
+    }
+    let mut take = |atom: &Atom| match unmatched.get_mut(atom) {
+        Some(count) if *count > 0 => { *count -= 1; true },
+        _ => false,
+    };
+    let mut added = Vec::new();
+    new.visit(&mut |atom: Cow<Atom>| {
+        if !take(&atom) {
+            added.push(atom.into_owned());
+        }
+    })?;
+    let removed = old_atoms.iter().filter(|atom| take(atom)).cloned().collect();
+    Ok(SpaceDiff{ added, removed })
+}
+
+// Query operators are prefixed by `query:` to not change the meaning of the
+// patterns like `(not $x)` or `(or $x $y)` which are used to match data.
+
+/// Symbol of the negation as failure query operator.
+pub const NOT_SYMBOL : Atom = sym!("query:not");
+/// Symbol of the disjunction query operator.
+pub const OR_SYMBOL : Atom = sym!("query:or");
+/// Symbol of the optional pattern query operator.
+pub const OPTIONAL_SYMBOL : Atom = sym!("query:optional");
+/// Symbol of the filter query operator.
+pub const FILTER_SYMBOL : Atom = sym!("query:filter");
+
+/// Query operators which are supported by [complex_query].
+pub(crate) enum QueryOp<'a> {
+    /// `(, <query> ...)`: all sub-queries should be matched.
+    And(Vec<&'a Atom>),
+    /// `(query:or <query> ...)`: results of all sub-queries are returned.
+    Or(Vec<&'a Atom>),
+    /// `(query:not <query>)`: matched when sub-query has no results.
+    Not(&'a Atom),
+    /// `(query:optional <query>)`: results of the sub-query or the previous
+    /// bindings when sub-query has no results.
+    Optional(&'a Atom),
+    /// `(query:filter (<grounded function> <arg> ...))`: matched when function
+    /// returns `True`.
+    Filter(&'a Atom),
+    /// Any other atom is a pattern to be matched with the space.
+    Pattern(&'a Atom),
+}
+
+impl<'a> QueryOp<'a> {
+    pub(crate) fn parse(query: &'a Atom) -> Self {
+        match query {
+            Atom::Expression(expr) => match expr.children().as_slice() {
+                [op, args @ ..] if *op == COMMA_SYMBOL => Self::And(args.iter().collect()),
+                [op, args @ ..] if *op == OR_SYMBOL && !args.is_empty() => Self::Or(args.iter().collect()),
+                [op, arg] if *op == NOT_SYMBOL => Self::Not(arg),
+                [op, arg] if *op == OPTIONAL_SYMBOL => Self::Optional(arg),
+                [op, arg] if *op == FILTER_SYMBOL => Self::Filter(arg),
+                _ => Self::Pattern(query),
+            },
+            _ => Self::Pattern(query),
+        }
+    }
+
+    /// Returns true when operator is evaluated after all other sub-queries
+    /// of the conjunction.
+    fn is_deferred(&self) -> bool {
+        matches!(self, Self::Not(_) | Self::Optional(_) | Self::Filter(_))
+    }
+}
+
+/// Returns `true` if `query` contains query operators supported by
+/// [complex_query], and `false` if it is a single pattern.
+pub fn is_complex_query(query: &Atom) -> bool {
+    !matches!(QueryOp::parse(query), QueryOp::Pattern(_))
+}
+
+/// Executes `query` which may include sub-queries. Sub-queries are executed
+/// left to right using `single_query` and results of the previous sub-queries
+/// are applied to the next one. Query can be constructed using the following
+/// operators:
+/// - `(, <query> ...)` - conjunction, all sub-queries should be matched;
+/// - `(query:or <query> ...)` - disjunction, results of all sub-queries are
+///   returned;
+/// - `(query:not <query>)` - negation as failure, matched when sub-query has
+///   no results;
+/// - `(query:optional <query>)` - results of the sub-query or no new bindings
+///   when sub-query has no results;
+/// - `(query:filter (<function> <arg> ...))` - matched when grounded
+///   function returns `True` after variables are replaced by their values.
+///
+/// Negations, optional sub-queries and filters are evaluated after all other
+/// sub-queries of the conjunction in the order they are written. Thus their
+/// variables are bound by the rest of the conjunction.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::{expr, bind_set, sym};
+/// use hyperon_space::complex_query;
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C"),
+///     expr!("C" "D")]);
+///
+/// let result = complex_query(&expr!("," ("A" x) ("query:not" (x "D"))), |query| space.query(query));
+/// assert_eq!(result, bind_set![{x: sym!("B")}]);
+///
+/// let result = complex_query(&expr!("," ("A" x) ("query:optional" (x y))), |query| space.query(query));
+/// assert_eq!(result, bind_set![{x: sym!("B")}, {x: sym!("C"), y: sym!("D")}]);
+/// ```
 pub fn complex_query<F>(query: &Atom, single_query: F) -> BindingsSet
+where
+    F: Fn(&Atom) -> BindingsSet,
+{
+    complex_query_with_statistics(query, None, single_query)
+}
+
+/// Executes `query` like [complex_query] does. When `statistics` is passed
+/// then sub-queries of conjunctions are reordered: after applying the
+/// bindings found so far the sub-query with the least number of estimated
+/// matches is executed first. Thus variables bound by the previous
+/// sub-queries drive the lookup of the next one. Sub-queries with equal
+/// estimations are executed in the original order.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::{expr, bind_set, sym};
+/// use hyperon_space::{Space, complex_query_with_statistics};
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C"),
+///     expr!("B" "D")]);
+/// let query = expr!("," (x y) ("A" x));
+///
+/// let result = complex_query_with_statistics(&query, space.statistics(),
+///     |query| space.query(query));
+///
+/// assert_eq!(result, bind_set![{x: sym!("B"), y: sym!("C")}, {x: sym!("B"), y: sym!("D")}]);
+/// ```
+pub fn complex_query_with_statistics<F>(query: &Atom, statistics: Option<&dyn SpaceStatistics>,
+    single_query: F) -> BindingsSet
 where
     F: Fn(&Atom) -> BindingsSet,
 {
     log::debug!("complex_query: query: {}", query);
-    match split_expr(query) {
-        // Cannot match with COMMA_SYMBOL here, because Rust allows
-        // it only when Atom has PartialEq and Eq derived.
-        Some((sym @ Atom::Symbol(_), args)) if *sym == COMMA_SYMBOL => {
-            args.fold(BindingsSet::single(),
-                |mut acc, query| {
-                    let result = if acc.is_empty() {
-                        acc
-                    } else {
-                        acc.drain(0..).flat_map(|prev| -> BindingsSet {
-                            let query = matcher::apply_bindings_to_atom_move(query.clone(), &prev);
-                            let mut res = single_query(&query);
-                            res.drain(0..)
-                                .flat_map(|next| next.merge(&prev))
-                                .collect()
-                        }).collect()
-                    };
-                    log::debug!("ModuleSpace::query: current result: {}", result);
-                    result
-                })
+    let result = match QueryOp::parse(query) {
+        QueryOp::Pattern(query) => single_query(query),
+        _ => query_with_bindings(query, matcher::Bindings::new(), statistics, &single_query),
+    };
+    log::debug!("complex_query: result: {}", result);
+    result
+}
+
+fn query_with_bindings<F>(query: &Atom, prev: matcher::Bindings,
+    statistics: Option<&dyn SpaceStatistics>, single_query: &F) -> BindingsSet
+where
+    F: Fn(&Atom) -> BindingsSet,
+{
+    match QueryOp::parse(query) {
+        QueryOp::And(queries) => {
+            let (deferred, queries) = queries.into_iter()
+                .partition::<Vec<&Atom>, _>(|query| QueryOp::parse(query).is_deferred());
+            conjunction(queries, &deferred, prev, statistics, single_query)
+        },
+        QueryOp::Or(queries) => queries.into_iter()
+            .flat_map(|query| query_with_bindings(query, prev.clone(), statistics, single_query))
+            .collect(),
+        QueryOp::Not(query) => {
+            if query_with_bindings(query, prev.clone(), statistics, single_query).is_empty() {
+                BindingsSet::from(prev)
+            } else {
+                BindingsSet::empty()
+            }
+        },
+        QueryOp::Optional(query) => {
+            let result = query_with_bindings(query, prev.clone(), statistics, single_query);
+            if result.is_empty() {
+                BindingsSet::from(prev)
+            } else {
+                result
+            }
+        },
+        QueryOp::Filter(predicate) => {
+            let predicate = matcher::apply_bindings_to_atom_move(predicate.clone(), &prev);
+            if is_filter_passed(&predicate) {
+                BindingsSet::from(prev)
+            } else {
+                BindingsSet::empty()
+            }
+        },
+        QueryOp::Pattern(query) => {
+            let query = matcher::apply_bindings_to_atom_move(query.clone(), &prev);
+            let mut res = single_query(&query);
+            res.drain(0..)
+                .flat_map(|next| next.merge(&prev))
+                .collect()
+        },
+    }
+}
+
+fn conjunction<F>(mut queries: Vec<&Atom>, deferred: &[&Atom], prev: matcher::Bindings,
+    statistics: Option<&dyn SpaceStatistics>, single_query: &F) -> BindingsSet
+where
+    F: Fn(&Atom) -> BindingsSet,
+{
+    if queries.is_empty() {
+        return deferred.iter().fold(BindingsSet::from(prev), |mut acc, query| {
+            acc.drain(0..)
+                .flat_map(|prev| query_with_bindings(query, prev, statistics, single_query))
+                .collect()
+        });
+    }
+    let next = match statistics {
+        Some(statistics) => cheapest_query(&queries, &prev, statistics),
+        None => 0,
+    };
+    let query = queries.remove(next);
+    let mut res = query_with_bindings(query, prev, statistics, single_query);
+    log::trace!("complex_query: query: {}, current result: {}", query, res);
+    res.drain(0..)
+        .flat_map(|next| conjunction(queries.clone(), deferred, next, statistics, single_query))
+        .collect()
+}
+
+/// Returns index of the query with the least estimated number of matches.
+fn cheapest_query(queries: &[&Atom], prev: &matcher::Bindings, statistics: &dyn SpaceStatistics) -> usize {
+    let mut best = 0;
+    let mut limit = usize::MAX;
+    for (i, query) in queries.iter().enumerate() {
+        let estimation = estimate_query(query, prev, statistics, limit);
+        if estimation < limit {
+            limit = estimation;
+            best = i;
+        }
+        if limit == 0 {
+            break;
+        }
+    }
+    best
+}
+
+fn estimate_query(query: &Atom, prev: &matcher::Bindings, statistics: &dyn SpaceStatistics, limit: usize) -> usize {
+    match QueryOp::parse(query) {
+        QueryOp::Pattern(query) => {
+            let query = matcher::apply_bindings_to_atom_move(query.clone(), prev);
+            statistics.estimate_matches(&query, limit)
+        },
+        QueryOp::Or(queries) => queries.into_iter().fold(0, |count, query| {
+            if count < limit {
+                count + estimate_query(query, prev, statistics, limit - count)
+            } else {
+                limit
+            }
+        }),
+        _ => limit,
+    }
+}
+
+/// Executes grounded function from the `predicate` and checks that it
+/// returns `True`.
+fn is_filter_passed(predicate: &Atom) -> bool {
+    let result = match predicate {
+        Atom::Expression(expr) => match expr.children().as_slice() {
+            [Atom::Grounded(op), args @ ..] => match op.as_grounded().as_execute() {
+                Some(executable) => executable.execute(args),
+                None => return false,
+            },
+            _ => return false,
+        },
+        _ => return false,
+    };
+    match result {
+        Ok(results) => results.iter().any(|atom| Bool::from_atom(atom) == Some(Bool(true))),
+        Err(err) => {
+            log::debug!("complex_query: filter {} returned error: {:?}", predicate, err);
+            false
         },
-        _ => single_query(query),
     }
 }
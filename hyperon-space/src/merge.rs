@@ -0,0 +1,245 @@
+//! Three-way merge of the spaces. Changes made in two spaces comparing to
+//! the common base snapshot are calculated using [diff_spaces] and combined
+//! into a single list of [SpaceEvent]s which can be replayed on the base
+//! space or any other space.
+//!
+//! Atom removed from the base and added atom are considered a replacement
+//! when they are expressions with the same head and number of children and
+//! less than half of the removed atom is changed, see [diff_atoms]. For
+//! example `(age Bob 36)` is a replacement of `(age Bob 35)` while
+//! `(age Bob 35)` and `(age Ann 40)` are unrelated. Atom which is removed
+//! on one side and replaced on the other one, or replaced by different atoms
+//! on both sides, is reported as a [MergeConflict] and kept unchanged.
+
+use hyperon_atom::*;
+use hyperon_atom::diff::{diff_atoms, atom_size};
+
+use std::fmt::Display;
+use std::collections::HashMap;
+
+use crate::{Space, SpaceMut, SpaceEvent, SpaceDiff, DynSpace, diff_spaces};
+
+/// Change of the base atom which conflicts with the change on the other side.
+/// `None` means the atom is removed, `Some(atom)` means it is replaced by
+/// the `atom`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Atom of the base space.
+    pub atom: Atom,
+    /// Change of the atom in the first modified space.
+    pub ours: Option<Atom>,
+    /// Change of the atom in the second modified space.
+    pub theirs: Option<Atom>,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let change = |change: &Option<Atom>| match change {
+            Some(atom) => format!("replaced by {}", atom),
+            None => "removed".into(),
+        };
+        write!(f, "{}: {} vs {}", self.atom, change(&self.ours), change(&self.theirs))
+    }
+}
+
+/// Result of the three-way merge returned by [merge_spaces].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpaceMerge {
+    /// Changes which should be applied to the base space to get the merged
+    /// one.
+    pub events: Vec<SpaceEvent>,
+    /// Changes which cannot be merged automatically.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl SpaceMerge {
+    /// Returns `true` if some changes cannot be merged.
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    /// Returns merged changes as a single [SpaceEvent::Batch].
+    pub fn batch(&self) -> SpaceEvent {
+        SpaceEvent::Batch(self.events.clone())
+    }
+
+    /// Applies merged changes to the `space`. Applying the changes to the
+    /// base space makes it the merged space. Returns `false` if some of the
+    /// removed or replaced atoms are not found in the `space`.
+    pub fn apply<S: SpaceMut + ?Sized>(&self, space: &mut S) -> bool {
+        self.events.iter().fold(true, |applied, event| apply_event(space, event) && applied)
+    }
+
+    /// Replays merged changes on the `target` space, see [SpaceMerge::apply].
+    pub fn replay(&self, target: &DynSpace) -> bool {
+        self.apply(&mut *target.borrow_mut())
+    }
+}
+
+fn apply_event<S: SpaceMut + ?Sized>(space: &mut S, event: &SpaceEvent) -> bool {
+    match event {
+        SpaceEvent::Add(atom) => { space.add(atom.clone()); true },
+        SpaceEvent::Remove(atom) => space.remove(atom),
+        SpaceEvent::RemoveCount(atom, count) => (0..*count)
+            .fold(true, |applied, _| space.remove(atom) && applied),
+        SpaceEvent::Replace(from, to) => space.replace(from, to.clone()),
+        SpaceEvent::Batch(events) => events.iter()
+            .fold(true, |applied, event| apply_event(space, event) && applied),
+    }
+}
+
+/// Changes of the single side split into replacements, removals and
+/// additions.
+struct SideChanges {
+    replaced: Vec<(Atom, Atom)>,
+    removed: Vec<Atom>,
+    added: Vec<Atom>,
+}
+
+/// Returns head and number of children of the expression. Only expressions
+/// with the same head and number of children can replace each other.
+fn head_key(atom: &Atom) -> Option<(&Atom, usize)> {
+    match atom {
+        Atom::Expression(expr) => expr.children().first().map(|head| (head, expr.children().len())),
+        _ => None,
+    }
+}
+
+impl From<SpaceDiff> for SideChanges {
+    fn from(diff: SpaceDiff) -> Self {
+        let SpaceDiff{ added, removed: base } = diff;
+        let mut by_head: HashMap<(Atom, usize), Vec<usize>> = HashMap::new();
+        for (i, atom) in added.iter().enumerate() {
+            if let Some((head, len)) = head_key(atom) {
+                by_head.entry((head.clone(), len)).or_default().push(i);
+            }
+        }
+        let mut added: Vec<Option<Atom>> = added.into_iter().map(Some).collect();
+        let mut replaced = Vec::new();
+        let mut removed = Vec::new();
+        for atom in base {
+            let size = atom_size(&atom);
+            let candidates = head_key(&atom)
+                .and_then(|(head, len)| by_head.get_mut(&(head.clone(), len)));
+            let similar = candidates.and_then(|candidates| {
+                let (pos, _) = candidates.iter().enumerate()
+                    .map(|(pos, &i)| (pos, diff_atoms(&atom, added[i].as_ref().unwrap()).distance()))
+                    .filter(|(_, distance)| distance * 2 < size)
+                    .min_by_key(|(_, distance)| *distance)?;
+                Some(candidates.remove(pos))
+            });
+            match similar {
+                Some(i) => replaced.push((atom, added[i].take().unwrap())),
+                None => removed.push(atom),
+            }
+        }
+        Self{ replaced, removed, added: added.into_iter().flatten().collect() }
+    }
+}
+
+/// Changes indexed by the changed base atom. Changes taken by
+/// [Changes::take] are not returned by [Changes::rest].
+struct Changes<T> {
+    items: Vec<Option<T>>,
+    index: HashMap<Atom, Vec<usize>>,
+}
+
+impl<T> Changes<T> {
+    fn new<F: Fn(&T) -> &Atom>(items: Vec<T>, key: F) -> Self {
+        let mut index: HashMap<Atom, Vec<usize>> = HashMap::new();
+        for (i, item) in items.iter().enumerate().rev() {
+            index.entry(key(item).clone()).or_default().push(i);
+        }
+        Self{ items: items.into_iter().map(Some).collect(), index }
+    }
+
+    fn take(&mut self, atom: &Atom) -> Option<T> {
+        let i = self.index.get_mut(atom)?.pop()?;
+        self.items[i].take()
+    }
+
+    fn rest(self) -> impl Iterator<Item=T> {
+        self.items.into_iter().flatten()
+    }
+}
+
+/// Merges changes made in the `ours` and `theirs` spaces comparing to the
+/// `base` space. Changes made on both sides are merged once. Returns
+/// `Err(())` if one of the spaces cannot be visited.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::merge::{merge_spaces, MergeConflict};
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let base = GroundingSpace::from_vec(vec![expr!("age" "Bob" "35"), expr!("likes" "Bob" "tea")]);
+/// let ours = GroundingSpace::from_vec(vec![expr!("age" "Bob" "36"), expr!("likes" "Bob" "tea")]);
+/// let theirs = GroundingSpace::from_vec(vec![expr!("age" "Bob" "35")]);
+///
+/// let merge = merge_spaces(&base, &ours, &theirs).unwrap();
+///
+/// assert_eq!(merge.events, vec![SpaceEvent::Replace(expr!("age" "Bob" "35"), expr!("age" "Bob" "36")),
+///     SpaceEvent::Remove(expr!("likes" "Bob" "tea"))]);
+/// assert!(!merge.has_conflicts());
+///
+/// let theirs = GroundingSpace::from_vec(vec![expr!("likes" "Bob" "tea")]);
+/// let merge = merge_spaces(&base, &ours, &theirs).unwrap();
+///
+/// assert!(merge.events.is_empty());
+/// assert_eq!(merge.conflicts, vec![MergeConflict{ atom: expr!("age" "Bob" "35"),
+///     ours: Some(expr!("age" "Bob" "36")), theirs: None }]);
+/// ```
+pub fn merge_spaces<B, O, T>(base: &B, ours: &O, theirs: &T) -> Result<SpaceMerge, ()>
+    where B: Space + ?Sized, O: Space + ?Sized, T: Space + ?Sized
+{
+    let ours = SideChanges::from(diff_spaces(base, ours)?);
+    let theirs = SideChanges::from(diff_spaces(base, theirs)?);
+    let mut their_replaced = Changes::new(theirs.replaced, |(from, _)| from);
+    let mut their_removed = Changes::new(theirs.removed, |atom| atom);
+    let mut their_added = Changes::new(theirs.added, |atom| atom);
+    let mut events = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (from, to) in ours.replaced {
+        if let Some((_, their_to)) = their_replaced.take(&from) {
+            if their_to == to {
+                events.push(SpaceEvent::Replace(from, to));
+            } else {
+                conflicts.push(MergeConflict{ atom: from, ours: Some(to), theirs: Some(their_to) });
+            }
+        } else if their_removed.take(&from).is_some() {
+            conflicts.push(MergeConflict{ atom: from, ours: Some(to), theirs: None });
+        } else {
+            events.push(SpaceEvent::Replace(from, to));
+        }
+    }
+    for atom in ours.removed {
+        if let Some((_, their_to)) = their_replaced.take(&atom) {
+            conflicts.push(MergeConflict{ atom, ours: None, theirs: Some(their_to) });
+        } else {
+            their_removed.take(&atom);
+            events.push(SpaceEvent::Remove(atom));
+        }
+    }
+    events.extend(their_replaced.rest().map(|(from, to)| SpaceEvent::Replace(from, to)));
+    events.extend(their_removed.rest().map(SpaceEvent::Remove));
+    for atom in ours.added {
+        their_added.take(&atom);
+        events.push(SpaceEvent::Add(atom));
+    }
+    events.extend(their_added.rest().map(SpaceEvent::Add));
+
+    Ok(SpaceMerge{ events, conflicts })
+}
+
+/// Merges changes made in the `ours` and `theirs` spaces comparing to the
+/// `base` space and replays them on the `target` space, see [merge_spaces].
+/// Returns conflicts which are not applied.
+pub fn sync_spaces(base: &DynSpace, ours: &DynSpace, theirs: &DynSpace, target: &DynSpace) -> Result<Vec<MergeConflict>, ()> {
+    let merge = merge_spaces(&*base.borrow(), &*ours.borrow(), &*theirs.borrow())?;
+    merge.replay(target);
+    Ok(merge.conflicts)
+}
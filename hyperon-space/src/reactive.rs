@@ -0,0 +1,373 @@
+//! Standing queries which results are maintained incrementally while the
+//! space is modified. [StandingQuery] is registered as a [SpaceObserver] and
+//! collects [QueryDelta] of the added and retracted results.
+//!
+//! Observers are notified while the space is borrowed, thus standing query
+//! cannot query the space itself. Instead it keeps its own memory of the
+//! atoms which match patterns of the query and evaluates the query against
+//! it.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::{Bindings, BindingsSet, match_atoms, apply_bindings_to_atom_move};
+use std::collections::{HashMap, HashSet};
+
+use crate::{Space, SpaceEvent, SpaceObserver, SpaceObserverRef, DynSpace, QueryOp,
+    COMMA_SYMBOL, complex_query};
+use crate::index::{AtomIndex, AllowDuplication};
+
+/// Results added and retracted since the last time delta was taken.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryDelta {
+    /// New results of the query.
+    pub added: Vec<Bindings>,
+    /// Results which are not valid anymore.
+    pub removed: Vec<Bindings>,
+}
+
+impl QueryDelta {
+    /// Returns `true` if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Set of the query results. Each result is identified by the key which is
+/// an expression of the values of the query variables, see
+/// [StandingQuery::result_key].
+#[derive(Default)]
+struct ResultSet {
+    results: Vec<Bindings>,
+    keys: Vec<Atom>,
+    positions: HashMap<Atom, usize>,
+}
+
+impl ResultSet {
+    fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    fn contains(&self, key: &Atom) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    fn get(&self, key: &Atom) -> Option<&Bindings> {
+        self.positions.get(key).map(|&position| &self.results[position])
+    }
+
+    fn insert(&mut self, key: Atom, bindings: Bindings) -> bool {
+        if self.positions.contains_key(&key) {
+            return false;
+        }
+        self.positions.insert(key.clone(), self.results.len());
+        self.keys.push(key);
+        self.results.push(bindings);
+        true
+    }
+
+    fn remove(&mut self, key: &Atom) -> Option<Bindings> {
+        let position = self.positions.remove(key)?;
+        self.keys.swap_remove(position);
+        if let Some(moved) = self.keys.get(position) {
+            self.positions.insert(moved.clone(), position);
+        }
+        Some(self.results.swap_remove(position))
+    }
+
+    fn iter(&self) -> impl Iterator<Item=(&Atom, &Bindings)> {
+        self.keys.iter().zip(self.results.iter())
+    }
+}
+
+/// Results added and retracted since the last time delta was taken.
+#[derive(Default)]
+struct PendingDelta {
+    added: ResultSet,
+    removed: ResultSet,
+}
+
+impl PendingDelta {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    fn add(&mut self, key: Atom, bindings: Bindings) {
+        if self.removed.remove(&key).is_none() {
+            self.added.insert(key, bindings);
+        }
+    }
+
+    fn remove(&mut self, key: Atom, bindings: Bindings) {
+        if self.added.remove(&key).is_none() {
+            self.removed.insert(key, bindings);
+        }
+    }
+
+    fn take(&mut self) -> QueryDelta {
+        let delta = std::mem::take(self);
+        QueryDelta{ added: delta.added.results, removed: delta.removed.results }
+    }
+}
+
+type Callback = Box<dyn FnMut(&QueryDelta)>;
+
+/// Query which results are kept up to date while the space is modified.
+/// Query can be a single pattern or any query supported by
+/// [complex_query]. Results of the conjunctions of patterns are updated
+/// incrementally: only results which include the added or removed atom are
+/// recomputed. Results of the queries with other operators are recomputed
+/// on each relevant change.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::reactive::StandingQuery;
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("parent" "Tom" "Bob")]));
+/// let query = StandingQuery::register(&space,
+///     expr!("," ("parent" x y) ("parent" y z))).unwrap();
+///
+/// space.borrow_mut().add(expr!("parent" "Bob" "Ann"));
+///
+/// let delta = query.borrow_mut().take_delta();
+/// assert_eq!(delta.added, vec![bind!{x: sym!("Tom"), y: sym!("Bob"), z: sym!("Ann")}]);
+/// assert!(delta.removed.is_empty());
+/// ```
+pub struct StandingQuery {
+    query: Atom,
+    /// Patterns of the conjunction when query is a conjunction of patterns.
+    conjuncts: Option<Vec<Atom>>,
+    /// All patterns of the query, atom is stored in memory when it matches
+    /// one of them.
+    patterns: Vec<Atom>,
+    vars: HashSet<VariableAtom>,
+    /// Variables of the query in order they are used to make a key of the
+    /// result.
+    key_vars: Vec<VariableAtom>,
+    memory: AtomIndex<AllowDuplication>,
+    results: ResultSet,
+    delta: PendingDelta,
+    callback: Option<Callback>,
+}
+
+impl StandingQuery {
+    /// Constructs standing query and computes its initial results using the
+    /// atoms of the `space`. Returns an error if space doesn't support
+    /// visiting atoms.
+    pub fn new<S: Space + ?Sized>(space: &S, query: Atom) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        collect_patterns(&query, &mut patterns);
+        let conjuncts = match QueryOp::parse(&query) {
+            QueryOp::Pattern(pattern) => Some(vec![pattern.clone()]),
+            QueryOp::And(queries) => queries.into_iter()
+                .map(|query| match QueryOp::parse(query) {
+                    QueryOp::Pattern(pattern) => Some(pattern.clone()),
+                    _ => None,
+                }).collect(),
+            _ => None,
+        };
+        let vars: HashSet<VariableAtom> = query.iter().filter_type::<&VariableAtom>().cloned().collect();
+        let key_vars = vars.iter().cloned().collect();
+        let mut standing = Self{ query, conjuncts, patterns, vars, key_vars,
+            memory: AtomIndex::with_strategy(AllowDuplication{}),
+            results: ResultSet::default(), delta: PendingDelta::default(), callback: None };
+
+        let mut atoms = Vec::new();
+        space.visit(&mut |atom: std::borrow::Cow<Atom>| {
+            if standing.is_relevant(&atom) {
+                atoms.push(atom.into_owned());
+            }
+        }).map_err(|()| "Space doesn't support visiting atoms".to_string())?;
+        standing.memory.insert_all(atoms);
+        standing.results = standing.evaluate(&standing.query);
+        Ok(standing)
+    }
+
+    /// Constructs standing query and registers it as an observer of the
+    /// `space`. Query is deregistered when the returned reference is dropped.
+    pub fn register(space: &DynSpace, query: Atom) -> Result<SpaceObserverRef<Self>, String> {
+        let standing = Self::new(&*space.borrow(), query)?;
+        Ok(space.common().register_observer(standing))
+    }
+
+    /// Sets `callback` which is called with the delta after each space
+    /// modification which changes the results. Delta passed to the
+    /// callback is not returned by [StandingQuery::take_delta].
+    pub fn with_callback<F: FnMut(&QueryDelta) + 'static>(mut self, callback: F) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns query.
+    pub fn query(&self) -> &Atom {
+        &self.query
+    }
+
+    /// Returns current results of the query.
+    pub fn results(&self) -> &[Bindings] {
+        &self.results.results
+    }
+
+    /// Returns changes of the results collected since the last call.
+    pub fn take_delta(&mut self) -> QueryDelta {
+        self.delta.take()
+    }
+
+    /// Returns the key which identifies the result: expression of the values
+    /// of the query variables. Variables inside the values are renamed in
+    /// order of appearance, thus equivalent results have equal keys.
+    fn result_key(&self, bindings: &Bindings) -> Atom {
+        let mut key = Atom::expr(self.key_vars.iter()
+            .map(|var| bindings.resolve(var).unwrap_or_else(|| Atom::Variable(var.clone())))
+            .collect::<Vec<Atom>>());
+        let mut mapping: HashMap<VariableAtom, VariableAtom> = HashMap::new();
+        key.visit_mut(VisitOrder::TopDown, |_path, atom| {
+            if let Atom::Variable(var) = atom {
+                let next_id = mapping.len();
+                *var = mapping.entry(var.clone())
+                    .or_insert_with(|| VariableAtom::new_id("k", next_id)).clone();
+            }
+            VisitResult::Continue
+        });
+        key
+    }
+
+    fn is_relevant(&self, atom: &Atom) -> bool {
+        let atom = make_variables_unique(atom.clone());
+        self.patterns.iter().any(|pattern| match_atoms(pattern, &atom).next().is_some())
+    }
+
+    fn evaluate(&self, query: &Atom) -> ResultSet {
+        let memory = &self.memory;
+        let mut results = ResultSet::default();
+        for bindings in complex_query(query, |query| single_query(memory, query)) {
+            let bindings = bindings.narrow_vars(&self.vars);
+            results.insert(self.result_key(&bindings), bindings);
+        }
+        results
+    }
+
+    fn on_add(&mut self, atom: &Atom) {
+        if !self.is_relevant(atom) {
+            return;
+        }
+        self.memory.insert(atom.clone());
+        let conjuncts = match &self.conjuncts {
+            Some(conjuncts) => conjuncts.clone(),
+            None => return self.recompute(),
+        };
+        let atom = make_variables_unique(atom.clone());
+        for (i, pattern) in conjuncts.iter().enumerate() {
+            for matched in match_atoms(pattern, &atom) {
+                let rest: Vec<Atom> = conjuncts.iter().enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, query)| apply_bindings_to_atom_move(query.clone(), &matched))
+                    .collect();
+                let results = if rest.is_empty() {
+                    BindingsSet::from(matched.clone())
+                } else {
+                    let rest = Atom::expr(std::iter::once(COMMA_SYMBOL).chain(rest).collect::<Vec<_>>());
+                    let memory = &self.memory;
+                    complex_query(&rest, |query| single_query(memory, query)).into_iter()
+                        .flat_map(|bindings| bindings.merge(&matched))
+                        .collect()
+                };
+                for bindings in results {
+                    self.add_result(bindings.narrow_vars(&self.vars));
+                }
+            }
+        }
+    }
+
+    fn on_remove(&mut self, atom: &Atom, count: usize) {
+        if !self.is_relevant(atom) || self.memory.remove_count(atom, count) == 0 {
+            return;
+        }
+        let conjuncts = match &self.conjuncts {
+            Some(conjuncts) => conjuncts,
+            None => return self.recompute(),
+        };
+        let atom = make_variables_unique(atom.clone());
+        let touched: Vec<Atom> = self.results.iter()
+            .filter(|(_key, bindings)| conjuncts.iter().any(|pattern| {
+                let pattern = apply_bindings_to_atom_move(pattern.clone(), bindings);
+                match_atoms(&pattern, &atom).next().is_some()
+            })).map(|(key, _bindings)| key.clone()).collect();
+        for key in touched {
+            let query = match self.results.get(&key) {
+                Some(bindings) => apply_bindings_to_atom_move(self.query.clone(), bindings),
+                None => continue,
+            };
+            if self.evaluate(&query).is_empty() {
+                if let Some(bindings) = self.results.remove(&key) {
+                    self.delta.remove(key, bindings);
+                }
+            }
+        }
+    }
+
+    fn add_result(&mut self, bindings: Bindings) {
+        let key = self.result_key(&bindings);
+        if !self.results.contains(&key) {
+            self.results.insert(key.clone(), bindings.clone());
+            self.delta.add(key, bindings);
+        }
+    }
+
+    fn recompute(&mut self) {
+        let results = self.evaluate(&self.query);
+        for (key, bindings) in self.results.iter() {
+            if !results.contains(key) {
+                self.delta.remove(key.clone(), bindings.clone());
+            }
+        }
+        for (key, bindings) in results.iter() {
+            if !self.results.contains(key) {
+                self.delta.add(key.clone(), bindings.clone());
+            }
+        }
+        self.results = results;
+    }
+
+    fn process(&mut self, event: &SpaceEvent) {
+        match event {
+            SpaceEvent::Add(atom) => self.on_add(atom),
+            SpaceEvent::Remove(atom) => self.on_remove(atom, 1),
+            SpaceEvent::RemoveCount(atom, count) => self.on_remove(atom, *count),
+            SpaceEvent::Replace(from, to) => {
+                self.on_remove(from, 1);
+                self.on_add(to);
+            },
+            SpaceEvent::Batch(events) => events.iter().for_each(|event| self.process(event)),
+        }
+    }
+}
+
+impl SpaceObserver for StandingQuery {
+    fn notify(&mut self, event: &SpaceEvent) {
+        self.process(event);
+        if let Some(callback) = &mut self.callback {
+            if !self.delta.is_empty() {
+                let delta = self.delta.take();
+                callback(&delta);
+            }
+        }
+    }
+}
+
+fn collect_patterns(query: &Atom, patterns: &mut Vec<Atom>) {
+    match QueryOp::parse(query) {
+        QueryOp::And(queries) | QueryOp::Or(queries) =>
+            queries.into_iter().for_each(|query| collect_patterns(query, patterns)),
+        QueryOp::Not(query) | QueryOp::Optional(query) => collect_patterns(query, patterns),
+        QueryOp::Filter(_) => {},
+        QueryOp::Pattern(pattern) => patterns.push(pattern.clone()),
+    }
+}
+
+fn single_query(memory: &AtomIndex<AllowDuplication>, query: &Atom) -> BindingsSet {
+    let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+    memory.query(query).map(|bindings| bindings.narrow_vars(&query_vars)).collect()
+}
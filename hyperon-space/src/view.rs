@@ -0,0 +1,616 @@
+//! Views composing other spaces. View doesn't copy atoms, it forwards
+//! queries and modifications to the underlying spaces, thus changes of the
+//! underlying spaces are immediately visible through the view. Views
+//! implement [SpaceMut] and can be wrapped into [DynSpace] like any other
+//! space.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::{Bindings, BindingsSet, match_atoms, apply_bindings_to_atom_move};
+use hyperon_common::FlexRef;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+use crate::{Space, SpaceMut, SpaceCommon, SpaceStatistics, SpaceVisitor, DynSpace,
+    SpaceEvent, SpaceObserver, SpaceObserverRef, complex_query_with_statistics};
+
+/// Sums estimations of the `spaces`. When one of the spaces has no
+/// statistics the number of matches is unknown and `limit` is returned.
+fn estimate_matches<'a, I>(spaces: I, pattern: &Atom, limit: usize) -> usize
+    where I: IntoIterator<Item=&'a DynSpace>
+{
+    let mut count = 0;
+    for space in spaces {
+        if count >= limit {
+            break;
+        }
+        count += space.borrow().statistics()
+            .map_or(limit, |stat| stat.estimate_matches(pattern, limit - count));
+    }
+    count.min(limit)
+}
+
+fn sum_atom_counts<'a, I>(spaces: I) -> Option<usize>
+    where I: IntoIterator<Item=&'a DynSpace>
+{
+    spaces.into_iter().map(|space| space.borrow().atom_count()).sum()
+}
+
+/// Defines how results of the [UnionSpace] spaces are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnionPolicy {
+    /// Results of all spaces are returned.
+    All,
+    /// Results of the first space which has results are returned, thus
+    /// earlier spaces shadow the later ones. Policy is applied to each
+    /// pattern of the complex query separately.
+    First,
+}
+
+/// Read-only union of the spaces. Conjunctive queries are executed against
+/// the union, thus results can combine atoms from different spaces.
+/// Modifications of the union are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::view::{UnionSpace, UnionPolicy};
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let a = DynSpace::new(GroundingSpace::from_vec(vec![expr!("name" "x" "Alice")]));
+/// let b = DynSpace::new(GroundingSpace::from_vec(vec![expr!("name" "x" "Bob"), expr!("age" "x" "30")]));
+///
+/// let all = UnionSpace::new(vec![a.clone(), b.clone()], UnionPolicy::All);
+/// assert_eq!(all.query(&expr!("," ("name" "x" n) ("age" "x" y))),
+///     bind_set![{n: sym!("Alice"), y: sym!("30")}, {n: sym!("Bob"), y: sym!("30")}]);
+///
+/// let first = UnionSpace::new(vec![a, b], UnionPolicy::First);
+/// assert_eq!(first.query(&expr!("name" "x" n)), bind_set![{n: sym!("Alice")}]);
+/// ```
+pub struct UnionSpace {
+    common: SpaceCommon,
+    spaces: Vec<DynSpace>,
+    policy: UnionPolicy,
+}
+
+impl UnionSpace {
+    /// Constructs union of the `spaces`, order of the spaces is used by
+    /// [UnionPolicy::First].
+    pub fn new(spaces: Vec<DynSpace>, policy: UnionPolicy) -> Self {
+        Self{ common: SpaceCommon::default(), spaces, policy }
+    }
+
+    /// Returns spaces of the union.
+    pub fn spaces(&self) -> &[DynSpace] {
+        &self.spaces
+    }
+
+    /// Returns union policy.
+    pub fn policy(&self) -> UnionPolicy {
+        self.policy
+    }
+
+    fn single_query(&self, query: &Atom) -> BindingsSet {
+        let mut results = BindingsSet::empty();
+        for space in &self.spaces {
+            let space_results = space.borrow().query(query);
+            match self.policy {
+                UnionPolicy::All => results.extend(space_results),
+                UnionPolicy::First if !space_results.is_empty() => return space_results,
+                UnionPolicy::First => {},
+            }
+        }
+        results
+    }
+}
+
+impl SpaceStatistics for UnionSpace {
+    fn estimate_matches(&self, pattern: &Atom, limit: usize) -> usize {
+        estimate_matches(&self.spaces, pattern, limit)
+    }
+}
+
+impl Space for UnionSpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        FlexRef::from_simple(&self.common)
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        complex_query_with_statistics(query, Some(self), |query| self.single_query(query))
+    }
+    fn atom_count(&self) -> Option<usize> {
+        sum_atom_counts(&self.spaces)
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.spaces.iter().try_for_each(|space| space.borrow().visit(v))
+    }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        Some(self)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for UnionSpace {
+    fn add(&mut self, atom: Atom) {
+        log::warn!("UnionSpace::add: union is read-only, atom is not added: {}", atom);
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        log::warn!("UnionSpace::remove: union is read-only, atom is not removed: {}", atom);
+        false
+    }
+    fn replace(&mut self, from: &Atom, _to: Atom) -> bool {
+        log::warn!("UnionSpace::replace: union is read-only, atom is not replaced: {}", from);
+        false
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Display for UnionSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UnionSpace(")?;
+        for (i, space) in self.spaces.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", space)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Debug for UnionSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UnionSpace({:?}, {:?})", self.policy, self.spaces)
+    }
+}
+
+/// Overlay of the two spaces. Modifications go to the top space while
+/// queries return results of both top and base spaces. Observers are
+/// registered in the top space.
+///
+/// The base space is never modified through the overlay and overlay keeps
+/// no tombstones, thus atoms of the base space cannot be removed or
+/// replaced: [SpaceMut::remove] and [SpaceMut::replace] return `false` for
+/// them. Use [FilteredSpace] over the base space to hide its atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::view::OverlaySpace;
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let base = DynSpace::new(GroundingSpace::from_vec(vec![expr!("A" "B")]));
+/// let top = DynSpace::new(GroundingSpace::new());
+/// let mut overlay = OverlaySpace::new(top.clone(), base.clone());
+///
+/// overlay.add(expr!("A" "C"));
+///
+/// assert_eq!(overlay.query(&expr!("A" x)), bind_set![{x: sym!("C")}, {x: sym!("B")}]);
+/// assert_eq!(base.borrow().atom_count(), Some(1));
+/// assert_eq!(top.borrow().atom_count(), Some(1));
+///
+/// // atoms of the base space are read-only
+/// assert!(!overlay.remove(&expr!("A" "B")));
+/// assert!(!overlay.replace(&expr!("A" "B"), expr!("A" "D")));
+/// assert!(overlay.replace(&expr!("A" "C"), expr!("A" "D")));
+/// assert_eq!(overlay.query(&expr!("A" x)), bind_set![{x: sym!("D")}, {x: sym!("B")}]);
+/// ```
+pub struct OverlaySpace {
+    top: DynSpace,
+    base: DynSpace,
+}
+
+impl OverlaySpace {
+    /// Constructs overlay which writes into the `top` space and reads from
+    /// both `top` and `base` spaces.
+    pub fn new(top: DynSpace, base: DynSpace) -> Self {
+        Self{ top, base }
+    }
+
+    /// Returns the space which receives modifications.
+    pub fn top(&self) -> &DynSpace {
+        &self.top
+    }
+
+    /// Returns the read-only base space.
+    pub fn base(&self) -> &DynSpace {
+        &self.base
+    }
+
+    fn single_query(&self, query: &Atom) -> BindingsSet {
+        let mut results = self.top.borrow().query(query);
+        results.extend(self.base.borrow().query(query));
+        results
+    }
+}
+
+impl SpaceStatistics for OverlaySpace {
+    fn estimate_matches(&self, pattern: &Atom, limit: usize) -> usize {
+        estimate_matches([&self.top, &self.base], pattern, limit)
+    }
+}
+
+impl Space for OverlaySpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        self.top.common()
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        complex_query_with_statistics(query, Some(self), |query| self.single_query(query))
+    }
+    fn atom_count(&self) -> Option<usize> {
+        sum_atom_counts([&self.top, &self.base])
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.top.borrow().visit(v)?;
+        self.base.borrow().visit(v)
+    }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        Some(self)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for OverlaySpace {
+    fn add(&mut self, atom: Atom) {
+        self.top.borrow_mut().add(atom)
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        self.top.borrow_mut().remove(atom)
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.top.borrow_mut().replace(from, to)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Display for OverlaySpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OverlaySpace({} {})", self.top, self.base)
+    }
+}
+
+impl Debug for OverlaySpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OverlaySpace({:?}, {:?})", self.top, self.base)
+    }
+}
+
+/// Filter of the atoms exposed by [FilteredSpace].
+#[derive(Clone)]
+pub enum AtomFilter {
+    /// Atom should match the pattern.
+    Pattern(Atom),
+    /// Predicate should return `true` for the atom.
+    Predicate(Rc<dyn Fn(&Atom) -> bool>),
+}
+
+impl AtomFilter {
+    /// Returns `true` if `atom` passes the filter.
+    pub fn accepts(&self, atom: &Atom) -> bool {
+        match self {
+            Self::Pattern(pattern) => {
+                let atom = make_variables_unique(atom.clone());
+                match_atoms(pattern, &atom).next().is_some()
+            },
+            Self::Predicate(predicate) => predicate(atom),
+        }
+    }
+}
+
+impl Debug for AtomFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pattern(pattern) => write!(f, "Pattern({})", pattern),
+            Self::Predicate(_) => write!(f, "Predicate"),
+        }
+    }
+}
+
+/// View of the space which exposes only atoms passing the filter. Results
+/// of the query are checked by applying them to the query pattern, thus the
+/// filter is exact for the spaces which contain no variables. Atoms which
+/// don't pass the filter cannot be added or removed through the view.
+///
+/// View has its own observers. Events of the underlying space are relayed
+/// to them after filtering: atoms which don't pass the filter are dropped
+/// from the events and replacement which crosses the filter is reported as
+/// addition or removal.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::view::{FilteredSpace, AtomFilter};
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// struct Events(Vec<SpaceEvent>);
+///
+/// impl SpaceObserver for Events {
+///     fn notify(&mut self, event: &SpaceEvent) {
+///         self.0.push(event.clone());
+///     }
+/// }
+///
+/// let space = DynSpace::new(GroundingSpace::from_vec(vec![
+///     expr!("public" "A"), expr!("secret" "B"),
+/// ]));
+/// let view = FilteredSpace::new(space.clone(), AtomFilter::Pattern(expr!("public" x)));
+/// let events = view.common().register_observer(Events(Vec::new()));
+///
+/// assert_eq!(view.query(&expr!(k x)), bind_set![{k: sym!("public"), x: sym!("A")}]);
+///
+/// space.borrow_mut().add(expr!("secret" "C"));
+/// space.borrow_mut().add(expr!("public" "C"));
+/// space.borrow_mut().replace(&expr!("public" "A"), expr!("secret" "A"));
+/// assert_eq!(events.borrow().0, vec![SpaceEvent::Add(expr!("public" "C")),
+///     SpaceEvent::Remove(expr!("public" "A"))]);
+/// ```
+pub struct FilteredSpace {
+    common: Rc<SpaceCommon>,
+    space: DynSpace,
+    filter: AtomFilter,
+    _relay: SpaceObserverRef<FilterRelay>,
+}
+
+impl FilteredSpace {
+    /// Constructs view of the `space` exposing the atoms which pass the
+    /// `filter`.
+    pub fn new(space: DynSpace, filter: AtomFilter) -> Self {
+        let common = Rc::new(SpaceCommon::default());
+        let relay = FilterRelay{ common: common.clone(), filter: filter.clone() };
+        let _relay = space.common().register_observer(relay);
+        Self{ common, space, filter, _relay }
+    }
+
+    /// Returns the underlying space.
+    pub fn space(&self) -> &DynSpace {
+        &self.space
+    }
+
+    /// Returns the filter.
+    pub fn filter(&self) -> &AtomFilter {
+        &self.filter
+    }
+
+    fn single_query(&self, query: &Atom) -> BindingsSet {
+        let mut results = self.space.borrow().query(query);
+        results.drain(0..)
+            .filter(|bindings| self.filter.accepts(&apply_bindings_to_atom_move(query.clone(), bindings)))
+            .collect()
+    }
+}
+
+impl Space for FilteredSpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        FlexRef::from_simple(&*self.common)
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        complex_query_with_statistics(query, self.space.borrow().statistics(),
+            |query| self.single_query(query))
+    }
+    fn atom_count(&self) -> Option<usize> {
+        let mut count = 0;
+        let result = self.visit(&mut |_atom: Cow<Atom>| count += 1);
+        result.ok().map(|()| count)
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.space.borrow().visit(&mut |atom: Cow<Atom>| {
+            if self.filter.accepts(&atom) {
+                v.accept(atom)
+            }
+        })
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for FilteredSpace {
+    fn add(&mut self, atom: Atom) {
+        if self.filter.accepts(&atom) {
+            self.space.borrow_mut().add(atom)
+        } else {
+            log::warn!("FilteredSpace::add: atom doesn't pass filter {:?} and is not added: {}", self.filter, atom);
+        }
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        self.filter.accepts(atom) && self.space.borrow_mut().remove(atom)
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.filter.accepts(from) && self.filter.accepts(&to)
+            && self.space.borrow_mut().replace(from, to)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Observer of the underlying space which passes filtered events to the
+/// observers of the [FilteredSpace].
+struct FilterRelay {
+    common: Rc<SpaceCommon>,
+    filter: AtomFilter,
+}
+
+impl FilterRelay {
+    fn filter_event(&self, event: &SpaceEvent) -> Option<SpaceEvent> {
+        match event {
+            SpaceEvent::Add(atom) | SpaceEvent::Remove(atom) | SpaceEvent::RemoveCount(atom, _)
+                if !self.filter.accepts(atom) => None,
+            SpaceEvent::Add(_) | SpaceEvent::Remove(_) | SpaceEvent::RemoveCount(_, _) => Some(event.clone()),
+            SpaceEvent::Replace(from, to) => {
+                match (self.filter.accepts(from), self.filter.accepts(to)) {
+                    (true, true) => Some(event.clone()),
+                    (true, false) => Some(SpaceEvent::Remove(from.clone())),
+                    (false, true) => Some(SpaceEvent::Add(to.clone())),
+                    (false, false) => None,
+                }
+            },
+            SpaceEvent::Batch(events) => {
+                let events: Vec<SpaceEvent> = events.iter()
+                    .filter_map(|event| self.filter_event(event))
+                    .collect();
+                (!events.is_empty()).then_some(SpaceEvent::Batch(events))
+            },
+        }
+    }
+}
+
+impl SpaceObserver for FilterRelay {
+    fn notify(&mut self, event: &SpaceEvent) {
+        if let Some(event) = self.filter_event(event) {
+            self.common.notify_all_observers(&event);
+        }
+    }
+}
+
+impl Display for FilteredSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FilteredSpace({})", self.space)
+    }
+}
+
+impl Debug for FilteredSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FilteredSpace({:?}, {:?})", self.space, self.filter)
+    }
+}
+
+/// View of the space which renames symbols. Symbols of the underlying space
+/// are replaced by the view names in the results and visited atoms, view
+/// names are replaced back in the queries and modifications. Observers are
+/// registered in the underlying space and receive atoms with original
+/// names.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::*;
+/// use hyperon_space::view::RenamingSpace;
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("Parent" "Tom" "Bob")]));
+/// let mut view = RenamingSpace::new(space.clone(),
+///     [(SymbolAtom::new("Parent".into()), SymbolAtom::new("parent".into()))]);
+///
+/// assert_eq!(view.query(&expr!("parent" x y)), bind_set![{x: sym!("Tom"), y: sym!("Bob")}]);
+///
+/// view.add(expr!("parent" "Bob" "Ann"));
+/// assert_eq!(space.borrow().query(&expr!("Parent" "Bob" x)), bind_set![{x: sym!("Ann")}]);
+/// ```
+pub struct RenamingSpace {
+    space: DynSpace,
+    to_view: HashMap<SymbolAtom, SymbolAtom>,
+    to_space: HashMap<SymbolAtom, SymbolAtom>,
+}
+
+impl RenamingSpace {
+    /// Constructs view of the `space`. `names` are pairs of the symbol of
+    /// the space and the symbol of the view.
+    pub fn new<I: IntoIterator<Item=(SymbolAtom, SymbolAtom)>>(space: DynSpace, names: I) -> Self {
+        let to_view: HashMap<SymbolAtom, SymbolAtom> = names.into_iter().collect();
+        let to_space = to_view.iter()
+            .map(|(space_name, view_name)| (view_name.clone(), space_name.clone()))
+            .collect();
+        Self{ space, to_view, to_space }
+    }
+
+    /// Returns the underlying space.
+    pub fn space(&self) -> &DynSpace {
+        &self.space
+    }
+
+    fn to_view(&self, atom: Atom) -> Atom {
+        rename_symbols(atom, &self.to_view)
+    }
+
+    fn to_space(&self, atom: Atom) -> Atom {
+        rename_symbols(atom, &self.to_space)
+    }
+}
+
+fn rename_symbols(mut atom: Atom, names: &HashMap<SymbolAtom, SymbolAtom>) -> Atom {
+    for sub in atom.iter_mut() {
+        let renamed = match &*sub {
+            Atom::Symbol(sym) => names.get(sym).cloned(),
+            _ => None,
+        };
+        if let Some(renamed) = renamed {
+            *sub = Atom::Symbol(renamed);
+        }
+    }
+    atom
+}
+
+impl Space for RenamingSpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        self.space.common()
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        let query = self.to_space(query.clone());
+        let mut results = self.space.borrow().query(&query);
+        results.drain(0..)
+            .map(|bindings| bindings.iter()
+                .map(|(var, value)| (var.clone(), self.to_view(value)))
+                .collect::<Vec<(VariableAtom, Atom)>>())
+            .map(Bindings::from)
+            .collect()
+    }
+    fn atom_count(&self) -> Option<usize> {
+        self.space.borrow().atom_count()
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.space.borrow().visit(&mut |atom: Cow<Atom>| {
+            v.accept(Cow::Owned(self.to_view(atom.into_owned())))
+        })
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for RenamingSpace {
+    fn add(&mut self, atom: Atom) {
+        let atom = self.to_space(atom);
+        self.space.borrow_mut().add(atom)
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        let atom = self.to_space(atom.clone());
+        self.space.borrow_mut().remove(&atom)
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        let from = self.to_space(from.clone());
+        let to = self.to_space(to);
+        self.space.borrow_mut().replace(&from, to)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Display for RenamingSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RenamingSpace({})", self.space)
+    }
+}
+
+impl Debug for RenamingSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RenamingSpace({:?}, {:?})", self.space, self.to_view)
+    }
+}
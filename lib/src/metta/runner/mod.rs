@@ -91,6 +91,8 @@ use super::interpreter::{interpret, interpret_init, interpret_step, InterpreterS
 pub mod stdlib;
 use stdlib::CoreLibLoader;
 
+pub mod sync;
+
 mod builtin_mods;
 use builtin_mods::*;
 
@@ -156,6 +158,9 @@ pub(crate) struct MettaContents {
     settings: PragmaSettings,
     /// The runner's Environment
     environment: Arc<Environment>,
+    /// Atoms scheduled to be evaluated in the paired spaces when the current
+    /// operation is finished, see [Metta::reactions]
+    reactions: Shared<Vec<(DynSpace, Atom)>>,
     //TODO-HACK: This is a terrible horrible ugly hack that should not be merged.  Delete this field
     // The real context is an interface to the state in a run, and should not live across runs
     // This hack will fail badly if we end up running code from two different modules in parallel
@@ -260,6 +265,7 @@ impl Metta {
             stdlib_mod: OnceLock::new(),
             settings,
             environment,
+            reactions: Shared::new(vec![]),
             context: std::sync::Arc::new(std::sync::Mutex::new(vec![])),
         };
         let metta = Self(Rc::new(contents));
@@ -453,6 +459,14 @@ impl Metta {
         self.0.settings.get(key).map(|a| a.to_string())
     }
 
+    /// Returns a queue of the atoms to be evaluated after the current top-level
+    /// operation is finished. Each atom is evaluated in the paired space. It is
+    /// used by the operations which react on space modifications (like `on-match`)
+    /// because the space is borrowed while observers are notified.
+    pub(crate) fn reactions(&self) -> &Shared<Vec<(DynSpace, Atom)>> {
+        &self.0.reactions
+    }
+
     pub fn run(&self, parser: impl Parser) -> Result<Vec<Vec<Atom>>, String> {
         let state = RunnerState::new_with_parser(self, Box::new(parser));
         state.run_to_completion()
@@ -1050,6 +1064,15 @@ impl<'input> RunContext<'_, 'input> {
             Ok(())
         } else {
 
+            // Evaluate reactions scheduled by the previous operation
+            let reactions = std::mem::take(&mut **self.metta.reactions().borrow_mut());
+            if !reactions.is_empty() {
+                for (space, atom) in reactions {
+                    self.evaluate_reaction(space, atom);
+                }
+                return Ok(());
+            }
+
             // Get the next operation
             let tokenizer_option = self.mod_ptr.as_ref().map(|module| module.tokenizer().borrow());
             let tokenizer = tokenizer_option.as_ref().map(|tok| &**tok as &Tokenizer);
@@ -1117,6 +1140,21 @@ impl<'input> RunContext<'_, 'input> {
         }
     }
 
+
+    /// Evaluates reaction atom, reactions have no caller to return results or
+    /// errors to, thus errors are logged
+    fn evaluate_reaction(&self, space: DynSpace, atom: Atom) {
+        let atom = if is_bare_minimal_interpreter(self.metta) {
+            atom
+        } else {
+            wrap_atom_by_metta_interpreter(space.clone(), atom)
+        };
+        match interpret(space, &atom) {
+            Ok(results) => results.iter().filter(|result| atom_is_error(result))
+                .for_each(|error| log::error!("Reaction {} returned error: {}", atom, error)),
+            Err(err) => log::error!("Reaction {} failed: {}", atom, err),
+        }
+    }
 }
 
 fn is_bare_minimal_interpreter(metta: &Metta) -> bool {
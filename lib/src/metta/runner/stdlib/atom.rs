@@ -7,6 +7,8 @@ use hyperon_atom::matcher::atoms_are_equivalent;
 use hyperon_common::multitrie::{MultiTrie, TrieKey, TrieToken};
 use super::{grounded_op, regex};
 use hyperon_atom::gnd::number::*;
+use hyperon_atom::gnd::op::Unevaluated;
+use hyperon_macros::metta_op;
 
 use std::convert::TryInto;
 use std::hash::{DefaultHasher, Hasher};
@@ -284,6 +286,37 @@ impl CustomExecute for IndexAtomOp {
     }
 }
 
+fn path_arg(op: &str, path: &ExpressionAtom) -> Result<Vec<usize>, ExecError> {
+    path.children().iter()
+        .map(|index| match Number::from_atom(index) {
+            Some(Number::Integer(index)) if index >= 0 => Ok(index as usize),
+            _ => Err(ExecError::from(format!("{} expects path of non-negative integers as argument 2", op))),
+        })
+        .collect()
+}
+
+fn path_to_atom(path: Vec<usize>) -> Atom {
+    Atom::expr(path.into_iter().map(|index| Atom::gnd(Number::Integer(index as i64))).collect::<Vec<_>>())
+}
+
+#[metta_op(name = "atom-at")]
+pub fn atom_at(atom: Unevaluated, path: &ExpressionAtom) -> Result<Atom, ExecError> {
+    let path = path_arg("atom-at", path)?;
+    atom.0.get_at(&path).cloned().ok_or_else(|| ExecError::from("Path is out of bounds"))
+}
+
+#[metta_op(name = "atom-replace-at")]
+pub fn atom_replace_at(atom: Unevaluated, path: &ExpressionAtom, new: Unevaluated) -> Result<Atom, ExecError> {
+    let path = path_arg("atom-replace-at", path)?;
+    atom.0.with_replaced_at(&path, new.0.clone()).ok_or_else(|| ExecError::from("Path is out of bounds"))
+}
+
+#[metta_op(name = "atom-paths-matching")]
+pub fn atom_paths_matching(atom: Unevaluated, pattern: Unevaluated) -> ExpressionAtom {
+    let paths = atom.0.paths_matching(pattern.0).into_iter().map(path_to_atom).collect::<Vec<_>>();
+    ExpressionAtom::new(paths.into())
+}
+
 #[derive(Clone, Debug)]
 pub struct SubtractionAtomOp {}
 
@@ -464,6 +497,9 @@ pub(super) fn register_context_independent_tokens(tref: &mut Tokenizer) {
     tref.register_token(regex(r"size-atom"), move |_| { size_atom_op.clone() });
     let index_atom_op = Atom::gnd(IndexAtomOp{});
     tref.register_token(regex(r"index-atom"), move |_| { index_atom_op.clone() });
+    tref.register_op::<AtomAtOp>();
+    tref.register_op::<AtomReplaceAtOp>();
+    tref.register_op::<AtomPathsMatchingOp>();
     let unique_op = Atom::gnd(UniqueAtomOp{});
     tref.register_token(regex(r"unique-atom"), move |_| { unique_op.clone() });
     let subtraction_op = Atom::gnd(SubtractionAtomOp{});
@@ -592,6 +628,45 @@ mod tests {
         assert_eq!(res, Err(ExecError::from("Index is out of bounds")));
     }
 
+    #[test]
+    fn atom_at_op() {
+        let atom = expr!("A" ("B" "C"));
+        let res = AtomAtOp{}.execute(&mut vec![atom.clone(), expr!({Number::Integer(1)} {Number::Integer(0)})]);
+        assert_eq!(res, Ok(vec![expr!("B")]));
+        let res = AtomAtOp{}.execute(&mut vec![atom.clone(), expr!()]);
+        assert_eq!(res, Ok(vec![atom.clone()]));
+        let res = AtomAtOp{}.execute(&mut vec![atom.clone(), expr!({Number::Integer(0)} {Number::Integer(0)})]);
+        assert_eq!(res, Err(ExecError::from("Path is out of bounds")));
+        let res = AtomAtOp{}.execute(&mut vec![atom.clone(), expr!({Number::Integer(-1)})]);
+        assert_eq!(res, Err(ExecError::from("atom-at expects path of non-negative integers as argument 2")));
+        let res = AtomAtOp{}.execute(&mut vec![atom.clone(), expr!("A")]);
+        assert_eq!(res, Err(ExecError::from("atom-at expects Expression as argument 2")));
+        let res = AtomAtOp{}.execute(&mut vec![atom]);
+        assert_eq!(res, Err(ExecError::from("atom-at expects 2 arguments")));
+    }
+
+    #[test]
+    fn atom_replace_at_op() {
+        let atom = expr!("A" ("B" "C"));
+        let res = AtomReplaceAtOp{}.execute(&mut vec![atom.clone(), expr!({Number::Integer(1)} {Number::Integer(1)}), expr!("D" x)]);
+        assert_eq!(res, Ok(vec![expr!("A" ("B" ("D" x)))]));
+        let res = AtomReplaceAtOp{}.execute(&mut vec![atom, expr!({Number::Integer(2)}), expr!("D")]);
+        assert_eq!(res, Err(ExecError::from("Path is out of bounds")));
+    }
+
+    #[test]
+    fn metta_atom_paths_matching() {
+        assert_eq!(run_program("!(atom-paths-matching (A (B C) (B D)) (B $x))"),
+            Ok(vec![vec![expr!(({Number::Integer(1)}) ({Number::Integer(2)}))]]));
+        assert_eq!(run_program("!(atom-paths-matching (A B) C)"), Ok(vec![vec![expr!()]]));
+    }
+
+    #[test]
+    fn metta_atom_at_and_replace_at() {
+        assert_eq!(run_program("!(atom-at (A (B C)) (1 1))"), Ok(vec![vec![expr!("C")]]));
+        assert_eq!(run_program("!(atom-replace-at (A (B C)) (1 1) D)"), Ok(vec![vec![expr!("A" ("B" "D"))]]));
+    }
+
     #[test]
     fn test_error_is_used_as_an_argument() {
         let metta = Metta::new(Some(EnvBuilder::test_env()));
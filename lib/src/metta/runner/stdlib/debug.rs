@@ -4,6 +4,7 @@ use crate::metta::text::Tokenizer;
 use hyperon_common::collections::{SliceDisplay, Equality, DefaultEquality};
 use hyperon_common::assert::compare_vec_no_order;
 use hyperon_atom::matcher::atoms_are_equivalent;
+use hyperon_atom::diff::diff_atoms;
 use crate::metta::runner::stdlib::{grounded_op, regex, unit_result};
 use hyperon_atom::gnd::bool::*;
 use hyperon_atom::gnd::str::*;
@@ -105,6 +106,17 @@ impl Equality<&Atom> for AlphaEquality {
     }
 }
 
+/// Returns structural difference when single expression is expected and
+/// single expression is returned, empty string otherwise. Reading the
+/// difference is simpler than comparing two large expressions.
+fn structural_diff(actual: &[Atom], expected: &[Atom]) -> String {
+    match (actual, expected) {
+        ([actual @ Atom::Expression(_)], [expected @ Atom::Expression(_)]) =>
+            format!("\nDifference:\n{}", diff_atoms(expected, actual)),
+        _ => String::new(),
+    }
+}
+
 fn assert_results_are_equal<'a, E: Equality<&'a Atom>>(args: &'a [Atom], cmp: E) -> Result<Vec<Atom>, ExecError> {
     let arg_error = || ExecError::from("Pair of evaluation results with bindings is expected as an argument");
     let actual = TryInto::<&ExpressionAtom>::try_into(args.get(0).ok_or_else(arg_error)?)?.children();
@@ -118,7 +130,7 @@ fn assert_results_are_equal<'a, E: Equality<&'a Atom>>(args: &'a [Atom], cmp: E)
         None => unit_result(),
         Some(diff) => {
             let msg = match args.get(3) {
-                None => Atom::gnd(Str::from_string(format!("{}\n{}", report, diff))),
+                None => Atom::gnd(Str::from_string(format!("{}\n{}{}", report, diff, structural_diff(actual, expected)))),
                 Some(m) => m.clone(),
             };
             Ok(vec![Atom::expr([ERROR_SYMBOL, assert.clone(), msg])])
@@ -206,6 +218,15 @@ mod tests {
         ]));
     }
 
+    #[test]
+    fn metta_assert_equal_shows_difference() {
+        let metta = Metta::new(Some(EnvBuilder::test_env()));
+        assert_eq!(metta.run(SExprParser::new("(= (foo) (A (B C) D))")), Ok(vec![]));
+        assert_eq!(metta.run(SExprParser::new("!(assertEqual (foo) (A (B E) D))")), Ok(vec![
+            vec![expr!("Error" ("assertEqual" ("foo") ("A" ("B" "E") "D")) {Str::from_str("\nExpected: [(A (B E) D)]\nGot: [(A (B C) D)]\nMissed results: (A (B E) D)\nExcessive results: (A (B C) D)\nDifference:\n[1, 1]: E -> C")})],
+        ]));
+    }
+
     #[test]
     fn metta_assert_alpha_equal_op() {
         let metta = Metta::new(Some(EnvBuilder::test_env()));
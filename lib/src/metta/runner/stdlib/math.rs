@@ -1,443 +1,131 @@
 use hyperon_atom::*;
-use crate::metta::*;
 use crate::metta::text::Tokenizer;
-use super::{grounded_op, regex};
+use super::regex;
 use hyperon_atom::gnd::number::*;
-use hyperon_atom::gnd::bool::*;
+use hyperon_macros::metta_op;
 
 use std::convert::TryInto;
 
-#[derive(Clone, Debug)]
-pub struct PowMathOp {}
-grounded_op!(PowMathOp, "pow-math");
-impl Grounded for PowMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-impl CustomExecute for PowMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("pow-math expects two arguments: number (base) and number (power)");
-        let base: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        let pow = args.get(1).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        let res = match pow {
-            Number::Integer(n) => {
-                match TryInto::<i32>::try_into(n) {
-                    Ok(n) => base.powi(n),
-                    Err(_) => return Err(ExecError::from("power argument is too big, try using float value")),
-                }
-            },
-            Number::Float(f) => base.powf(f),
-        };
-        Ok(vec![Atom::gnd(Number::Float(res))])
+#[metta_op(name = "pow-math")]
+pub fn pow_math(base: f64, pow: Number) -> Result<f64, ExecError> {
+    match pow {
+        Number::Integer(n) => match TryInto::<i32>::try_into(n) {
+            Ok(n) => Ok(base.powi(n)),
+            Err(_) => Err(ExecError::from("power argument is too big, try using float value")),
+        },
+        Number::Float(f) => Ok(base.powf(f)),
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct SqrtMathOp {}
-
-grounded_op!(SqrtMathOp, "sqrt-math");
-
-impl Grounded for SqrtMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "sqrt-math")]
+pub fn sqrt_math(input: f64) -> f64 {
+    input.sqrt()
 }
 
-impl CustomExecute for SqrtMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("sqrt-math expects one argument: number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.sqrt()))])
+#[metta_op(name = "abs-math")]
+pub fn abs_math(input: Number) -> Number {
+    match input {
+        Number::Integer(n) => Number::Integer(n.abs()),
+        Number::Float(f) => Number::Float(f.abs()),
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct AbsMathOp {}
-
-grounded_op!(AbsMathOp, "abs-math");
-
-impl Grounded for AbsMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "log-math")]
+pub fn log_math(base: f64, input: f64) -> f64 {
+    input.log(base)
 }
 
-impl CustomExecute for AbsMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("abs-math expects one argument: number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        match input {
-            Number::Integer(n) => Ok(vec![Atom::gnd(Number::Integer(n.abs()))]),
-            Number::Float(f) => Ok(vec![Atom::gnd(Number::Float(f.abs()))])
-        }
+/// Applies `round` to the float number, integer number is returned as is.
+fn round_number(input: Number, round: fn(f64) -> f64) -> Number {
+    match input {
+        Number::Integer(n) => Number::Integer(n),
+        Number::Float(f) => Number::Float(round(f)),
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct LogMathOp {}
-
-grounded_op!(LogMathOp, "log-math");
-
-impl Grounded for LogMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "trunc-math")]
+pub fn trunc_math(input: Number) -> Number {
+    round_number(input, f64::trunc)
 }
 
-impl CustomExecute for LogMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("log-math expects two arguments: base (number) and input value (number)");
-        let base: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        let input: f64 = args.get(1).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.log(base)))])
-    }
+#[metta_op(name = "ceil-math")]
+pub fn ceil_math(input: Number) -> Number {
+    round_number(input, f64::ceil)
 }
 
-#[derive(Clone, Debug)]
-pub struct TruncMathOp {}
-
-grounded_op!(TruncMathOp, "trunc-math");
-
-impl Grounded for TruncMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "floor-math")]
+pub fn floor_math(input: Number) -> Number {
+    round_number(input, f64::floor)
 }
 
-impl CustomExecute for TruncMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("trunc-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        match input {
-            Number::Integer(n) => Ok(vec![Atom::gnd(Number::Integer(n))]),
-            Number::Float(f) => Ok(vec![Atom::gnd(Number::Float(f.trunc()))])
-        }
-    }
+#[metta_op(name = "round-math")]
+pub fn round_math(input: Number) -> Number {
+    round_number(input, f64::round)
 }
 
-#[derive(Clone, Debug)]
-pub struct CeilMathOp {}
-
-grounded_op!(CeilMathOp, "ceil-math");
-
-impl Grounded for CeilMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "sin-math")]
+pub fn sin_math(input: f64) -> f64 {
+    input.sin()
 }
 
-impl CustomExecute for CeilMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("ceil-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        match input {
-            Number::Integer(n) => Ok(vec![Atom::gnd(Number::Integer(n))]),
-            Number::Float(f) => Ok(vec![Atom::gnd(Number::Float(f.ceil()))])
-        }
-    }
+#[metta_op(name = "asin-math")]
+pub fn asin_math(input: f64) -> f64 {
+    input.asin()
 }
 
-#[derive(Clone, Debug)]
-pub struct FloorMathOp {}
-
-grounded_op!(FloorMathOp, "floor-math");
-
-impl Grounded for FloorMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "cos-math")]
+pub fn cos_math(input: f64) -> f64 {
+    input.cos()
 }
 
-impl CustomExecute for FloorMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("floor-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        match input {
-            Number::Integer(n) => Ok(vec![Atom::gnd(Number::Integer(n))]),
-            Number::Float(f) => Ok(vec![Atom::gnd(Number::Float(f.floor()))])
-        }
-    }
+#[metta_op(name = "acos-math")]
+pub fn acos_math(input: f64) -> f64 {
+    input.acos()
 }
 
-#[derive(Clone, Debug)]
-pub struct RoundMathOp {}
-
-grounded_op!(RoundMathOp, "round-math");
-
-impl Grounded for RoundMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
+#[metta_op(name = "tan-math")]
+pub fn tan_math(input: f64) -> f64 {
+    input.tan()
 }
 
-impl CustomExecute for RoundMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("round-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        match input {
-            Number::Integer(n) => Ok(vec![Atom::gnd(Number::Integer(n))]),
-            Number::Float(f) => Ok(vec![Atom::gnd(Number::Float(f.round()))])
-        }
-    }
+#[metta_op(name = "atan-math")]
+pub fn atan_math(input: f64) -> f64 {
+    input.atan()
 }
 
-#[derive(Clone, Debug)]
-pub struct SinMathOp {}
-
-grounded_op!(SinMathOp, "sin-math");
-
-impl Grounded for SinMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for SinMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("sin-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.sin()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct AsinMathOp {}
-
-grounded_op!(AsinMathOp, "asin-math");
-
-impl Grounded for AsinMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for AsinMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("asin-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.asin()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct CosMathOp {}
-
-grounded_op!(CosMathOp, "cos-math");
-
-impl Grounded for CosMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for CosMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("cos-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.cos()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct AcosMathOp {}
-
-grounded_op!(AcosMathOp, "acos-math");
-
-impl Grounded for AcosMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for AcosMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("acos-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.acos()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct TanMathOp {}
-
-grounded_op!(TanMathOp, "tan-math");
-
-impl Grounded for TanMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for TanMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("tan-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.tan()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct AtanMathOp {}
-
-grounded_op!(AtanMathOp, "atan-math");
-
-impl Grounded for AtanMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_NUMBER])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for AtanMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("atan-math expects one argument: input number");
-        let input: f64 = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?.into();
-        Ok(vec![Atom::gnd(Number::Float(input.atan()))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct IsNanMathOp {}
-
-grounded_op!(IsNanMathOp, "isnan-math");
-
-impl Grounded for IsNanMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_BOOL])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
-    }
-}
-
-impl CustomExecute for IsNanMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("isnan-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        let res = match input {
-            Number::Integer(_) => false,
-            Number::Float(f) => f.is_nan(),
-        };
-        Ok(vec![Atom::gnd(Bool(res))])
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct IsInfMathOp {}
-
-grounded_op!(IsInfMathOp, "isinf-math");
-
-impl Grounded for IsInfMathOp {
-    fn type_(&self) -> Atom {
-        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_NUMBER, ATOM_TYPE_BOOL])
-    }
-
-    fn as_execute(&self) -> Option<&dyn CustomExecute> {
-        Some(self)
+#[metta_op(name = "isnan-math")]
+pub fn is_nan_math(input: Number) -> bool {
+    match input {
+        Number::Integer(_) => false,
+        Number::Float(f) => f.is_nan(),
     }
 }
 
-impl CustomExecute for IsInfMathOp {
-    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
-        let arg_error = || ExecError::from("isinf-math expects one argument: input number");
-        let input = args.get(0).and_then(Number::from_atom).ok_or_else(arg_error)?;
-        let res = match input {
-            Number::Integer(_) => false,
-            Number::Float(f) => f.is_infinite(),
-        };
-        Ok(vec![Atom::gnd(Bool(res))])
+#[metta_op(name = "isinf-math")]
+pub fn is_inf_math(input: Number) -> bool {
+    match input {
+        Number::Integer(_) => false,
+        Number::Float(f) => f.is_infinite(),
     }
 }
 
 pub(super) fn register_context_independent_tokens(tref: &mut Tokenizer) {
-    let pow_math_op = Atom::gnd(PowMathOp {});
-    tref.register_token(regex(r"pow-math"), move |_| { pow_math_op.clone() });
-    let sqrt_math_op = Atom::gnd(SqrtMathOp {});
-    tref.register_token(regex(r"sqrt-math"), move |_| { sqrt_math_op.clone() });
-    let abs_math_op = Atom::gnd(AbsMathOp {});
-    tref.register_token(regex(r"abs-math"), move |_| { abs_math_op.clone() });
-    let log_math_op = Atom::gnd(LogMathOp {});
-    tref.register_token(regex(r"log-math"), move |_| { log_math_op.clone() });
-    let trunc_math_op = Atom::gnd(TruncMathOp {});
-    tref.register_token(regex(r"trunc-math"), move |_| { trunc_math_op.clone() });
-    let ceil_math_op = Atom::gnd(CeilMathOp {});
-    tref.register_token(regex(r"ceil-math"), move |_| { ceil_math_op.clone() });
-    let floor_math_op = Atom::gnd(FloorMathOp{});
-    tref.register_token(regex(r"floor-math"), move |_| { floor_math_op.clone() });
-    let round_math_op = Atom::gnd(RoundMathOp{});
-    tref.register_token(regex(r"round-math"), move |_| { round_math_op.clone() });
-    let sin_math_op = Atom::gnd(SinMathOp{});
-    tref.register_token(regex(r"sin-math"), move |_| { sin_math_op.clone() });
-    let asin_math_op = Atom::gnd(AsinMathOp{});
-    tref.register_token(regex(r"asin-math"), move |_| { asin_math_op.clone() });
-    let cos_math_op = Atom::gnd(CosMathOp{});
-    tref.register_token(regex(r"cos-math"), move |_| { cos_math_op.clone() });
-    let acos_math_op = Atom::gnd(AcosMathOp{});
-    tref.register_token(regex(r"acos-math"), move |_| { acos_math_op.clone() });
-    let tan_math_op = Atom::gnd(TanMathOp{});
-    tref.register_token(regex(r"tan-math"), move |_| { tan_math_op.clone() });
-    let atan_math_op = Atom::gnd(AtanMathOp{});
-    tref.register_token(regex(r"atan-math"), move |_| { atan_math_op.clone() });
-    let isnan_math_op = Atom::gnd(IsNanMathOp{});
-    tref.register_token(regex(r"isnan-math"), move |_| { isnan_math_op.clone() });
-    let isinf_math_op = Atom::gnd(IsInfMathOp{});
-    tref.register_token(regex(r"isinf-math"), move |_| { isinf_math_op.clone() });
+    tref.register_op::<PowMathOp>();
+    tref.register_op::<SqrtMathOp>();
+    tref.register_op::<AbsMathOp>();
+    tref.register_op::<LogMathOp>();
+    tref.register_op::<TruncMathOp>();
+    tref.register_op::<CeilMathOp>();
+    tref.register_op::<FloorMathOp>();
+    tref.register_op::<RoundMathOp>();
+    tref.register_op::<SinMathOp>();
+    tref.register_op::<AsinMathOp>();
+    tref.register_op::<CosMathOp>();
+    tref.register_op::<AcosMathOp>();
+    tref.register_op::<TanMathOp>();
+    tref.register_op::<AtanMathOp>();
+    tref.register_op::<IsNanMathOp>();
+    tref.register_op::<IsInfMathOp>();
     tref.register_token(regex(r"PI"),
                         |_| { Atom::gnd(Number::Float(std::f64::consts::PI)) });
     tref.register_token(regex(r"EXP"),
@@ -448,27 +136,28 @@ pub(super) fn register_context_independent_tokens(tref: &mut Tokenizer) {
 mod tests {
     use super::*;
     use crate::metta::runner::run_program;
+    use hyperon_atom::gnd::bool::Bool;
 
     #[test]
     fn metta_pow_math() {
         assert_eq!(run_program(&format!("!(pow-math 5 2)")), Ok(vec![vec![expr!({Number::Integer(5_i64.pow(2))})]]));
         assert_eq!(run_program(&format!("!(pow-math 5 200000000000000)")), Ok(vec![vec![expr!("Error" ({ PowMathOp{} } {Number::Integer(5)} {Number::Integer(200000000000000)}) "power argument is too big, try using float value")]]));
         assert_eq!(run_program(&format!("!(pow-math 5.5 2.3)")), Ok(vec![vec![expr!({Number::Float(5.5_f64.powf(2.3))})]]));
-        assert_eq!(run_program(&format!("!(pow-math A 2)")), Ok(vec![vec![expr!("Error" ({ PowMathOp{} } "A" {Number::Integer(2)}) "pow-math expects two arguments: number (base) and number (power)")]]));
+        assert_eq!(run_program(&format!("!(pow-math A 2)")), Ok(vec![vec![expr!("Error" ({ PowMathOp{} } "A" {Number::Integer(2)}) "pow-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_sqrt_math() {
         assert_eq!(run_program(&format!("!(sqrt-math 4)")), Ok(vec![vec![expr!({Number::Integer(2)})]]));
         assert_eq!(run_program(&format!("!(let $sqrt (sqrt-math -4) (isnan-math $sqrt))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(sqrt-math A)")), Ok(vec![vec![expr!("Error" ({ SqrtMathOp{} } "A") "sqrt-math expects one argument: number")]]));
+        assert_eq!(run_program(&format!("!(sqrt-math A)")), Ok(vec![vec![expr!("Error" ({ SqrtMathOp{} } "A") "sqrt-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_abs_math() {
         assert_eq!(run_program(&format!("!(abs-math 4)")), Ok(vec![vec![expr!({Number::Integer(4)})]]));
         assert_eq!(run_program(&format!("!(abs-math -5)")), Ok(vec![vec![expr!({Number::Integer(5)})]]));
-        assert_eq!(run_program(&format!("!(abs-math A)")), Ok(vec![vec![expr!("Error" ({ AbsMathOp{} } "A") "abs-math expects one argument: number")]]));
+        assert_eq!(run_program(&format!("!(abs-math A)")), Ok(vec![vec![expr!("Error" ({ AbsMathOp{} } "A") "abs-math expects Number as argument 1")]]));
     }
 
     #[test]
@@ -481,84 +170,84 @@ mod tests {
     #[test]
     fn metta_trunc_math() {
         assert_eq!(run_program(&format!("!(trunc-math 2.4)")), Ok(vec![vec![expr!({Number::Integer(2)})]]));
-        assert_eq!(run_program(&format!("!(trunc-math A)")), Ok(vec![vec![expr!("Error" ({ TruncMathOp{} } "A") "trunc-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(trunc-math A)")), Ok(vec![vec![expr!("Error" ({ TruncMathOp{} } "A") "trunc-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_ceil_math() {
         assert_eq!(run_program(&format!("!(ceil-math 2.4)")), Ok(vec![vec![expr!({Number::Integer(3)})]]));
         assert_eq!(run_program(&format!("!(ceil-math -2.4)")), Ok(vec![vec![expr!({Number::Integer(-2)})]]));
-        assert_eq!(run_program(&format!("!(ceil-math A)")), Ok(vec![vec![expr!("Error" ({ CeilMathOp{} } "A") "ceil-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(ceil-math A)")), Ok(vec![vec![expr!("Error" ({ CeilMathOp{} } "A") "ceil-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_floor_math() {
         assert_eq!(run_program(&format!("!(floor-math 2.4)")), Ok(vec![vec![expr!({Number::Integer(2)})]]));
         assert_eq!(run_program(&format!("!(floor-math -2.4)")), Ok(vec![vec![expr!({Number::Integer(-3)})]]));
-        assert_eq!(run_program(&format!("!(floor-math A)")), Ok(vec![vec![expr!("Error" ({ FloorMathOp{} } "A") "floor-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(floor-math A)")), Ok(vec![vec![expr!("Error" ({ FloorMathOp{} } "A") "floor-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_round_math() {
         assert_eq!(run_program(&format!("!(round-math 2.4)")), Ok(vec![vec![expr!({Number::Integer(2)})]]));
         assert_eq!(run_program(&format!("!(round-math -2.7)")), Ok(vec![vec![expr!({Number::Integer(-3)})]]));
-        assert_eq!(run_program(&format!("!(round-math A)")), Ok(vec![vec![expr!("Error" ({ RoundMathOp{} } "A") "round-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(round-math A)")), Ok(vec![vec![expr!("Error" ({ RoundMathOp{} } "A") "round-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_sin_math() {
         assert_eq!(run_program(&format!("!(sin-math 0)")), Ok(vec![vec![expr!({Number::Integer(0)})]]));
         assert_eq!(run_program(&format!("!(let $sin (sin-math 1.570796327) (< (- $sin 1.0) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(sin-math A)")), Ok(vec![vec![expr!("Error" ({ SinMathOp{} } "A") "sin-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(sin-math A)")), Ok(vec![vec![expr!("Error" ({ SinMathOp{} } "A") "sin-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_asin_math() {
         assert_eq!(run_program(&format!("!(asin-math 0)")), Ok(vec![vec![expr!({Number::Integer(0)})]]));
         assert_eq!(run_program(&format!("!(let $sin (sin-math 1) (< (- (asin-math $sin) 1.0) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(asin-math A)")), Ok(vec![vec![expr!("Error" ({ AsinMathOp{} } "A") "asin-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(asin-math A)")), Ok(vec![vec![expr!("Error" ({ AsinMathOp{} } "A") "asin-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_cos_math() {
         assert_eq!(run_program(&format!("!(cos-math 0)")), Ok(vec![vec![expr!({Number::Integer(1)})]]));
         assert_eq!(run_program(&format!("!(let $cos (cos-math 1.570796327) (< (- $cos 0.0) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(cos-math A)")), Ok(vec![vec![expr!("Error" ({ CosMathOp{} } "A") "cos-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(cos-math A)")), Ok(vec![vec![expr!("Error" ({ CosMathOp{} } "A") "cos-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_acos_math() {
         assert_eq!(run_program(&format!("!(acos-math 1)")), Ok(vec![vec![expr!({Number::Integer(0)})]]));
         assert_eq!(run_program(&format!("!(let $cos (cos-math 1) (< (- (acos-math $cos) 1.0) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(acos-math A)")), Ok(vec![vec![expr!("Error" ({ AcosMathOp{} } "A") "acos-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(acos-math A)")), Ok(vec![vec![expr!("Error" ({ AcosMathOp{} } "A") "acos-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_tan_math() {
         assert_eq!(run_program(&format!("!(tan-math 0)")), Ok(vec![vec![expr!({Number::Integer(0)})]]));
         assert_eq!(run_program(&format!("!(let $tan (tan-math 0.78539816339) (< (- $tan 1.0) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(tan-math A)")), Ok(vec![vec![expr!("Error" ({ TanMathOp{} } "A") "tan-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(tan-math A)")), Ok(vec![vec![expr!("Error" ({ TanMathOp{} } "A") "tan-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_atan_math() {
         assert_eq!(run_program(&format!("!(atan-math 0)")), Ok(vec![vec![expr!({Number::Integer(0)})]]));
         assert_eq!(run_program(&format!("!(let $atan (atan-math 1) (< (- $atan 0.78539816339) 1e-10))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(atan-math A)")), Ok(vec![vec![expr!("Error" ({ AtanMathOp{} } "A") "atan-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(atan-math A)")), Ok(vec![vec![expr!("Error" ({ AtanMathOp{} } "A") "atan-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_isnan_math() {
         assert_eq!(run_program(&format!("!(isnan-math 0)")), Ok(vec![vec![expr!({Bool(false)})]]));
         assert_eq!(run_program(&format!("!(let $log (log-math 0 0) (isnan-math $log))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(isnan-math A)")), Ok(vec![vec![expr!("Error" ({ IsNanMathOp{} } "A") "isnan-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(isnan-math A)")), Ok(vec![vec![expr!("Error" ({ IsNanMathOp{} } "A") "isnan-math expects Number as argument 1")]]));
     }
 
     #[test]
     fn metta_isinf_math() {
         assert_eq!(run_program(&format!("!(isinf-math 0)")), Ok(vec![vec![expr!({Bool(false)})]]));
         assert_eq!(run_program(&format!("!(let $log (log-math 5 0) (isinf-math $log))")), Ok(vec![vec![expr!({Bool(true)})]]));
-        assert_eq!(run_program(&format!("!(isinf-math A)")), Ok(vec![vec![expr!("Error" ({ IsInfMathOp{} } "A") "isinf-math expects one argument: input number")]]));
+        assert_eq!(run_program(&format!("!(isinf-math A)")), Ok(vec![vec![expr!("Error" ({ IsInfMathOp{} } "A") "isinf-math expects Number as argument 1")]]));
     }
 
     #[test]
@@ -570,7 +259,7 @@ mod tests {
         let res = PowMathOp {}.execute(&mut vec![expr!({Number::Float(5.5)}), expr!({Number::Float(2.3)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Float(5.5_f64.powf(2.3))})]);
         let res = PowMathOp {}.execute(&mut vec![expr!("A"), expr!({Number::Integer(2)})]);
-        assert_eq!(res, Err(ExecError::from("pow-math expects two arguments: number (base) and number (power)")));
+        assert_eq!(res, Err(ExecError::from("pow-math expects Number as argument 1")));
     }
 
     #[test]
@@ -581,7 +270,7 @@ mod tests {
         let res_f64: f64 = res.unwrap().get(0).and_then(Number::from_atom).unwrap().into();
         assert!(res_f64.is_nan());
         let res = SqrtMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("sqrt-math expects one argument: number")));
+        assert_eq!(res, Err(ExecError::from("sqrt-math expects Number as argument 1")));
     }
 
     #[test]
@@ -591,7 +280,7 @@ mod tests {
         let res = AbsMathOp {}.execute(&mut vec![expr!({Number::Integer(-4)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Integer(4)})]);
         let res = AbsMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("abs-math expects one argument: number")));
+        assert_eq!(res, Err(ExecError::from("abs-math expects Number as argument 1")));
     }
 
     #[test]
@@ -605,7 +294,7 @@ mod tests {
         let res_f64: f64 = res.unwrap().get(0).and_then(Number::from_atom).unwrap().into();
         assert!(res_f64.is_infinite());
         let res = LogMathOp {}.execute(&mut vec![expr!({Number::Integer(2)}), expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("log-math expects two arguments: base (number) and input value (number)")));
+        assert_eq!(res, Err(ExecError::from("log-math expects Number as argument 2")));
     }
 
     #[test]
@@ -613,7 +302,7 @@ mod tests {
         let res = TruncMathOp {}.execute(&mut vec![expr!({Number::Float(2.4)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Integer(2)})]);
         let res = TruncMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("trunc-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("trunc-math expects Number as argument 1")));
     }
 
     #[test]
@@ -623,7 +312,7 @@ mod tests {
         let res = CeilMathOp {}.execute(&mut vec![expr!({Number::Float(-2.4)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Integer(-2)})]);
         let res = CeilMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("ceil-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("ceil-math expects Number as argument 1")));
     }
 
     #[test]
@@ -633,7 +322,7 @@ mod tests {
         let res = FloorMathOp {}.execute(&mut vec![expr!({Number::Float(-2.4)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Integer(-3)})]);
         let res = FloorMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("floor-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("floor-math expects Number as argument 1")));
     }
 
     #[test]
@@ -643,7 +332,7 @@ mod tests {
         let res = RoundMathOp {}.execute(&mut vec![expr!({Number::Float(-2.7)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Number::Integer(-3)})]);
         let res = RoundMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("round-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("round-math expects Number as argument 1")));
     }
 
     #[test]
@@ -655,7 +344,7 @@ mod tests {
         let abs_difference = (res_f64 - 1.0).abs();
         assert!(abs_difference < 1e-10);
         let res = SinMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("sin-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("sin-math expects Number as argument 1")));
     }
 
     #[test]
@@ -667,7 +356,7 @@ mod tests {
         let abs_difference = (res_f64 - std::f64::consts::FRAC_PI_2).abs();
         assert!(abs_difference < 1e-10);
         let res = AsinMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("asin-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("asin-math expects Number as argument 1")));
     }
 
     #[test]
@@ -679,7 +368,7 @@ mod tests {
         let abs_difference = (res_f64 - 0.0).abs();
         assert!(abs_difference < 1e-10);
         let res = CosMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("cos-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("cos-math expects Number as argument 1")));
     }
 
     #[test]
@@ -691,7 +380,7 @@ mod tests {
         let abs_difference = (res_f64 - std::f64::consts::FRAC_PI_2).abs();
         assert!(abs_difference < 1e-10);
         let res = AcosMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("acos-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("acos-math expects Number as argument 1")));
     }
 
     #[test]
@@ -703,7 +392,7 @@ mod tests {
         let abs_difference = (res_f64 - 1.0).abs();
         assert!(abs_difference < 1e-10);
         let res = TanMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("tan-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("tan-math expects Number as argument 1")));
     }
 
     #[test]
@@ -715,7 +404,7 @@ mod tests {
         let abs_difference = (res_f64 - std::f64::consts::FRAC_PI_4).abs();
         assert!(abs_difference < 1e-10);
         let res = AtanMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("atan-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("atan-math expects Number as argument 1")));
     }
 
     #[test]
@@ -725,7 +414,7 @@ mod tests {
         let res = IsNanMathOp {}.execute(&mut vec![expr!({Number::Float(f64::NAN)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Bool(true)})]);
         let res = IsNanMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("isnan-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("isnan-math expects Number as argument 1")));
     }
 
     #[test]
@@ -735,6 +424,6 @@ mod tests {
         let res = IsInfMathOp {}.execute(&mut vec![expr!({Number::Float(f64::INFINITY)})]).expect("No result returned");
         assert_eq!(res, vec![expr!({Bool(true)})]);
         let res = IsInfMathOp {}.execute(&mut vec![expr!("A")]);
-        assert_eq!(res, Err(ExecError::from("isinf-math expects one argument: input number")));
+        assert_eq!(res, Err(ExecError::from("isinf-math expects Number as argument 1")));
     }
 }
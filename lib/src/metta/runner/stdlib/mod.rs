@@ -67,6 +67,7 @@ fn register_context_dependent_tokens(tref: &mut Tokenizer, tokenizer: Shared<Tok
 
     atom::register_context_dependent_tokens(tref, space);
     core::register_context_dependent_tokens(tref, space, metta);
+    space::register_context_dependent_tokens(tref, tokenizer.clone(), space, metta);
     module::register_context_dependent_tokens(tref, tokenizer.clone(), metta);
     #[cfg(feature = "pkg_mgmt")]
     package::register_context_dependent_tokens(tref, metta);
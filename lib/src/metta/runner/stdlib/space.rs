@@ -2,12 +2,29 @@ use hyperon_atom::*;
 use hyperon_space::*;
 use crate::metta::*;
 use crate::metta::text::Tokenizer;
-use crate::metta::runner::stdlib::{grounded_op, unit_result, regex};
+use crate::metta::runner::stdlib::{grounded_op, unit_result, regex, interpret};
+use crate::metta::runner::{Metta, PragmaSettings};
 use hyperon_atom::gnd::GroundedFunctionAtom;
+use hyperon_atom::gnd::op::{MettaType, Unevaluated};
+use hyperon_macros::metta_op;
+
+use crate::space::persistent::PersistentSpace;
+use crate::space::dump::{TokenizerFallback, save_space_to_file, load_space_from_file};
+use crate::space::remote::RemoteSpace;
+use hyperon_atom::gnd::str::{Str, ATOM_TYPE_STRING};
+use hyperon_atom::gnd::number::Number;
+use hyperon_space::aggregate::{Aggregate, group_by};
+use hyperon_space::reactive::StandingQuery;
+use hyperon_space::merge::{SpaceMerge, MergeConflict};
+use hyperon_space::view::{UnionSpace, UnionPolicy, OverlaySpace, FilteredSpace, AtomFilter, RenamingSpace};
+use hyperon_common::shared::Shared;
 
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::fmt::Display;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct NewSpaceOp {}
@@ -35,6 +52,209 @@ impl CustomExecute for NewSpaceOp {
     }
 }
 
+thread_local! {
+    static OPENED_SPACES: RefCell<HashMap<PathBuf, WeakDynSpace>> = RefCell::new(HashMap::new());
+}
+
+/// Opens persistent space stored in a file. Spaces which are already opened
+/// are cached by the current thread and returned again while they are
+/// referenced, thus the same file is never written by two space instances.
+/// Cache keeps weak references, thus file is closed when the space is not
+/// used anymore.
+#[metta_op(name = "open-space")]
+pub fn open_space(path: &str) -> Result<DynSpace, ExecError> {
+    let opened = std::fs::canonicalize(path).ok()
+        .and_then(|key| OPENED_SPACES.with(|opened| opened.borrow().get(&key).and_then(WeakDynSpace::upgrade)));
+    if let Some(space) = opened {
+        return Ok(space);
+    }
+    let space = PersistentSpace::open(path).map_err(ExecError::Runtime)?;
+    let key = std::fs::canonicalize(space.path()).map_err(|e| ExecError::Runtime(e.to_string()))?;
+    let space = DynSpace::new(space);
+    OPENED_SPACES.with(|opened| {
+        let mut opened = opened.borrow_mut();
+        opened.retain(|_, space| space.upgrade().is_some());
+        opened.insert(key, space.downgrade());
+    });
+    Ok(space)
+}
+
+/// Connects to the space exposed by [SpaceServer](crate::space::remote::SpaceServer).
+#[metta_op(name = "connect-space")]
+pub fn connect_space(url: &str) -> Result<DynSpace, ExecError> {
+    let space = RemoteSpace::connect(url).map_err(ExecError::Runtime)?;
+    Ok(DynSpace::new(space))
+}
+
+/// Evaluates expression inside a transaction on the space. Transaction is
+/// rolled back when evaluation fails or returns an error.
+#[derive(Clone, Debug)]
+pub struct WithTransactionOp {
+    space: DynSpace,
+    settings: PragmaSettings,
+}
+
+grounded_op!(WithTransactionOp, "with-transaction");
+
+impl WithTransactionOp {
+    pub fn new(space: DynSpace, settings: PragmaSettings) -> Self {
+        Self{ space, settings }
+    }
+}
+
+impl Grounded for WithTransactionOp {
+    fn type_(&self) -> Atom {
+        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_SPACE, ATOM_TYPE_ATOM, ATOM_TYPE_ATOM])
+    }
+
+    fn as_execute(&self) -> Option<&dyn CustomExecute> {
+        Some(self)
+    }
+}
+
+impl CustomExecute for WithTransactionOp {
+    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
+        let arg_error = || ExecError::from("with-transaction expects two arguments: space and atom");
+        let space = args.get(0).ok_or_else(arg_error)?;
+        let atom = args.get(1).ok_or_else(arg_error)?;
+        let space = Atom::as_gnd::<DynSpace>(space).ok_or("with-transaction expects a space as the first argument")?;
+        let transaction = space.begin_transaction();
+        let results = interpret(self.space.clone(), atom, self.settings.clone())
+            .map_err(ExecError::from)?;
+        if results.iter().any(atom_is_error) {
+            transaction.rollback();
+        } else {
+            transaction.commit();
+        }
+        Ok(results)
+    }
+}
+
+#[derive(Default)]
+struct Subscriptions {
+    next_id: usize,
+    queries: HashMap<usize, SpaceObserverRef<StandingQuery>>,
+}
+
+/// Handle of the standing query registered by [OnMatchOp]. Query is
+/// unsubscribed by [UnsubscribeOp], dropping the handle doesn't release it.
+#[derive(Clone)]
+pub struct Subscription {
+    id: usize,
+    subscriptions: Rc<RefCell<Subscriptions>>,
+}
+
+impl Subscription {
+    fn unsubscribe(&self) -> bool {
+        self.subscriptions.borrow_mut().queries.remove(&self.id).is_some()
+    }
+}
+
+impl PartialEq for Subscription {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && Rc::ptr_eq(&self.subscriptions, &other.subscriptions)
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Subscription({})", self.id)
+    }
+}
+
+impl Display for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(Subscription {})", self.id)
+    }
+}
+
+impl Grounded for Subscription {
+    fn type_(&self) -> Atom {
+        <&Self as MettaType>::metta_type()
+    }
+}
+
+impl MettaType for &Subscription {
+    fn metta_type() -> Atom {
+        Atom::sym("Subscription")
+    }
+}
+
+impl<'a> TryFrom<&'a Atom> for &'a Subscription {
+    type Error = &'static str;
+    fn try_from(atom: &'a Atom) -> Result<Self, Self::Error> {
+        atom.as_gnd::<Subscription>().ok_or("Atom is not a subscription")
+    }
+}
+
+/// Subscribes callback on the results of the query to the space. When space
+/// modification adds new results of the query the callback is evaluated for
+/// each of them with the query variables replaced by their values. Callbacks
+/// are evaluated in the module space after the current top-level expression
+/// is evaluated. Returns [Subscription] handle which is passed to
+/// [UnsubscribeOp] to stop the query.
+#[derive(Clone)]
+pub struct OnMatchOp {
+    space: DynSpace,
+    reactions: Shared<Vec<(DynSpace, Atom)>>,
+    subscriptions: Rc<RefCell<Subscriptions>>,
+}
+
+grounded_op!(OnMatchOp, "on-match");
+
+impl OnMatchOp {
+    pub fn new(space: DynSpace, metta: &Metta) -> Self {
+        Self{ space, reactions: metta.reactions().clone(), subscriptions: Rc::new(RefCell::new(Subscriptions::default())) }
+    }
+}
+
+impl std::fmt::Debug for OnMatchOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OnMatchOp")
+    }
+}
+
+impl Grounded for OnMatchOp {
+    fn type_(&self) -> Atom {
+        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_SPACE, ATOM_TYPE_ATOM, ATOM_TYPE_ATOM, Atom::sym("Subscription")])
+    }
+
+    fn as_execute(&self) -> Option<&dyn CustomExecute> {
+        Some(self)
+    }
+}
+
+impl CustomExecute for OnMatchOp {
+    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
+        let arg_error = || ExecError::from("on-match expects three arguments: space, pattern and callback");
+        let space = args.get(0).ok_or_else(arg_error)?;
+        let pattern = args.get(1).ok_or_else(arg_error)?;
+        let callback = args.get(2).ok_or_else(arg_error)?.clone();
+        let space = Atom::as_gnd::<DynSpace>(space).ok_or("on-match expects a space as the first argument")?;
+        let reactions = self.reactions.clone();
+        let module_space = self.space.clone();
+        let query = StandingQuery::new(&*space.borrow(), pattern.clone())
+            .map_err(ExecError::from)?
+            .with_callback(move |delta| {
+                for bindings in &delta.added {
+                    let atom = matcher::apply_bindings_to_atom_move(callback.clone(), bindings);
+                    reactions.borrow_mut().push((module_space.clone(), atom));
+                }
+            });
+        let observer = space.common().register_observer(query);
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let id = subscriptions.next_id;
+        subscriptions.next_id += 1;
+        subscriptions.queries.insert(id, observer);
+        Ok(vec![Atom::gnd(Subscription{ id, subscriptions: self.subscriptions.clone() })])
+    }
+}
+
+#[metta_op(name = "unsubscribe")]
+pub fn unsubscribe(subscription: &Subscription) {
+    subscription.unsubscribe();
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct StateAtom {
     state: Rc<RefCell<(Atom, Atom)>>
@@ -121,6 +341,12 @@ impl CustomExecute for ChangeStateOp {
     }
 }
 
+/// Error returned when atoms of the space cannot be visited, see
+/// [Space::visit].
+fn traverse_error() -> ExecError {
+    ExecError::Runtime("Unsupported Operation. Can't traverse atoms in this space".to_string())
+}
+
 #[derive(Clone, Debug)]
 pub struct GetAtomsOp {}
 
@@ -144,10 +370,236 @@ impl CustomExecute for GetAtomsOp {
         let mut result = Vec::new();
         space.borrow().visit(&mut |atom: std::borrow::Cow<Atom>| {
             result.push(make_variables_unique(atom.into_owned()))
-        }).map_or(Err(ExecError::Runtime("Unsupported Operation. Can't traverse atoms in this space".to_string())), |_| Ok(result))
+        }).map_or(Err(traverse_error()), |_| Ok(result))
+    }
+}
+
+#[metta_op(name = "match-count")]
+pub fn match_count(space: &DynSpace, pattern: Unevaluated) -> Result<Number, ExecError> {
+    let count = space.borrow().aggregate(pattern.0, &Aggregate::Count).map_err(ExecError::Runtime)?;
+    count.as_ref().and_then(Number::from_atom)
+        .ok_or_else(|| ExecError::Runtime(format!("Space {} returned no count of the results", space)))
+}
+
+#[metta_op(name = "match-aggregate")]
+pub fn match_aggregate(space: &DynSpace, pattern: Unevaluated, aggregate: Unevaluated) -> Result<Vec<Atom>, ExecError> {
+    let aggregate = Aggregate::from_atom(aggregate.0).map_err(ExecError::Runtime)?;
+    let value = space.borrow().aggregate(pattern.0, &aggregate).map_err(ExecError::Runtime)?;
+    Ok(value.into_iter().map(make_variables_unique).collect())
+}
+
+#[metta_op(name = "match-group")]
+pub fn match_group(space: &DynSpace, pattern: Unevaluated, key: Unevaluated, aggregate: Unevaluated) -> Result<Vec<Atom>, ExecError> {
+    let aggregate = Aggregate::from_atom(aggregate.0).map_err(ExecError::Runtime)?;
+    let results = space.borrow().query(pattern.0);
+    let groups = group_by(results, key.0, &aggregate).map_err(ExecError::Runtime)?;
+    Ok(groups.into_iter()
+        .map(|(key, value)| make_variables_unique(Atom::expr([key, value])))
+        .collect())
+}
+
+/// Returns in-memory fork of the space if space supports forking.
+fn fork_dyn_space(space: &DynSpace) -> Option<GroundingSpace> {
+    let space = space.borrow();
+    let space = space.as_any();
+    space.downcast_ref::<GroundingSpace>().map(GroundingSpace::fork)
+        .or_else(|| space.downcast_ref::<PersistentSpace>().map(PersistentSpace::fork))
+}
+
+#[metta_op(name = "fork-space")]
+pub fn fork_space(space: &DynSpace) -> Result<DynSpace, ExecError> {
+    let fork = fork_dyn_space(space)
+        .ok_or_else(|| ExecError::Runtime(format!("Space {} cannot be forked", space)))?;
+    Ok(DynSpace::new(fork))
+}
+
+#[metta_op(name = "compare-spaces")]
+pub fn compare_spaces(old: &DynSpace, new: &DynSpace) -> Result<ExpressionAtom, ExecError> {
+    let diff = old.diff(new).map_err(|()| traverse_error())?;
+    let added = diff.added.into_iter().map(make_variables_unique).collect::<Vec<_>>();
+    let removed = diff.removed.into_iter().map(make_variables_unique).collect::<Vec<_>>();
+    Ok(ExpressionAtom::new(vec![Atom::expr(added), Atom::expr(removed)].into()))
+}
+
+#[metta_op(name = "reset-space-to")]
+pub fn reset_space_to(target: &DynSpace, source: &DynSpace) -> Result<(), ExecError> {
+    let diff = target.diff(source).map_err(|()| traverse_error())?;
+    diff.apply(&mut *target.borrow_mut());
+    Ok(())
+}
+
+fn merge_conflicts_expr(conflicts: Vec<MergeConflict>) -> ExpressionAtom {
+    let change = |change: Option<Atom>| match change {
+        Some(atom) => Atom::expr([Atom::sym("replaced"), atom]),
+        None => Atom::sym("removed"),
+    };
+    ExpressionAtom::new(conflicts.into_iter()
+        .map(|MergeConflict{ atom, ours, theirs }| make_variables_unique(Atom::expr([atom, change(ours), change(theirs)])))
+        .collect::<Vec<_>>().into())
+}
+
+fn merge_dyn_spaces(base: &DynSpace, ours: &DynSpace, theirs: &DynSpace) -> Result<SpaceMerge, ExecError> {
+    hyperon_space::merge::merge_spaces(&*base.borrow(), &*ours.borrow(), &*theirs.borrow())
+        .map_err(|()| traverse_error())
+}
+
+#[metta_op(name = "merge-spaces")]
+pub fn merge_spaces(base: &DynSpace, ours: &DynSpace, theirs: &DynSpace) -> Result<ExpressionAtom, ExecError> {
+    let merge = merge_dyn_spaces(base, ours, theirs)?;
+    let mut merged = match fork_dyn_space(base) {
+        Some(fork) => fork,
+        None => {
+            let mut atoms = Vec::new();
+            base.borrow().visit(&mut |atom: std::borrow::Cow<Atom>| atoms.push(atom.into_owned()))
+                .map_err(|()| traverse_error())?;
+            GroundingSpace::from_vec(atoms)
+        },
+    };
+    merge.apply(&mut merged);
+    let conflicts = Atom::Expression(merge_conflicts_expr(merge.conflicts));
+    Ok(ExpressionAtom::new(vec![Atom::gnd(DynSpace::new(merged)), conflicts].into()))
+}
+
+#[metta_op(name = "sync-spaces")]
+pub fn sync_spaces(base: &DynSpace, ours: &DynSpace, theirs: &DynSpace, target: &DynSpace) -> Result<ExpressionAtom, ExecError> {
+    let merge = merge_dyn_spaces(base, ours, theirs)?;
+    merge.replay(target);
+    Ok(merge_conflicts_expr(merge.conflicts))
+}
+
+/// Writes atoms of the space into a binary dump file. Grounded atoms which
+/// cannot be encoded are written in a text form when they can be parsed back
+/// by the module tokenizer.
+#[derive(Clone, Debug)]
+pub struct SaveSpaceOp {
+    tokenizer: Shared<Tokenizer>,
+}
+
+grounded_op!(SaveSpaceOp, "save-space");
+
+impl SaveSpaceOp {
+    pub fn new(tokenizer: Shared<Tokenizer>) -> Self {
+        Self{ tokenizer }
+    }
+}
+
+impl Grounded for SaveSpaceOp {
+    fn type_(&self) -> Atom {
+        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_SPACE, ATOM_TYPE_STRING, UNIT_TYPE])
+    }
+
+    fn as_execute(&self) -> Option<&dyn CustomExecute> {
+        Some(self)
+    }
+}
+
+impl CustomExecute for SaveSpaceOp {
+    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
+        let arg_error = || ExecError::from("save-space expects two arguments: space and path to the dump file");
+        let space = args.get(0).ok_or_else(arg_error)?;
+        let space = Atom::as_gnd::<DynSpace>(space).ok_or("save-space expects a space as the first argument")?;
+        let path = args.get(1).and_then(Atom::as_gnd::<Str>).ok_or_else(arg_error)?;
+        let tokenizer = self.tokenizer.borrow();
+        let fallback = TokenizerFallback(&tokenizer);
+        save_space_to_file(&*space.borrow(), path.as_str(), Some(&fallback))
+            .map_err(ExecError::Runtime)?;
+        unit_result()
+    }
+}
+
+/// Loads new space from the binary dump file written by `save-space`.
+#[derive(Clone, Debug)]
+pub struct LoadSpaceOp {
+    tokenizer: Shared<Tokenizer>,
+}
+
+grounded_op!(LoadSpaceOp, "load-space");
+
+impl LoadSpaceOp {
+    pub fn new(tokenizer: Shared<Tokenizer>) -> Self {
+        Self{ tokenizer }
+    }
+}
+
+impl Grounded for LoadSpaceOp {
+    fn type_(&self) -> Atom {
+        Atom::expr([ARROW_SYMBOL, ATOM_TYPE_STRING, ATOM_TYPE_SPACE])
+    }
+
+    fn as_execute(&self) -> Option<&dyn CustomExecute> {
+        Some(self)
+    }
+}
+
+impl CustomExecute for LoadSpaceOp {
+    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
+        let arg_error = || ExecError::from("load-space expects one argument: path to the dump file");
+        let path = args.get(0).and_then(Atom::as_gnd::<Str>).ok_or_else(arg_error)?;
+        let tokenizer = self.tokenizer.borrow();
+        let fallback = TokenizerFallback(&tokenizer);
+        let space = load_space_from_file(path.as_str(), Some(&fallback))
+            .map_err(ExecError::Runtime)?;
+        Ok(vec![Atom::gnd(DynSpace::new(space))])
     }
 }
 
+#[metta_op(name = "union-space")]
+pub fn union_space(policy: Unevaluated, spaces: &ExpressionAtom) -> Result<DynSpace, ExecError> {
+    let policy = match policy.0.to_string().as_str() {
+        "all" => UnionPolicy::All,
+        "first" => UnionPolicy::First,
+        _ => return Err("union-space expects all or first as a policy".into()),
+    };
+    let spaces = spaces.children().iter()
+        .map(|space| Atom::as_gnd::<DynSpace>(space).cloned()
+            .ok_or_else(|| ExecError::from("union-space expects an expression of spaces as the second argument")))
+        .collect::<Result<Vec<DynSpace>, ExecError>>()?;
+    Ok(DynSpace::new(UnionSpace::new(spaces, policy)))
+}
+
+#[metta_op(name = "overlay-space")]
+pub fn overlay_space(top: &DynSpace, base: &DynSpace) -> DynSpace {
+    DynSpace::new(OverlaySpace::new(top.clone(), base.clone()))
+}
+
+#[metta_op(name = "filter-space")]
+pub fn filter_space(space: &DynSpace, pattern: Unevaluated) -> DynSpace {
+    DynSpace::new(FilteredSpace::new(space.clone(), AtomFilter::Pattern(pattern.0.clone())))
+}
+
+#[metta_op(name = "rename-space")]
+pub fn rename_space(space: &DynSpace, names: &ExpressionAtom) -> Result<DynSpace, ExecError> {
+    let arg_error = || ExecError::from("rename-space expects an expression of (<space name> <view name>) pairs as the second argument");
+    let names = names.children().iter()
+        .map(|pair| match pair {
+            Atom::Expression(pair) => match pair.children().as_slice() {
+                [Atom::Symbol(from), Atom::Symbol(to)] => Ok((from.clone(), to.clone())),
+                _ => Err(arg_error()),
+            },
+            _ => Err(arg_error()),
+        })
+        .collect::<Result<Vec<(SymbolAtom, SymbolAtom)>, ExecError>>()?;
+    Ok(DynSpace::new(RenamingSpace::new(space.clone(), names)))
+}
+
+#[metta_op(name = "space-stats")]
+pub fn space_stats(space: &DynSpace) -> Result<ExpressionAtom, ExecError> {
+    let stats = space.borrow().memory_stats()
+        .ok_or_else(|| ExecError::Runtime(format!("Space {} doesn't provide memory statistics", space)))?;
+    let int = |name: &str, value: usize| Atom::expr([Atom::sym(name), Atom::gnd(Number::Integer(value as i64))]);
+    Ok(ExpressionAtom::new(vec![
+        int("atoms", stats.atoms),
+        int("distinct-atoms", stats.distinct_atoms),
+        int("nodes", stats.nodes),
+        int("free-nodes", stats.free_nodes),
+        int("hashable-keys", stats.hashable_keys),
+        int("other-keys", stats.other_keys),
+        int("grounded-keys", stats.grounded_keys),
+        int("storage-bytes", stats.storage_bytes),
+        Atom::expr([Atom::sym("duplicate-ratio"), Atom::gnd(Number::Float(stats.duplicate_ratio()))]),
+    ].into()))
+}
+
 #[derive(Clone, Debug)]
 pub struct AddAtomOp {}
 
@@ -175,6 +627,13 @@ impl CustomExecute for AddAtomOp {
     }
 }
 
+#[metta_op(name = "add-atom-ttl")]
+pub fn add_atom_ttl(space: &DynSpace, atom: Unevaluated, seconds: Number) -> Result<(), ExecError> {
+    let ttl = Duration::try_from_secs_f64(seconds.into())
+        .map_err(|_| ExecError::from("add-atom-ttl expects a non-negative time to live"))?;
+    space.borrow_mut().add_with_ttl(atom.0.clone(), ttl).map_err(ExecError::Runtime)
+}
+
 #[derive(Clone, Debug)]
 pub struct RemoveAtomOp {}
 
@@ -203,13 +662,43 @@ impl CustomExecute for RemoveAtomOp {
     }
 }
 
+#[metta_op(name = "add-atoms")]
+pub fn add_atoms(space: &DynSpace, atoms: &ExpressionAtom) {
+    space.borrow_mut().add_all(atoms.children().to_vec());
+}
+
+#[metta_op(name = "remove-atoms-matching")]
+pub fn remove_atoms_matching(space: &DynSpace, pattern: Unevaluated) -> ExpressionAtom {
+    let removed = space.borrow_mut().remove_matching(pattern.0);
+    ExpressionAtom::new(removed.into_iter().map(make_variables_unique).collect::<Vec<_>>().into())
+}
+
+pub(super) fn register_context_dependent_tokens(tref: &mut Tokenizer, tokenizer: Shared<Tokenizer>, space: &DynSpace, metta: &Metta) {
+    let with_transaction_op = Atom::gnd(WithTransactionOp::new(space.clone(), metta.settings().clone()));
+    tref.register_token(regex(r"with-transaction"), move |_| { with_transaction_op.clone() });
+    let on_match_op = Atom::gnd(OnMatchOp::new(space.clone(), metta));
+    tref.register_token(regex(r"on-match"), move |_| { on_match_op.clone() });
+    let save_space_op = Atom::gnd(SaveSpaceOp::new(tokenizer.clone()));
+    tref.register_token(regex(r"save-space"), move |_| { save_space_op.clone() });
+    let load_space_op = Atom::gnd(LoadSpaceOp::new(tokenizer));
+    tref.register_token(regex(r"load-space"), move |_| { load_space_op.clone() });
+}
+
 pub(super) fn register_context_independent_tokens(tref: &mut Tokenizer) {
     let new_space_op = Atom::gnd(NewSpaceOp{});
     tref.register_token(regex(r"new-space"), move |_| { new_space_op.clone() });
+    tref.register_op::<OpenSpaceOp>();
+    tref.register_op::<ConnectSpaceOp>();
     let add_atom_op = Atom::gnd(AddAtomOp{});
     tref.register_token(regex(r"add-atom"), move |_| { add_atom_op.clone() });
+    tref.register_op::<AddAtomTtlOp>();
     let remove_atom_op = Atom::gnd(RemoveAtomOp{});
     tref.register_token(regex(r"remove-atom"), move |_| { remove_atom_op.clone() });
+    tref.register_op::<MatchCountOp>();
+    tref.register_op::<MatchAggregateOp>();
+    tref.register_op::<MatchGroupOp>();
+    tref.register_op::<AddAtomsOp>();
+    tref.register_op::<RemoveAtomsMatchingOp>();
     tref.register_function(GroundedFunctionAtom::new(
             r"_new-state".into(),
             expr!("->" t "Expression" ("StateMonad" t)),
@@ -221,6 +710,17 @@ pub(super) fn register_context_independent_tokens(tref: &mut Tokenizer) {
     tref.register_token(regex(r"get-state"), move |_| { get_state_op.clone() });
     let get_atoms_op = Atom::gnd(GetAtomsOp{});
     tref.register_token(regex(r"get-atoms"), move |_| { get_atoms_op.clone() });
+    tref.register_op::<ForkSpaceOp>();
+    tref.register_op::<UnsubscribeOp>();
+    tref.register_op::<CompareSpacesOp>();
+    tref.register_op::<ResetSpaceToOp>();
+    tref.register_op::<MergeSpacesOp>();
+    tref.register_op::<SyncSpacesOp>();
+    tref.register_op::<UnionSpaceOp>();
+    tref.register_op::<OverlaySpaceOp>();
+    tref.register_op::<FilterSpaceOp>();
+    tref.register_op::<RenameSpaceOp>();
+    tref.register_op::<SpaceStatsOp>();
 }
 
 #[cfg(test)]
@@ -228,9 +728,10 @@ mod tests {
     use super::*;
     use crate::metta::text::SExprParser;
     use crate::space::grounding::metta_space;
-    use crate::metta::runner::Metta;
     use hyperon_common::assert_eq_no_order;
     use hyperon_macros::metta;
+    use hyperon_atom::gnd::number::Number;
+    use crate::space::clock::ManualClock;
 
     #[test]
     fn mod_space_op() {
@@ -245,6 +746,327 @@ mod tests {
         assert_eq!(result[2], vec![Atom::expr([Atom::gnd(super::super::module::ModSpaceOp::new(runner.clone())), Atom::sym("stdlib")])]);
     }
 
+    #[test]
+    fn open_space_op() {
+        let path = std::env::temp_dir().join(format!("hyperon-open-space-op-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let program = format!(r#"
+            !(add-atom (open-space "{0}") (A B))
+            !(match (open-space "{0}") (A $x) $x)
+        "#, path.display());
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program.as_str())).unwrap();
+        assert_eq!(result, vec![vec![UNIT_ATOM], vec![expr!("B")]]);
+
+        let space = DynSpace::new(PersistentSpace::open(&path).unwrap());
+        assert_eq!(collect_atoms(&space), vec![expr!("A" "B")]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn open_space_op_drops_unused_spaces() {
+        let path = std::env::temp_dir().join(format!("hyperon-open-space-op-drop-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key = |path: &std::path::Path| std::fs::canonicalize(path).unwrap();
+        let is_cached = |path: &std::path::Path| OPENED_SPACES.with(|opened|
+            opened.borrow().get(&key(path)).and_then(WeakDynSpace::upgrade).is_some());
+
+        let space = open_space(path.to_str().unwrap()).unwrap();
+        assert!(is_cached(&path));
+        assert_eq!(open_space(path.to_str().unwrap()).unwrap(), space);
+        drop(space);
+        assert!(!is_cached(&path));
+
+        let other = std::env::temp_dir().join(format!("hyperon-open-space-op-other-{}.log", std::process::id()));
+        let _other_space = open_space(other.to_str().unwrap()).unwrap();
+        assert!(OPENED_SPACES.with(|opened| !opened.borrow().contains_key(&key(&path))));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&other);
+    }
+
+    #[test]
+    fn connect_space_op() {
+        let server = crate::space::remote::SpaceServer::bind("tcp://127.0.0.1:0").unwrap();
+        let url = server.local_url().unwrap();
+        std::thread::spawn(move || {
+            let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("A" "B")]));
+            server.serve(&space)
+        });
+        let program = format!(r#"
+            !(bind! &remote (connect-space "{0}"))
+            !(add-atom &remote (A C))
+            !(match &remote (A $x) $x)
+        "#, url);
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program.as_str())).unwrap();
+        assert_eq!(result[1], vec![UNIT_ATOM]);
+        assert_eq_no_order!(result[2], vec![expr!("B"), expr!("C")]);
+    }
+
+    #[test]
+    fn with_transaction_op() {
+        let program = r#"
+            !(with-transaction &self (add-atom &self (stored A)))
+            !(with-transaction &self (let $u (add-atom &self (stored B)) (Error (stored B) failed)))
+            !(match &self (stored $x) $x)
+        "#;
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result, vec![
+            vec![UNIT_ATOM],
+            vec![expr!("Error" ("stored" "B") "failed")],
+            vec![expr!("A")],
+        ]);
+    }
+
+    #[test]
+    fn with_transaction_op_keeps_expired_atoms_removed() {
+        let clock = ManualClock::new(std::time::SystemTime::UNIX_EPOCH);
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        runner.space().borrow_mut().as_any_mut().downcast_mut::<GroundingSpace>()
+            .expect("GroundingSpace is expected").set_clock(std::sync::Arc::new(clock.clone()));
+        runner.run(SExprParser::new("!(add-atom-ttl &self (Seen A) 10)")).unwrap();
+
+        clock.advance(Duration::from_secs(10));
+        let program = "
+            !(with-transaction &self (let $u (add-atom &self (Seen B)) (Error (Seen B) failed)))
+            !(match &self (Seen $x) $x)
+        ";
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result, vec![vec![expr!("Error" ("Seen" "B") "failed")], vec![]]);
+        assert!(runner.space().borrow().query(&expr!("Seen" x)).is_empty());
+    }
+
+    #[test]
+    fn add_atoms_and_remove_atoms_matching_op() {
+        let space = DynSpace::new(GroundingSpace::new());
+        let satom = Atom::gnd(space.clone());
+        let res = AddAtomsOp{}.execute(&mut vec![satom.clone(), expr!(("foo" "bar") ("foo" "baz") ("bar" "foo"))]).expect("No result returned");
+        assert_eq!(res, vec![UNIT_ATOM]);
+        assert_eq!(space.borrow().atom_count(), Some(3));
+
+        let res = RemoveAtomsMatchingOp{}.execute(&mut vec![satom, expr!("foo" x)]).expect("No result returned");
+        assert_eq!(res.len(), 1);
+        let removed = TryInto::<&ExpressionAtom>::try_into(&res[0]).unwrap().children().to_vec();
+        assert_eq_no_order!(removed, vec![expr!("foo" "bar"), expr!("foo" "baz")]);
+        assert_eq!(collect_atoms(&space), vec![expr!("bar" "foo")]);
+    }
+
+    #[test]
+    fn match_not_and_or_patterns() {
+        let program = "
+            (person Alice) (person Bob) (person Carol)
+            (likes Alice Bob) (hates Bob Carol)
+            !(match &self (, (person $x) (query:not (likes $x $_))) $x)
+            !(match &self (query:or (likes $x $y) (hates $x $y)) ($x $y))
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq_no_order!(result[0], vec![expr!("Bob"), expr!("Carol")]);
+        assert_eq_no_order!(result[1], vec![expr!("Alice" "Bob"), expr!("Bob" "Carol")]);
+    }
+
+    #[test]
+    fn match_not_and_or_data() {
+        let program = "
+            (rule (not (wet road)) (dry road))
+            (rule (or (sun road) (wind road)) (dry road))
+            !(match &self (rule (not $p) $c) ($p $c))
+            !(match &self (rule (or $p $q) $c) $q)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[0], vec![expr!(("wet" "road") ("dry" "road"))]);
+        assert_eq!(result[1], vec![expr!("wind" "road")]);
+    }
+
+    #[test]
+    fn save_and_load_space_ops() {
+        let path = std::env::temp_dir().join(format!("hyperon-save-space-op-{}.dump", std::process::id()));
+        let program = format!(r#"
+            !(bind! &s (new-space))
+            !(add-atom &s (age Bob 35))
+            !(add-atom &s (op +))
+            !(save-space &s "{0}")
+            !(let $l (load-space "{0}") (match $l (age Bob $a) $a))
+            !(let $l (load-space "{0}") (match $l (op $f) ($f 1 2)))
+            !(save-space (new-space) "{1}")
+        "#, path.display(), std::env::temp_dir().display());
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program.as_str())).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(result[3], vec![UNIT_ATOM]);
+        assert_eq!(result[4], vec![expr!({Number::Integer(35)})]);
+        assert_eq!(result[5], vec![expr!({Number::Integer(3)})]);
+        assert!(atom_is_error(&result[6][0]));
+    }
+
+    #[test]
+    fn view_space_ops() {
+        let program = "
+            !(bind! &a (new-space))
+            !(bind! &b (new-space))
+            !(add-atom &a (Parent Tom Bob))
+            !(add-atom &b (Parent Bob Ann))
+            !(add-atom &b (secret Bob))
+            !(let $u (union-space all (&a &b)) (match $u (, (Parent $x $y) (Parent $y $z)) ($x $z)))
+            !(let $f (filter-space &b (Parent $_ $_)) (match $f ($k Bob) $k))
+            !(let $r (rename-space &a ((Parent parent))) (match $r (parent $x $y) ($x $y)))
+            !(let $o (overlay-space (new-space) &a) (let () (add-atom $o (Parent Tom Liz)) (match $o (Parent Tom $x) $x)))
+            !(match &a (Parent Tom $x) $x)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[5], vec![expr!("Tom" "Ann")]);
+        assert!(result[6].is_empty());
+        assert_eq!(result[7], vec![expr!("Tom" "Bob")]);
+        assert_eq_no_order!(result[8], vec![expr!("Bob"), expr!("Liz")]);
+        assert_eq!(result[9], vec![expr!("Bob")]);
+    }
+
+    #[test]
+    fn add_atom_ttl_op() {
+        let program = "
+            !(add-atom-ttl &self (Seen A) 0)
+            !(add-atom-ttl &self (Seen B) 3600)
+            !(match &self (Seen $x) $x)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[0], vec![UNIT_ATOM]);
+        assert_eq!(result[2], vec![expr!("B")]);
+
+        let space = Atom::gnd(DynSpace::new(GroundingSpace::new()));
+        assert_eq!(AddAtomTtlOp{}.execute(&mut vec![space, sym!("A"), expr!({Number::Integer(-1)})]),
+            Err(ExecError::from("add-atom-ttl expects a non-negative time to live")));
+    }
+
+    #[test]
+    fn space_stats_op() {
+        let program = "
+            !(bind! &s (new-space))
+            !(add-atom &s (A B))
+            !(add-atom &s (A B))
+            !(add-atom &s (A C))
+            !(remove-atom &s (A C))
+            !(space-stats &s)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        let stats = match result[5].as_slice() {
+            [Atom::Expression(stats)] => stats.children().clone(),
+            _ => panic!("Unexpected result: {:?}", result[5]),
+        };
+        assert!(stats.contains(&expr!("atoms" {Number::Integer(2)})));
+        assert!(stats.contains(&expr!("distinct-atoms" {Number::Integer(1)})));
+        assert!(stats.contains(&expr!("duplicate-ratio" {Number::Float(0.5)})));
+    }
+
+    #[test]
+    fn on_match_op() {
+        let program = "
+            (Alert A)
+            !(on-match &self (, (Alert $x) (query:not (Ack $x))) (add-atom &self (Seen $x)))
+            !(add-atom &self (Alert B))
+            !(add-atom &self (Ack C))
+            !(add-atom &self (Alert C))
+            !(match &self (Seen $x) $x)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[4], vec![expr!("B")]);
+    }
+
+    #[test]
+    fn unsubscribe_op() {
+        let program = "
+            !(bind! &sub (on-match &self (Alert $x) (add-atom &self (Seen $x))))
+            !(add-atom &self (Alert A))
+            !(unsubscribe &sub)
+            !(add-atom &self (Alert B))
+            !(match &self (Seen $x) $x)
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[2], vec![UNIT_ATOM]);
+        assert_eq!(result[4], vec![expr!("A")]);
+    }
+
+    #[test]
+    fn match_count_aggregate_and_group() {
+        let program = "
+            (Parent Tom Bob) (Parent Tom Liz) (Parent Bob Ann)
+            (age Tom 60) (age Bob 35) (age Liz 30) (age Ann 10)
+            !(match-count &self (Parent $x $y))
+            !(match-count &self (, (Parent $x $y) (Parent $y $z)))
+            !(match-aggregate &self (age $x $a) (max $a))
+            !(match-aggregate &self (, (Parent Tom $c) (age $c $a)) (sum $a))
+            !(match-group &self (Parent $x $y) $x count)
+            !(match-group &self (Parent $x $y) $y (distinct $x))
+        ";
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[0..4], vec![
+            vec![expr!({Number::Integer(3)})],
+            vec![expr!({Number::Integer(1)})],
+            vec![expr!({Number::Integer(60)})],
+            vec![expr!({Number::Integer(65)})],
+        ]);
+        assert_eq_no_order!(result[4], vec![expr!("Tom" {Number::Integer(2)}), expr!("Bob" {Number::Integer(1)})]);
+        assert_eq_no_order!(result[5], vec![expr!("Bob" ("Tom")), expr!("Liz" ("Tom")), expr!("Ann" ("Bob"))]);
+    }
+
+    #[test]
+    fn fork_space_op() {
+        let program = r#"
+            !(bind! &space (new-space))
+            !(add-atom &space (fact A))
+            !(bind! &fork (fork-space &space))
+            !(add-atom &fork (fact B))
+            !(remove-atom &fork (fact A))
+            !(match &space (fact $x) $x)
+            !(compare-spaces &space &fork)
+            !(reset-space-to &space &fork)
+            !(match &space (fact $x) $x)
+        "#;
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[5..], [
+            vec![expr!("A")],
+            vec![expr!((("fact" "B")) (("fact" "A")))],
+            vec![UNIT_ATOM],
+            vec![expr!("B")],
+        ]);
+    }
+
+    #[test]
+    fn merge_spaces_op() {
+        let program = r#"
+            !(bind! &base (new-space))
+            !(add-atom &base (color sky blue))
+            !(add-atom &base (color grass green))
+            !(bind! &ours (fork-space &base))
+            !(bind! &theirs (fork-space &base))
+            !(remove-atom &ours (color sky blue))
+            !(add-atom &ours (color sky grey))
+            !(remove-atom &theirs (color sky blue))
+            !(remove-atom &theirs (color grass green))
+            !(let ($merged $conflicts) (merge-spaces &base &ours &theirs) $conflicts)
+            !(let ($merged $conflicts) (merge-spaces &base &ours &theirs) (get-atoms $merged))
+            !(sync-spaces &base &ours &theirs &base)
+            !(get-atoms &base)
+        "#;
+        let runner = Metta::new(Some(runner::environment::EnvBuilder::test_env()));
+        let result = runner.run(SExprParser::new(program)).unwrap();
+        assert_eq!(result[9..], [
+            vec![expr!((("color" "sky" "blue") ("replaced" ("color" "sky" "grey")) "removed"))],
+            vec![expr!("color" "sky" "blue")],
+            vec![expr!((("color" "sky" "blue") ("replaced" ("color" "sky" "grey")) "removed"))],
+            vec![expr!("color" "sky" "blue")],
+        ]);
+    }
+
     fn collect_atoms(space: &DynSpace) -> Vec<Atom> {
         let mut atoms = Vec::new();
         space.borrow().visit(&mut |atom: std::borrow::Cow<Atom>| atoms.push(atom.into_owned()))
@@ -0,0 +1,178 @@
+//! MeTTa runner which can be shared between threads. [Metta] itself is
+//! neither [Send] nor [Sync]: its modules, tokenizer and spaces are built on
+//! `Rc<RefCell<_>>` and the atoms it works with can contain arbitrary
+//! grounded values. [SyncMetta] keeps a pool of worker threads instead, each
+//! worker owns its own [Metta] instance which is initialized once when the
+//! worker is started. Programs are sent to the workers and results are sent
+//! back, thus modules are not reloaded on each request.
+//!
+//! State which should be visible to all workers is kept in a
+//! [SyncSpace](crate::space::sync::SyncSpace) which is bound to a token by
+//! the initialization function. Only atoms which can be sent between threads
+//! (see [is_sendable]) are returned as results.
+
+use hyperon_atom::*;
+
+use super::Metta;
+use crate::metta::text::SExprParser;
+use crate::space::sync::is_sendable;
+
+use std::sync::{Arc, Mutex, mpsc};
+
+/// Results of the program which are checked to be sendable.
+struct SendableResults(Vec<Vec<Atom>>);
+
+// SAFETY: SendableResults is constructed by SendableResults::new() only,
+// which checks each atom by is_sendable(). Such atoms contain symbols,
+// variables, expressions, numbers, booleans and strings only. They are
+// backed by &'static str, Arc and owned values without interior mutability,
+// see SyncSpace for details.
+unsafe impl Send for SendableResults {}
+
+impl SendableResults {
+    fn new(results: Vec<Vec<Atom>>) -> Result<Self, String> {
+        match results.iter().flatten().find(|atom| !is_sendable(atom)) {
+            Some(atom) => Err(format!("Result {} cannot be shared between threads", atom)),
+            None => Ok(Self(results)),
+        }
+    }
+}
+
+struct Job {
+    program: String,
+    reply: mpsc::Sender<Result<SendableResults, String>>,
+}
+
+/// Runner which can be shared between threads. Cloned instance refers to
+/// the same pool of workers. Workers are stopped when the last instance is
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::DynSpace;
+/// use hyperon::metta::runner::{Metta, EnvBuilder};
+/// use hyperon::metta::runner::sync::SyncMetta;
+/// use hyperon::space::sync::SyncSpace;
+///
+/// let space = SyncSpace::new();
+/// let metta = {
+///     let space = space.clone();
+///     SyncMetta::new(2, move || {
+///         let metta = Metta::new(Some(EnvBuilder::test_env()));
+///         let shared = Atom::gnd(DynSpace::new(space.handle()));
+///         metta.tokenizer().borrow_mut().register_token_with_regex_str("&shared", move |_| shared.clone());
+///         metta
+///     })
+/// };
+///
+/// let request = {
+///     let metta = metta.clone();
+///     std::thread::spawn(move || metta.run("!(add-atom &shared (fact A))"))
+/// };
+/// assert_eq!(request.join().unwrap(), Ok(vec![vec![Atom::expr([])]]));
+/// assert_eq!(metta.run("!(match &shared (fact $x) $x)"), Ok(vec![vec![sym!("A")]]));
+/// ```
+#[derive(Clone)]
+pub struct SyncMetta {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl SyncMetta {
+    /// Starts `workers` threads, each of them calls `init` to construct its
+    /// own [Metta] instance.
+    pub fn new<F>(workers: usize, init: F) -> Self
+        where F: Fn() -> Metta + Send + Sync + 'static
+    {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let init = Arc::new(init);
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let init = init.clone();
+            std::thread::spawn(move || Self::work(init(), &receiver));
+        }
+        Self{ jobs }
+    }
+
+    fn work(metta: Metta, receiver: &Mutex<mpsc::Receiver<Job>>) {
+        loop {
+            let job = match receiver.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            let Ok(Job{ program, reply }) = job else {
+                return;
+            };
+            let result = metta.run(SExprParser::new(program.as_str()))
+                .and_then(SendableResults::new);
+            // Caller can stop waiting for the result, it is not an error
+            let _ = reply.send(result);
+        }
+    }
+
+    /// Runs `program` on one of the workers and returns the results. Returns
+    /// an error if the program fails, if results cannot be sent between
+    /// threads or if the worker is terminated.
+    pub fn run(&self, program: &str) -> Result<Vec<Vec<Atom>>, String> {
+        let (reply, result) = mpsc::channel();
+        self.jobs.send(Job{ program: program.to_string(), reply })
+            .map_err(|_| "All workers are terminated".to_string())?;
+        match result.recv() {
+            Ok(result) => result.map(|results| results.0),
+            Err(_) => Err("Worker is terminated while running the program".into()),
+        }
+    }
+}
+
+impl std::fmt::Debug for SyncMetta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SyncMetta")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metta::runner::EnvBuilder;
+    use crate::space::sync::SyncSpace;
+    use hyperon_space::DynSpace;
+    use hyperon_atom::gnd::number::Number;
+
+    fn shared_metta(space: &SyncSpace, workers: usize) -> SyncMetta {
+        let space = space.clone();
+        SyncMetta::new(workers, move || {
+            let metta = Metta::new(Some(EnvBuilder::test_env()));
+            let shared = Atom::gnd(DynSpace::new(space.handle()));
+            metta.tokenizer().borrow_mut().register_token_with_regex_str("&shared", move |_| shared.clone());
+            metta.run(SExprParser::new("(= (double $x) (* 2 $x))")).unwrap();
+            metta
+        })
+    }
+
+    #[test]
+    fn sync_metta_concurrent_requests() {
+        let space = SyncSpace::new();
+        let metta = shared_metta(&space, 3);
+
+        let requests: Vec<_> = (0..8).map(|i| {
+            let metta = metta.clone();
+            std::thread::spawn(move || metta.run(&format!("!(add-atom &shared (value {}))\n!(double {})", i, i)))
+        }).collect();
+        for (i, request) in requests.into_iter().enumerate() {
+            let result = request.join().unwrap().unwrap();
+            assert_eq!(result[1], vec![Atom::gnd(Number::Integer(2 * i as i64))]);
+        }
+
+        assert_eq!(space.atom_count(), 8);
+    }
+
+    #[test]
+    fn sync_metta_rejects_non_sendable_results() {
+        let metta = shared_metta(&SyncSpace::new(), 1);
+        let error = metta.run("!(new-space)").unwrap_err();
+        assert!(error.contains("cannot be shared between threads"), "unexpected error: {}", error);
+        assert_eq!(metta.run("!(double 2)"), Ok(vec![vec![Atom::gnd(Number::Integer(4))]]));
+    }
+}
@@ -54,6 +54,14 @@ impl Tokenizer {
         self.register_token(regex, constr)
     }
 
+    /// Registers grounded operation generated by [hyperon_macros::metta_op]
+    /// using its name as a token.
+    pub fn register_op<T: op::GroundedOp>(&mut self) {
+        let regex = Regex::new(&regex::escape(T::NAME)).unwrap();
+        let atom = Atom::gnd(T::default());
+        self.register_token(regex, move |_token| atom.clone())
+    }
+
     /// Moves all tokenizer entries from `from` into `self`, leaving `from` empty
     ///
     /// NOTE: Tokens are tried in reverse order, so `move_front` actually adds entries that will be tried
@@ -923,6 +931,22 @@ mod tests {
         result
     }
 
+    #[test]
+    fn test_pretty_printed_atom_is_parsed_back() {
+        use hyperon_atom::pretty::PrettyPrinter;
+
+        let atoms = parse_atoms(r#"
+            (= (foo $x) (let $y (bar $x "some string") (chain (baz $y) $z (quux $z $z))))
+            (A (B (C (D (E (F (G (H (I (J (K (L (M (N (O (P Q))))))))))))))))
+        "#);
+        for width in [1, 10, 40, 80] {
+            let printer = PrettyPrinter::new().width(width);
+            for atom in &atoms {
+                assert_eq!(parse_atoms(&printer.print(atom)), vec![atom.clone()]);
+            }
+        }
+    }
+
     #[test]
     fn test_lattice_in_var_name() {
         let mut parser = SExprParser::new("$a#");
@@ -568,8 +568,9 @@ pub fn match_reducted_types(left: &Atom, right: &Atom) -> matcher::MatchResultIt
 
 fn replace_undefined_types(atom: &Atom) -> Atom {
     let mut atom = atom.clone();
-    atom.iter_mut().filter(|atom| **atom == ATOM_TYPE_UNDEFINED)
-        .for_each(|atom| *atom = Atom::gnd(UndefinedTypeMatch{}));
+    atom.rewrite(VisitOrder::TopDown, |_path, atom| {
+        if *atom == ATOM_TYPE_UNDEFINED { Some(Atom::gnd(UndefinedTypeMatch{})) } else { None }
+    });
     atom
 }
 
@@ -0,0 +1,339 @@
+//! Binary encoding of atoms which is used to keep atoms outside of memory.
+//! Symbols, variables and expressions are encoded structurally. Grounded
+//! atoms are encoded using [Grounded::serialize] and only atoms of the
+//! `Number`, `Bool` and `String` types are supported because only they can
+//! be restored back.
+//!
+//! Encoding:
+//! - symbol: `0`, name;
+//! - variable: `1`, name in format returned by [VariableAtom::name];
+//! - expression: `2`, number of children, children;
+//! - grounded: `3` + type of the serialized value (`0` - bool, `1` - i64,
+//!   `2` - f64, `3` - string), value;
+//! - text: `4`, text form of the grounded atom which cannot be encoded
+//!   otherwise, it is written only when [TextFallback] is passed.
+//!
+//! Numbers of children and lengths of strings are encoded as LEB128 integers,
+//! i64 and f64 values are encoded as 8 bytes little endian values.
+//! Expressions nested deeper than [MAX_DEPTH] are not decoded to not
+//! overflow the stack on the malformed input.
+
+use hyperon_atom::*;
+use hyperon_atom::serial;
+use hyperon_atom::gnd::number::{Number, ATOM_TYPE_NUMBER};
+use hyperon_atom::gnd::bool::{Bool, ATOM_TYPE_BOOL};
+use hyperon_atom::gnd::str::{Str, ATOM_TYPE_STRING};
+
+const TAG_SYMBOL: u8 = 0;
+const TAG_VARIABLE: u8 = 1;
+const TAG_EXPRESSION: u8 = 2;
+const TAG_GROUNDED: u8 = 3;
+const TAG_TEXT: u8 = 4;
+
+const GND_BOOL: u8 = 0;
+const GND_I64: u8 = 1;
+const GND_F64: u8 = 2;
+const GND_STR: u8 = 3;
+
+/// Maximal nesting depth of the expressions which can be decoded.
+pub const MAX_DEPTH: usize = 1024;
+
+/// Converts grounded atoms which cannot be encoded into text and back. It
+/// allows encoding grounded atoms which can be restored by parsing.
+pub trait TextFallback {
+    /// Returns text form of the grounded `atom` or an error if atom cannot
+    /// be restored from the text.
+    fn to_text(&self, atom: &Atom) -> Result<String, String>;
+    /// Restores atom from the `text` returned by [TextFallback::to_text].
+    fn from_text(&self, text: &str) -> Result<Atom, String>;
+}
+
+/// Appends binary representation of the `atom` to the `buf`. Returns an error
+/// if `atom` contains grounded atom which cannot be encoded. `buf` can
+/// contain partially written atom in case of error.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::gnd::number::Number;
+/// use hyperon::space::codec::{encode_atom, decode_atom};
+///
+/// let atom = expr!("A" x {Number::Integer(42)});
+/// let mut buf = Vec::new();
+/// encode_atom(&atom, &mut buf).unwrap();
+///
+/// assert_eq!(decode_atom(&mut buf.as_slice()), Ok(atom));
+/// ```
+pub fn encode_atom(atom: &Atom, buf: &mut Vec<u8>) -> Result<(), String> {
+    encode(atom, buf, None)
+}
+
+/// Appends binary representation of the `atom` to the `buf` like
+/// [encode_atom] does. Grounded atoms which cannot be encoded are written in
+/// a text form returned by the `fallback`.
+pub fn encode_atom_with_fallback(atom: &Atom, buf: &mut Vec<u8>, fallback: &dyn TextFallback) -> Result<(), String> {
+    encode(atom, buf, Some(fallback))
+}
+
+fn encode(atom: &Atom, buf: &mut Vec<u8>, fallback: Option<&dyn TextFallback>) -> Result<(), String> {
+    match atom {
+        Atom::Symbol(sym) => {
+            buf.push(TAG_SYMBOL);
+            encode_str(sym.name(), buf);
+        },
+        Atom::Variable(var) => {
+            buf.push(TAG_VARIABLE);
+            encode_str(&var.name(), buf);
+        },
+        Atom::Expression(expr) => {
+            buf.push(TAG_EXPRESSION);
+            encode_len(expr.children().len(), buf);
+            for child in expr.children() {
+                encode(child, buf, fallback)?;
+            }
+        },
+        Atom::Grounded(gnd) => {
+            let mut value = ValueSerializer(None);
+            let value = match gnd.serialize(&mut value) {
+                Ok(()) => value.0,
+                Err(serial::Error::NotSupported) => None,
+            };
+            let typ = gnd.type_();
+            match value {
+                Some(Value::Bool(v)) if typ == ATOM_TYPE_BOOL => {
+                    buf.extend([TAG_GROUNDED, GND_BOOL, v as u8]);
+                },
+                Some(Value::I64(v)) if typ == ATOM_TYPE_NUMBER => {
+                    buf.extend([TAG_GROUNDED, GND_I64]);
+                    buf.extend(v.to_le_bytes());
+                },
+                Some(Value::F64(v)) if typ == ATOM_TYPE_NUMBER => {
+                    buf.extend([TAG_GROUNDED, GND_F64]);
+                    buf.extend(v.to_le_bytes());
+                },
+                Some(Value::Str(v)) if typ == ATOM_TYPE_STRING => {
+                    buf.extend([TAG_GROUNDED, GND_STR]);
+                    encode_str(&v, buf);
+                },
+                _ => match fallback {
+                    Some(fallback) => {
+                        buf.push(TAG_TEXT);
+                        encode_str(&fallback.to_text(atom)?, buf);
+                    },
+                    None => return Err(format!("Grounded atom {} cannot be encoded", atom)),
+                },
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Reads an atom from the beginning of the `buf` and moves `buf` to the
+/// first byte after the atom.
+pub fn decode_atom(buf: &mut &[u8]) -> Result<Atom, String> {
+    decode(buf, None, 0)
+}
+
+/// Reads an atom like [decode_atom] does. Atoms written in a text form by
+/// [encode_atom_with_fallback] are restored using the `fallback`.
+pub fn decode_atom_with_fallback(buf: &mut &[u8], fallback: &dyn TextFallback) -> Result<Atom, String> {
+    decode(buf, Some(fallback), 0)
+}
+
+fn decode(buf: &mut &[u8], fallback: Option<&dyn TextFallback>, depth: usize) -> Result<Atom, String> {
+    match decode_u8(buf)? {
+        TAG_SYMBOL => Ok(Atom::sym(decode_str(buf)?)),
+        TAG_VARIABLE => VariableAtom::parse_name(&decode_str(buf)?).map(Atom::Variable),
+        TAG_EXPRESSION => {
+            if depth >= MAX_DEPTH {
+                return Err(format!("Expression is nested deeper than {} levels", MAX_DEPTH));
+            }
+            let len = decode_len(buf)?;
+            let mut children = Vec::with_capacity(len.min(buf.len()));
+            for _ in 0..len {
+                children.push(decode(buf, fallback, depth + 1)?);
+            }
+            Ok(Atom::expr(children))
+        },
+        TAG_GROUNDED => match decode_u8(buf)? {
+            GND_BOOL => Ok(Atom::gnd(Bool(decode_u8(buf)? != 0))),
+            GND_I64 => Ok(Atom::gnd(Number::Integer(i64::from_le_bytes(decode_bytes(buf)?)))),
+            GND_F64 => Ok(Atom::gnd(Number::Float(f64::from_le_bytes(decode_bytes(buf)?)))),
+            GND_STR => Ok(Atom::gnd(Str::from_string(decode_str(buf)?))),
+            tag => Err(format!("Unexpected grounded value tag: {}", tag)),
+        },
+        TAG_TEXT => {
+            let text = decode_str(buf)?;
+            match fallback {
+                Some(fallback) => fallback.from_text(&text),
+                None => Err(format!("Atom {} in text form cannot be decoded", text)),
+            }
+        },
+        tag => Err(format!("Unexpected atom tag: {}", tag)),
+    }
+}
+
+/// Appends LEB128 representation of the `len` to the `buf`.
+pub fn encode_len(mut len: usize, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            buf.push(byte);
+            break;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Reads LEB128 integer from the beginning of the `buf`.
+pub fn decode_len(buf: &mut &[u8]) -> Result<usize, String> {
+    let mut len: usize = 0;
+    let mut shift = 0;
+    loop {
+        let byte = decode_u8(buf)?;
+        if shift >= usize::BITS {
+            return Err("Length is too big".into());
+        }
+        len |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(len);
+        }
+        shift += 7;
+    }
+}
+
+fn encode_str(s: &str, buf: &mut Vec<u8>) {
+    encode_len(s.len(), buf);
+    buf.extend(s.as_bytes());
+}
+
+fn decode_str(buf: &mut &[u8]) -> Result<String, String> {
+    let len = decode_len(buf)?;
+    if buf.len() < len {
+        return Err("Unexpected end of data".into());
+    }
+    let (s, rest) = buf.split_at(len);
+    *buf = rest;
+    String::from_utf8(s.to_vec()).map_err(|e| e.to_string())
+}
+
+fn decode_u8(buf: &mut &[u8]) -> Result<u8, String> {
+    let [byte] = decode_bytes(buf)?;
+    Ok(byte)
+}
+
+fn decode_bytes<const N: usize>(buf: &mut &[u8]) -> Result<[u8; N], String> {
+    if buf.len() < N {
+        return Err("Unexpected end of data".into());
+    }
+    let (bytes, rest) = buf.split_at(N);
+    *buf = rest;
+    Ok(bytes.try_into().unwrap())
+}
+
+enum Value {
+    Bool(bool),
+    I64(i64),
+    F64(f64),
+    Str(String),
+}
+
+/// Keeps the value of the grounded atom which is serialized as a single
+/// primitive value.
+struct ValueSerializer(Option<Value>);
+
+impl ValueSerializer {
+    fn set(&mut self, value: Value) -> serial::Result {
+        match self.0 {
+            None => {
+                self.0 = Some(value);
+                Ok(())
+            },
+            Some(_) => Err(serial::Error::NotSupported),
+        }
+    }
+}
+
+impl serial::Serializer for ValueSerializer {
+    fn serialize_bool(&mut self, v: bool) -> serial::Result { self.set(Value::Bool(v)) }
+    fn serialize_i64(&mut self, v: i64) -> serial::Result { self.set(Value::I64(v)) }
+    fn serialize_f64(&mut self, v: f64) -> serial::Result { self.set(Value::F64(v)) }
+    fn serialize_str(&mut self, v: &str) -> serial::Result { self.set(Value::Str(v.into())) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(atom: Atom) {
+        let mut buf = Vec::new();
+        encode_atom(&atom, &mut buf).unwrap();
+        let mut data = buf.as_slice();
+        assert_eq!(decode_atom(&mut data), Ok(atom));
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn codec_round_trip() {
+        round_trip(expr!("A"));
+        round_trip(expr!(x));
+        round_trip(Atom::Variable(VariableAtom::new("x").make_unique()));
+        round_trip(expr!());
+        round_trip(expr!("A" ("B" x) {Number::Float(1.5)} {Bool(true)} {Str::from_str("str")}));
+        round_trip(Atom::expr((0..200).map(|i| Atom::gnd(Number::Integer(i))).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn codec_unsupported_grounded_atom() {
+        let mut buf = Vec::new();
+        assert_eq!(encode_atom(&expr!("A" {crate::metta::runner::stdlib::arithmetics::SumOp{}}), &mut buf),
+            Err("Grounded atom + cannot be encoded".into()));
+    }
+
+    struct Sum;
+
+    impl TextFallback for Sum {
+        fn to_text(&self, atom: &Atom) -> Result<String, String> {
+            Ok(atom.to_string())
+        }
+        fn from_text(&self, text: &str) -> Result<Atom, String> {
+            match text {
+                "+" => Ok(Atom::gnd(crate::metta::runner::stdlib::arithmetics::SumOp{})),
+                _ => Err(format!("Unexpected text: {}", text)),
+            }
+        }
+    }
+
+    #[test]
+    fn codec_text_fallback() {
+        let atom = expr!("A" {crate::metta::runner::stdlib::arithmetics::SumOp{}} {Number::Integer(1)});
+        let mut buf = Vec::new();
+        encode_atom_with_fallback(&atom, &mut buf, &Sum).unwrap();
+
+        assert_eq!(decode_atom_with_fallback(&mut buf.as_slice(), &Sum), Ok(atom));
+        assert_eq!(decode_atom(&mut buf.as_slice()), Err("Atom + in text form cannot be decoded".into()));
+    }
+
+    #[test]
+    fn codec_truncated_data() {
+        let mut buf = Vec::new();
+        encode_atom(&expr!("A" "B"), &mut buf).unwrap();
+        buf.pop();
+        assert_eq!(decode_atom(&mut buf.as_slice()), Err("Unexpected end of data".into()));
+    }
+
+    #[test]
+    fn codec_too_deep_expression() {
+        let nested = |depth: usize| {
+            let mut buf = [TAG_EXPRESSION, 1].repeat(depth);
+            buf.extend([TAG_EXPRESSION, 0]);
+            buf
+        };
+        assert!(decode_atom(&mut nested(MAX_DEPTH - 1).as_slice()).is_ok());
+        assert_eq!(decode_atom(&mut nested(MAX_DEPTH).as_slice()),
+            Err(format!("Expression is nested deeper than {} levels", MAX_DEPTH)));
+        assert!(decode_atom(&mut nested(1_000_000).as_slice()).is_err());
+    }
+}
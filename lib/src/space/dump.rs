@@ -0,0 +1,214 @@
+//! Dump of the space atoms in a compact binary format. Atoms are encoded
+//! using [codec](super::codec) thus no MeTTa parsing is required to load
+//! them back. Grounded atoms which cannot be encoded can be written in a
+//! text form using [TextFallback], see [TokenizerFallback].
+//!
+//! Dump starts from the header: magic bytes and format version (4 bytes
+//! little endian). Header is followed by the number of atoms encoded as
+//! LEB128 integer and encoded atoms.
+
+use hyperon_atom::*;
+use hyperon_space::Space;
+use hyperon_space::index::{AtomIndex, AllowDuplication, ALLOW_DUPLICATION};
+
+use super::grounding::GroundingSpace;
+use super::codec::{TextFallback, encode_atom_with_fallback, decode_atom_with_fallback,
+    encode_len, decode_len};
+use crate::metta::text::{Tokenizer, SExprParser};
+
+use std::borrow::Cow;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"MeTTaDmp";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 12;
+
+/// Fallback which doesn't write atoms in a text form, thus dump of the space
+/// with grounded atoms which cannot be encoded fails.
+struct NoFallback;
+
+impl TextFallback for NoFallback {
+    fn to_text(&self, atom: &Atom) -> Result<String, String> {
+        Err(format!("Grounded atom {} cannot be serialized", atom))
+    }
+    fn from_text(&self, text: &str) -> Result<Atom, String> {
+        Err(format!("Atom {} in text form cannot be loaded without tokenizer", text))
+    }
+}
+
+/// Writes grounded atoms in their text form and restores them by parsing
+/// the text using the tokenizer. Atom is written only when it is restored
+/// back by the tokenizer, otherwise it is reported as non-serializable.
+pub struct TokenizerFallback<'a>(pub &'a Tokenizer);
+
+impl TokenizerFallback<'_> {
+    fn parse(&self, text: &str) -> Result<Atom, String> {
+        SExprParser::new(text).parse(self.0)?
+            .ok_or_else(|| format!("Atom {} in text form cannot be parsed", text))
+    }
+}
+
+impl TextFallback for TokenizerFallback<'_> {
+    fn to_text(&self, atom: &Atom) -> Result<String, String> {
+        let text = atom.to_string();
+        match self.parse(&text) {
+            Ok(parsed) if parsed == *atom => Ok(text),
+            _ => Err(format!("Grounded atom {} cannot be serialized: it is not serializable and cannot be restored from its text form", atom)),
+        }
+    }
+    fn from_text(&self, text: &str) -> Result<Atom, String> {
+        self.parse(text)
+    }
+}
+
+/// Writes all atoms of the `space` into a binary dump. Returns an error if
+/// space cannot be visited or contains grounded atom which cannot be
+/// serialized. When `fallback` is passed grounded atoms which cannot be
+/// encoded are written in a text form.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_atom::gnd::number::Number;
+/// use hyperon_space::Space;
+/// use hyperon::space::grounding::GroundingSpace;
+/// use hyperon::space::dump::{save_space, load_space};
+///
+/// let space = GroundingSpace::from_vec(vec![expr!("age" "Bob" {Number::Integer(35)})]);
+/// let dump = save_space(&space, None).unwrap();
+/// let loaded = load_space(&dump, None).unwrap();
+///
+/// assert_eq!(loaded.query(&expr!("age" "Bob" x)), bind_set![{x: expr!({Number::Integer(35)})}]);
+/// ```
+pub fn save_space<S: Space + ?Sized>(space: &S, fallback: Option<&dyn TextFallback>) -> Result<Vec<u8>, String> {
+    let fallback = fallback.unwrap_or(&NoFallback);
+    let mut atoms = Vec::new();
+    let mut count = 0;
+    let mut error = None;
+    space.visit(&mut |atom: Cow<Atom>| {
+        if error.is_none() {
+            match encode_atom_with_fallback(&atom, &mut atoms, fallback) {
+                Ok(()) => count += 1,
+                Err(err) => error = Some(err),
+            }
+        }
+    }).map_err(|()| format!("Space {} doesn't support visiting atoms", space))?;
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + 10 + atoms.len());
+    buf.extend(MAGIC);
+    buf.extend(VERSION.to_le_bytes());
+    encode_len(count, &mut buf);
+    buf.extend(atoms);
+    Ok(buf)
+}
+
+/// Builds index from the binary dump written by [save_space]. Atoms are
+/// decoded first and then inserted into the index in bulk, see
+/// [AtomIndex::insert_all].
+pub fn load_index(mut data: &[u8], fallback: Option<&dyn TextFallback>) -> Result<AtomIndex<AllowDuplication>, String> {
+    let fallback = fallback.unwrap_or(&NoFallback);
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err("Data is not a space dump".into());
+    }
+    let version = u32::from_le_bytes(data[MAGIC.len()..HEADER_LEN].try_into().unwrap());
+    if version != VERSION {
+        return Err(format!("Unsupported space dump version: {}, expected: {}", version, VERSION));
+    }
+    data = &data[HEADER_LEN..];
+    let count = decode_len(&mut data)?;
+    let atoms = (0..count)
+        .map(|_| decode_atom_with_fallback(&mut data, fallback))
+        .collect::<Result<Vec<Atom>, String>>()?;
+    if !data.is_empty() {
+        return Err("Unexpected data after the last atom of the space dump".into());
+    }
+    let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
+    index.insert_all(atoms);
+    Ok(index)
+}
+
+/// Loads space from the binary dump written by [save_space].
+pub fn load_space(data: &[u8], fallback: Option<&dyn TextFallback>) -> Result<GroundingSpace, String> {
+    load_index(data, fallback).map(GroundingSpace::from_index)
+}
+
+/// Writes binary dump of the `space` into the file, see [save_space].
+pub fn save_space_to_file<S, P>(space: &S, path: P, fallback: Option<&dyn TextFallback>) -> Result<(), String>
+    where S: Space + ?Sized, P: AsRef<Path>
+{
+    let dump = save_space(space, fallback)?;
+    std::fs::write(path.as_ref(), dump)
+        .map_err(|e| format!("Cannot write space dump into {}: {}", path.as_ref().display(), e))
+}
+
+/// Loads space from the file written by [save_space_to_file].
+pub fn load_space_from_file<P: AsRef<Path>>(path: P, fallback: Option<&dyn TextFallback>) -> Result<GroundingSpace, String> {
+    let data = std::fs::read(path.as_ref())
+        .map_err(|e| format!("Cannot read space dump from {}: {}", path.as_ref().display(), e))?;
+    load_space(&data, fallback)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metta::runner::stdlib::arithmetics::SumOp;
+    use hyperon_atom::gnd::number::Number;
+    use hyperon_space::SpaceMut;
+    use regex::Regex;
+
+    fn tokenizer() -> Tokenizer {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.register_token(Regex::new(r"\+").unwrap(), |_| Atom::gnd(SumOp{}));
+        tokenizer
+    }
+
+    #[test]
+    fn dump_round_trip() {
+        let atoms = vec![expr!("A" ("B" x)), expr!("A" ("B" x)), expr!("n" {Number::Float(1.5)})];
+        let space = GroundingSpace::from_vec(atoms.clone());
+
+        let loaded = load_space(&save_space(&space, None).unwrap(), None).unwrap();
+
+        let mut loaded_atoms = Vec::new();
+        loaded.visit(&mut |atom: Cow<Atom>| loaded_atoms.push(atom.into_owned())).unwrap();
+        hyperon_common::assert_eq_no_order!(loaded_atoms, atoms);
+    }
+
+    #[test]
+    fn dump_text_fallback() {
+        let tokenizer = tokenizer();
+        let fallback = TokenizerFallback(&tokenizer);
+        let mut space = GroundingSpace::new();
+        space.add(expr!("op" {SumOp{}}));
+
+        assert_eq!(save_space(&space, None), Err("Grounded atom + cannot be serialized".into()));
+        let dump = save_space(&space, Some(&fallback)).unwrap();
+        assert_eq!(load_space(&dump, None).err(),
+            Some("Atom + in text form cannot be loaded without tokenizer".into()));
+        let loaded = load_space(&dump, Some(&fallback)).unwrap();
+        assert_eq!(loaded.atom_count(), Some(1));
+        assert!(!loaded.query(&expr!("op" {SumOp{}})).is_empty());
+    }
+
+    #[test]
+    fn dump_non_serializable_atom() {
+        let tokenizer = tokenizer();
+        let mut space = GroundingSpace::new();
+        space.add(expr!("space" {hyperon_space::DynSpace::new(GroundingSpace::new())}));
+
+        let result = save_space(&space, Some(&TokenizerFallback(&tokenizer)));
+        assert!(result.unwrap_err().ends_with("it is not serializable and cannot be restored from its text form"));
+    }
+
+    #[test]
+    fn dump_wrong_data() {
+        assert_eq!(load_space(b"text", None).err(), Some("Data is not a space dump".into()));
+        let mut dump = save_space(&GroundingSpace::new(), None).unwrap();
+        dump[MAGIC.len()] = 2;
+        assert_eq!(load_space(&dump, None).err(), Some("Unsupported space dump version: 2, expected: 1".into()));
+    }
+}
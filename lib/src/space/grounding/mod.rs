@@ -1,13 +1,18 @@
 //! Atomspace implementation with in-memory atom storage
 
 use hyperon_atom::{matcher::BindingsSet, *};
+use hyperon_atom::gnd::number::Number;
 use hyperon_common::FlexRef;
 #[cfg(test)]
 use hyperon_space::DynSpace;
 
 use std::fmt::{Debug, Display};
 use std::collections::HashSet;
-use hyperon_space::{complex_query, index::{AllowDuplication, AtomIndex, DuplicationStrategy, ALLOW_DUPLICATION}, Space, SpaceCommon, SpaceEvent, SpaceMut, SpaceVisitor};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use super::clock::{Clock, SystemClock};
+use hyperon_space::{complex_query_with_statistics, is_complex_query, aggregate::Aggregate, index::{AllowDuplication, AtomIndex, DuplicationStrategy, MemoryStats, ALLOW_DUPLICATION}, Space, SpaceCommon, SpaceEvent, SpaceMut, SpaceStatistics, SpaceVisitor};
 
 // Grounding space
 
@@ -18,6 +23,11 @@ pub struct GroundingSpace<D: DuplicationStrategy = AllowDuplication> {
     index: AtomIndex<D>,
     common: SpaceCommon,
     name: Option<String>,
+    clock: Arc<dyn Clock>,
+    /// Atoms added with expiration time ordered by the time.
+    deadlines: im::OrdMap<SystemTime, Vec<Atom>>,
+    /// Expiration times of each atom added with expiration time.
+    expiries: im::HashMap<Atom, Vec<SystemTime>>,
 }
 
 impl GroundingSpace {
@@ -29,27 +39,51 @@ impl GroundingSpace {
     /// Constructs space from vector of atoms.
     pub fn from_vec(atoms: Vec<Atom>) -> Self {
         let mut index = AtomIndex::with_strategy(ALLOW_DUPLICATION);
-        for atom in atoms {
-            index.insert(atom);
-        }
-        Self{
-            index,
-            common: SpaceCommon::default(),
-            name: None,
-        }
+        index.insert_all(atoms);
+        Self::from_index(index)
     }
 }
 
 impl<D: DuplicationStrategy> GroundingSpace<D> {
     /// Constructs new empty space using duplication strategy.
     pub fn with_strategy(strategy: D) -> Self {
+        Self::from_index(AtomIndex::with_strategy(strategy))
+    }
+
+    /// Constructs space from the prebuilt `index`. It allows building the
+    /// index in bulk, for instance when space is loaded from a dump.
+    pub fn from_index(index: AtomIndex<D>) -> Self {
         Self {
-            index: AtomIndex::with_strategy(strategy),
+            index,
             common: SpaceCommon::default(),
             name: None,
+            clock: Arc::new(SystemClock),
+            deadlines: im::OrdMap::new(),
+            expiries: im::HashMap::new(),
         }
     }
 
+    /// Adds secondary index on the `position` of the expression elements.
+    /// Queries which have a non-variable element on the `position`, for
+    /// example `($rel Alice $y)` for the position `1`, use the index when it
+    /// is more selective than the main one. See
+    /// [AtomIndex::add_secondary_index].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::{expr, bind_set, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![expr!("Edge" "A" "B"), expr!("Edge" "B" "C")]);
+    /// space.add_index(2);
+    ///
+    /// assert_eq!(space.query(&expr!("Edge" x "C")), bind_set![{x: sym!("B")}]);
+    /// ```
+    pub fn add_index(&mut self, position: usize) {
+        self.index.add_secondary_index(position)
+    }
+
     /// Adds `atom` into space.
     ///
     /// # Examples
@@ -68,6 +102,7 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
     /// assert_eq!(space.query(&sym!("C")), BindingsSet::empty());
     /// ```
     pub fn add(&mut self, atom: Atom) {
+        self.sweep();
         log::debug!("GroundingSpace::add: {}, atom: {}", self, atom);
         self.index.insert(atom.clone());
         self.common.notify_all_observers(&SpaceEvent::Add(atom));
@@ -90,9 +125,11 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
     /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
     /// ```
     pub fn remove(&mut self, atom: &Atom) -> bool {
+        self.sweep();
         log::debug!("GroundingSpace::remove: {}, atom: {}", self, atom);
         let is_removed = self.index.remove(atom);
         if is_removed {
+            self.forget_expiry(atom, 1);
             self.common.notify_all_observers(&SpaceEvent::Remove(atom.clone()));
         }
         is_removed
@@ -117,16 +154,195 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
     /// assert_eq!(space.query(&sym!("B")), BindingsSet::single());
     /// ```
     pub fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.sweep();
         let is_replaced = self.index.remove(from);
         if is_replaced {
+            self.forget_expiry(from, 1);
             self.index.insert(to.clone());
             self.common.notify_all_observers(&SpaceEvent::Replace(from.clone(), to));
         }
         is_replaced
     }
 
+    /// Adds all `atoms` into space. Observers are notified by a single
+    /// [SpaceEvent::Batch] event.
+    pub fn add_all(&mut self, atoms: Vec<Atom>) {
+        self.sweep();
+        log::debug!("GroundingSpace::add_all: {}, atoms: {}", self, atoms.len());
+        self.index.insert_all(atoms.iter().cloned());
+        self.notify_batch(atoms.into_iter().map(SpaceEvent::Add).collect());
+    }
+
+    /// Removes all `atoms` from space and returns number of atoms removed.
+    /// Observers are notified by a single [SpaceEvent::Batch] event.
+    pub fn remove_all(&mut self, atoms: &[Atom]) -> usize {
+        self.sweep();
+        log::debug!("GroundingSpace::remove_all: {}, atoms: {}", self, atoms.len());
+        let removed: Vec<&Atom> = atoms.iter()
+            .filter(|atom| self.index.remove(atom))
+            .collect();
+        for atom in &removed {
+            self.forget_expiry(atom, 1);
+        }
+        let events: Vec<SpaceEvent> = removed.into_iter()
+            .map(|atom| SpaceEvent::Remove(atom.clone()))
+            .collect();
+        let count = events.len();
+        self.notify_batch(events);
+        count
+    }
+
+    /// Removes all atoms which match `pattern` and returns them. Observers
+    /// are notified by a single [SpaceEvent::Batch] event.
+    pub fn remove_matching(&mut self, pattern: &Atom) -> Vec<Atom> {
+        self.sweep();
+        log::debug!("GroundingSpace::remove_matching: {}, pattern: {}", self, pattern);
+        let removed = self.index.remove_matching(pattern);
+        for atom in &removed {
+            self.forget_expiry(atom, 1);
+        }
+        self.notify_batch(removed.iter().cloned().map(SpaceEvent::Remove).collect());
+        removed
+    }
+
+    /// Sets the clock which is used to check expiration of the atoms.
+    /// [SystemClock] is used by default.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Adds `atom` which expires at `expires_at`. Expired atom is not
+    /// matched by queries. It is removed from the space by [GroundingSpace::sweep]
+    /// which is called explicitly or on the next modification of the space.
+    pub fn add_with_expiry(&mut self, atom: Atom, expires_at: SystemTime) {
+        self.sweep();
+        log::debug!("GroundingSpace::add_with_expiry: {}, atom: {}, expires at: {:?}", self, atom, expires_at);
+        self.index.insert(atom.clone());
+        self.deadlines.entry(expires_at).or_insert_with(Vec::new).push(atom.clone());
+        self.expiries.entry(atom.clone()).or_insert_with(Vec::new).push(expires_at);
+        self.common.notify_all_observers(&SpaceEvent::Add(atom));
+    }
+
+    /// Adds `atom` which expires after `ttl` passes, see
+    /// [GroundingSpace::add_with_expiry].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, SystemTime};
+    /// use hyperon_atom::sym;
+    /// use hyperon_atom::matcher::BindingsSet;
+    /// use hyperon::space::grounding::GroundingSpace;
+    /// use hyperon::space::clock::ManualClock;
+    ///
+    /// let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+    /// let mut space = GroundingSpace::new();
+    /// space.set_clock(Arc::new(clock.clone()));
+    ///
+    /// space.add_with_ttl(sym!("A"), Duration::from_secs(10));
+    /// assert_eq!(space.query(&sym!("A")), BindingsSet::single());
+    ///
+    /// clock.advance(Duration::from_secs(10));
+    /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
+    /// assert_eq!(space.sweep(), vec![sym!("A")]);
+    /// ```
+    pub fn add_with_ttl(&mut self, atom: Atom, ttl: Duration) {
+        let expires_at = self.clock.now() + ttl;
+        self.add_with_expiry(atom, expires_at)
+    }
+
+    /// Removes expired atoms from the space and returns them. Observers are
+    /// notified by a single [SpaceEvent::Batch] event which is not reverted
+    /// by the rollback of the transaction.
+    pub fn sweep(&mut self) -> Vec<Atom> {
+        let now = self.clock.now();
+        if !self.has_expired(now) {
+            return Vec::new();
+        }
+        let (mut expired, expired_now, live) = self.deadlines.split_lookup(&now);
+        self.deadlines = live;
+        if let Some(atoms) = expired_now {
+            expired.insert(now, atoms);
+        }
+        let mut removed = Vec::new();
+        for (expires_at, atoms) in expired {
+            for atom in atoms {
+                if let Some(times) = self.expiries.get_mut(&atom) {
+                    if let Some(i) = times.iter().position(|time| *time == expires_at) {
+                        times.swap_remove(i);
+                    }
+                    if times.is_empty() {
+                        self.expiries.remove(&atom);
+                    }
+                }
+                if self.index.remove(&atom) {
+                    removed.push(atom);
+                }
+            }
+        }
+        log::debug!("GroundingSpace::sweep: {}, removed: {}", self, removed.len());
+        // Expired atoms are not returned back when transaction is rolled back
+        if !removed.is_empty() {
+            let event = SpaceEvent::Batch(removed.iter().cloned().map(SpaceEvent::Remove).collect());
+            self.common.notify_all_observers_irreversible(&event);
+        }
+        removed
+    }
+
+    /// Forgets `count` expiration times of the `atom` after its copies are
+    /// removed from the space. The earliest expiration times are forgotten
+    /// first, thus the remaining copies live as long as possible.
+    fn forget_expiry(&mut self, atom: &Atom, count: usize) {
+        let Some(times) = self.expiries.get_mut(atom) else {
+            return;
+        };
+        times.sort();
+        let forgotten: Vec<SystemTime> = times.drain(..count.min(times.len())).collect();
+        if times.is_empty() {
+            self.expiries.remove(atom);
+        }
+        for expires_at in forgotten {
+            if let Some(atoms) = self.deadlines.get_mut(&expires_at) {
+                if let Some(i) = atoms.iter().position(|a| a == atom) {
+                    atoms.swap_remove(i);
+                }
+                if atoms.is_empty() {
+                    self.deadlines.remove(&expires_at);
+                }
+            }
+        }
+    }
+
+    fn has_expired(&self, now: SystemTime) -> bool {
+        self.deadlines.get_min().is_some_and(|(expires_at, _)| *expires_at <= now)
+    }
+
+    /// Returns index without expired atoms. Index is copied only when there
+    /// are expired atoms which are not swept yet.
+    fn live_index(&self) -> Cow<'_, AtomIndex<D>> {
+        let now = self.clock.now();
+        if !self.has_expired(now) {
+            return Cow::Borrowed(&self.index);
+        }
+        let mut index = self.index.clone();
+        for (_, atoms) in self.deadlines.range(..=now) {
+            for atom in atoms {
+                index.remove(atom);
+            }
+        }
+        Cow::Owned(index)
+    }
+
+    fn notify_batch(&self, events: Vec<SpaceEvent>) {
+        if !events.is_empty() {
+            self.common.notify_all_observers(&SpaceEvent::Batch(events));
+        }
+    }
+
     /// Executes `query` on the space and returns variable bindings found.
-    /// Query may include sub-queries glued by [COMMA_SYMBOL] symbol.
+    /// Query may include sub-queries glued by [COMMA_SYMBOL] symbol and other
+    /// query operators, see [hyperon_space::complex_query].
     /// Each [Bindings](matcher::Bindings) instance in the returned [BindingsSet]
     /// represents single result.
     ///
@@ -145,15 +361,16 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
     /// assert_eq!(result, bind_set![{x: sym!("B")}]);
     /// ```
     pub fn query(&self, query: &Atom) -> BindingsSet {
-        complex_query(query, |query| self.single_query(query))
+        let index = self.live_index();
+        complex_query_with_statistics(query, Some(&self.index), |query| self.single_query(&index, query))
     }
 
     /// Executes simple `query` without sub-queries on the space.
-    fn single_query(&self, query: &Atom) -> BindingsSet {
+    fn single_query(&self, index: &AtomIndex<D>, query: &Atom) -> BindingsSet {
         log::debug!("GroundingSpace::single_query: {} query: {}", self, query);
         let mut result = BindingsSet::empty();
         let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
-        for bindings in self.index.query(query) {
+        for bindings in index.query(query) {
             let bindings = bindings.narrow_vars(&query_vars);
             log::trace!("single_query: push result: {}", bindings);
             result.push(bindings);
@@ -162,6 +379,97 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
         result
     }
 
+    /// Executes simple `query` without sub-queries on the space and binds
+    /// `count` variable to the number of times each matched atom is kept in
+    /// the space. Single result is returned for each distinct matched atom,
+    /// see [AtomIndex::query_with_count].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon_space::index::COUNT_DUPLICATION;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::with_strategy(COUNT_DUPLICATION);
+    /// space.add(expr!("seen" "A"));
+    /// space.add(expr!("seen" "A"));
+    /// space.add(expr!("seen" "B"));
+    /// space.remove_count(&expr!("seen" "A"), 1);
+    ///
+    /// let result = space.query_with_count(&expr!("seen" x), &VariableAtom::new("n"));
+    /// assert_eq!(result.len(), 2);
+    /// assert!(result.contains(&bind!{x: sym!("A"), n: expr!({Number::Integer(1)})}));
+    /// ```
+    pub fn query_with_count(&self, query: &Atom, count: &VariableAtom) -> BindingsSet {
+        let mut vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+        vars.insert(count);
+        self.live_index().query_with_count(query, count)
+            .map(|bindings| bindings.narrow_vars(&vars))
+            .collect()
+    }
+
+    /// Returns number of times `atom` is kept in the space.
+    pub fn multiplicity(&self, atom: &Atom) -> usize {
+        self.live_index().multiplicity(atom)
+    }
+
+    /// Decrements counter of the `atom` by `count` instead of removing all
+    /// its copies. Returns the number the counter is actually decremented
+    /// by. Observers are notified by a single [SpaceEvent::RemoveCount]
+    /// event.
+    pub fn remove_count(&mut self, atom: &Atom, count: usize) -> usize {
+        self.sweep();
+        log::debug!("GroundingSpace::remove_count: {}, atom: {}, count: {}", self, atom, count);
+        let removed = self.index.remove_count(atom, count);
+        if removed > 0 {
+            self.forget_expiry(atom, removed);
+            self.common.notify_all_observers(&SpaceEvent::RemoveCount(atom.clone(), removed));
+        }
+        removed
+    }
+
+    /// Attaches numeric `weight` to the `atom`, see [AtomIndex::set_weight].
+    /// Returns `false` if `atom` is not in the space.
+    pub fn set_weight(&mut self, atom: &Atom, weight: Number) -> bool {
+        self.sweep();
+        self.index.set_weight(atom, weight)
+    }
+
+    /// Returns weight of the `atom` or `None` if weight is not set.
+    pub fn weight(&self, atom: &Atom) -> Option<Number> {
+        self.live_index().weight(atom)
+    }
+
+    /// Executes simple `query` without sub-queries on the space and binds
+    /// `weight` variable to the weight of each matched atom. Atoms without
+    /// weight are skipped, see [AtomIndex::query_with_weight].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_atom::gnd::number::Number;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::new();
+    /// space.add(expr!("attention" "A"));
+    /// space.add(expr!("attention" "B"));
+    /// space.set_weight(&expr!("attention" "A"), Number::Float(0.8));
+    ///
+    /// let result = space.query_with_weight(&expr!("attention" x), &VariableAtom::new("w"));
+    /// assert_eq!(result.len(), 1);
+    /// assert!(result.contains(&bind!{x: sym!("A"), w: expr!({Number::Float(0.8)})}));
+    /// ```
+    pub fn query_with_weight(&self, query: &Atom, weight: &VariableAtom) -> BindingsSet {
+        let mut vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+        vars.insert(weight);
+        self.live_index().query_with_weight(query, weight)
+            .map(|bindings| bindings.narrow_vars(&vars))
+            .collect()
+    }
+
     /// Sets the name property for the `GroundingSpace` which can be useful for debugging
     pub fn set_name(&mut self, name: String) {
         self.name = Some(name);
@@ -172,13 +480,39 @@ impl<D: DuplicationStrategy> GroundingSpace<D> {
         self.name.as_ref().map(|s| s.as_str())
     }
 
+    /// Returns a fork of the space. Forking is O(1): the space and the fork
+    /// share the atoms and only the modified part of the index is copied
+    /// when one of them is changed. Observers are not copied to the fork.
+    /// Use [hyperon_space::diff_spaces] to compare forks and
+    /// [hyperon_space::SpaceDiff::apply] to merge changes back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::sym;
+    /// use hyperon_atom::matcher::BindingsSet;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![sym!("A")]);
+    /// let mut fork = space.fork();
+    ///
+    /// fork.add(sym!("B"));
+    ///
+    /// assert_eq!(space.query(&sym!("B")), BindingsSet::empty());
+    /// assert_eq!(fork.query(&sym!("A")), BindingsSet::single());
+    /// assert_eq!(fork.query(&sym!("B")), BindingsSet::single());
+    /// ```
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
     #[cfg(test)]
     fn into_vec(&self) -> Vec<Atom> {
         self.index.iter().map(|a| a.into_owned()).collect()
     }
 }
 
-impl Space for GroundingSpace {
+impl<D: DuplicationStrategy + 'static> Space for GroundingSpace<D> {
     fn common(&self) -> FlexRef<'_, SpaceCommon> {
         FlexRef::from_simple(&self.common)
     }
@@ -186,17 +520,39 @@ impl Space for GroundingSpace {
         GroundingSpace::query(self, query)
     }
     fn atom_count(&self) -> Option<usize> {
-        Some(self.index.iter().count())
+        Some(self.live_index().iter().count())
     }
     fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
-       Ok(self.index.iter().for_each(|atom| v.accept(atom)))
+        let index = self.live_index();
+        Ok(index.iter().for_each(|atom| v.accept(atom)))
+    }
+    /// Number of atoms is calculated using index when it is possible.
+    /// Results of the query without sub-queries are passed to the aggregate
+    /// directly from the index instead of collecting them.
+    fn aggregate(&self, query: &Atom, aggregate: &Aggregate) -> Result<Option<Atom>, String> {
+        if is_complex_query(query) {
+            return aggregate.apply(self.query(query));
+        }
+        let index = self.live_index();
+        if *aggregate == Aggregate::Count {
+            if let Some(count) = index.count(query) {
+                return Ok(Some(Atom::gnd(Number::Integer(count as i64))));
+            }
+        }
+        aggregate.apply(index.query(query))
+    }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        Some(&self.index)
+    }
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        Some(self.index.memory_stats())
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
 }
 
-impl SpaceMut for GroundingSpace {
+impl<D: DuplicationStrategy + 'static> SpaceMut for GroundingSpace<D> {
     fn add(&mut self, atom: Atom) {
         GroundingSpace::add(self, atom)
     }
@@ -206,6 +562,22 @@ impl SpaceMut for GroundingSpace {
     fn replace(&mut self, from: &Atom, to: Atom) -> bool {
         GroundingSpace::replace(self, from, to)
     }
+    fn add_all(&mut self, atoms: Vec<Atom>) {
+        GroundingSpace::add_all(self, atoms)
+    }
+    fn remove_all(&mut self, atoms: &[Atom]) -> usize {
+        GroundingSpace::remove_all(self, atoms)
+    }
+    fn remove_matching(&mut self, pattern: &Atom) -> Vec<Atom> {
+        GroundingSpace::remove_matching(self, pattern)
+    }
+    fn compact_memory(&mut self) {
+        self.index.compact()
+    }
+    fn add_with_ttl(&mut self, atom: Atom, ttl: Duration) -> Result<(), String> {
+        GroundingSpace::add_with_ttl(self, atom, ttl);
+        Ok(())
+    }
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -244,12 +616,12 @@ pub(crate) fn metta_space(text: &str) -> DynSpace {
 
 #[cfg(test)]
 mod test {
-    use std::borrow::Cow;
-
     use super::*;
     use hyperon_atom::matcher::*;
     use hyperon_common::assert_eq_no_order;
-    use hyperon_space::SpaceObserver;
+    use hyperon_space::{SpaceObserver, diff_spaces};
+    use hyperon_space::merge::merge_spaces;
+    use crate::space::clock::ManualClock;
 
     struct SpaceEventCollector {
         events: Vec<SpaceEvent>,
@@ -297,6 +669,23 @@ mod test {
             SpaceEvent::Remove(sym!("b"))]);
     }
 
+    #[test]
+    fn remove_count_notifies_single_event() {
+        let mut space = GroundingSpace::with_strategy(hyperon_space::index::COUNT_DUPLICATION);
+        space.add(expr!("a"));
+        space.add(expr!("a"));
+        space.add(expr!("a"));
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        assert_eq!(space.remove_count(&expr!("a"), 2), 2);
+        assert_eq!(space.remove_count(&expr!("a"), 2), 1);
+        assert_eq!(space.remove_count(&expr!("a"), 2), 0);
+
+        assert_eq!(space.multiplicity(&expr!("a")), 0);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::RemoveCount(sym!("a"), 2),
+            SpaceEvent::RemoveCount(sym!("a"), 1)]);
+    }
+
     #[test]
     fn remove_duplicated_atom() {
         let mut space = GroundingSpace::new();
@@ -396,6 +785,187 @@ mod test {
         assert_eq_no_order!(atoms, vec![expr!("a"), expr!("c")]);
     }
 
+    #[test]
+    fn diff_dyn_spaces() {
+        let old = DynSpace::new(GroundingSpace::from_vec(vec![expr!("a"), expr!("b"), expr!("b")]));
+        let new = DynSpace::new(GroundingSpace::from_vec(vec![expr!("b"), expr!("c" x)]));
+
+        let diff = old.diff(&new).unwrap();
+        assert_eq!(diff.added, vec![expr!("c" x)]);
+        assert_eq_no_order!(diff.removed, vec![expr!("a"), expr!("b")]);
+        assert!(old.diff(&old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fork_and_merge_back() {
+        let mut parent = GroundingSpace::from_vec(vec![expr!("a"), expr!("b" x)]);
+        let mut fork = parent.fork();
+        fork.add(expr!("c"));
+        assert!(fork.remove(&expr!("a")));
+        assert!(fork.replace(&expr!("b" x), expr!("b" "d")));
+
+        assert_eq_no_order!(parent.into_vec(), vec![expr!("a"), expr!("b" x)]);
+        assert_eq_no_order!(fork.into_vec(), vec![expr!("b" "d"), expr!("c")]);
+        assert_eq!(parent.query(&expr!("a")), BindingsSet::single());
+
+        let diff = diff_spaces(&parent, &fork).unwrap();
+        assert!(diff.apply(&mut parent));
+        assert_eq_no_order!(parent.into_vec(), vec![expr!("b" "d"), expr!("c")]);
+    }
+
+    #[test]
+    fn three_way_merge_of_forks() {
+        let base = GroundingSpace::from_vec(vec![expr!("a"), expr!("age" "Bob" "35"), expr!("likes" "Bob" "tea")]);
+        let mut ours = base.fork();
+        ours.add(expr!("c"));
+        assert!(ours.remove(&expr!("a")));
+        assert!(ours.replace(&expr!("age" "Bob" "35"), expr!("age" "Bob" "36")));
+        let mut theirs = base.fork();
+        theirs.add(expr!("c"));
+        theirs.add(expr!("d"));
+        assert!(theirs.remove(&expr!("a")));
+        assert!(theirs.replace(&expr!("likes" "Bob" "tea"), expr!("likes" "Bob" "coffee")));
+
+        let merge = merge_spaces(&base, &ours, &theirs).unwrap();
+        assert!(!merge.has_conflicts());
+        assert_eq!(merge.events, vec![
+            SpaceEvent::Replace(expr!("age" "Bob" "35"), expr!("age" "Bob" "36")),
+            SpaceEvent::Remove(expr!("a")),
+            SpaceEvent::Replace(expr!("likes" "Bob" "tea"), expr!("likes" "Bob" "coffee")),
+            SpaceEvent::Add(expr!("c")),
+            SpaceEvent::Add(expr!("d")),
+        ]);
+
+        let target = DynSpace::new(base.fork());
+        let observer = target.common().register_observer(SpaceEventCollector::new());
+        assert!(merge.replay(&target));
+        assert_eq!(observer.borrow().events.len(), 5);
+        let mut merged = Vec::new();
+        target.borrow().visit(&mut |atom: Cow<Atom>| merged.push(atom.into_owned())).unwrap();
+        assert_eq_no_order!(merged, vec![expr!("age" "Bob" "36"),
+            expr!("likes" "Bob" "coffee"), expr!("c"), expr!("d")]);
+    }
+
+    #[test]
+    fn three_way_merge_does_not_pair_unrelated_atoms() {
+        let base = GroundingSpace::from_vec(vec![expr!("likes" "Bob" "tea")]);
+        let ours = GroundingSpace::from_vec(vec![expr!("age" "Alice" "40")]);
+        let theirs = GroundingSpace::new();
+
+        let merge = merge_spaces(&base, &ours, &theirs).unwrap();
+        assert!(!merge.has_conflicts());
+        assert_eq!(merge.events, vec![
+            SpaceEvent::Remove(expr!("likes" "Bob" "tea")),
+            SpaceEvent::Add(expr!("age" "Alice" "40")),
+        ]);
+
+        let ours = GroundingSpace::from_vec(vec![expr!("likes" "Ann" "coffee")]);
+        let merge = merge_spaces(&base, &ours, &theirs).unwrap();
+        assert!(!merge.has_conflicts());
+        assert_eq!(merge.events, vec![
+            SpaceEvent::Remove(expr!("likes" "Bob" "tea")),
+            SpaceEvent::Add(expr!("likes" "Ann" "coffee")),
+        ]);
+    }
+
+    #[test]
+    fn add_and_remove_atoms_in_batch() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add_all(vec![expr!("a" "b"), expr!("a" "c"), expr!("b" "c")]);
+        assert_eq!(space.remove_all(&[expr!("b" "c"), expr!("d")]), 1);
+        assert_eq_no_order!(space.remove_matching(&expr!("a" x)), vec![expr!("a" "b"), expr!("a" "c")]);
+        space.add_all(vec![]);
+
+        assert!(space.into_vec().is_empty());
+        let events = observer.borrow().events.clone();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], SpaceEvent::Batch(vec![SpaceEvent::Add(expr!("a" "b")),
+            SpaceEvent::Add(expr!("a" "c")), SpaceEvent::Add(expr!("b" "c"))]));
+        assert_eq!(events[1], SpaceEvent::Batch(vec![SpaceEvent::Remove(expr!("b" "c"))]));
+        assert!(matches!(&events[2], SpaceEvent::Batch(removed) if removed.len() == 2));
+    }
+
+    #[test]
+    fn transaction_rollback_batch() {
+        let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("a" "b")]));
+
+        let transaction = space.begin_transaction();
+        transaction.space().borrow_mut().add_all(vec![expr!("a" "c"), expr!("d")]);
+        transaction.space().borrow_mut().remove_matching(&expr!("a" x));
+        transaction.rollback();
+
+        assert_dyn_space_atoms(&space, vec![expr!("a" "b")]);
+    }
+
+    fn assert_dyn_space_atoms(space: &DynSpace, atoms: Vec<Atom>) {
+        let expected = DynSpace::new(GroundingSpace::from_vec(atoms));
+        let diff = space.diff(&expected).unwrap();
+        assert!(diff.is_empty(), "unexpected difference:\n{}", diff);
+    }
+
+    #[test]
+    fn transaction_commit() {
+        let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("a")]));
+        let observer = space.common().register_observer(SpaceEventCollector::new());
+
+        let transaction = space.begin_transaction();
+        transaction.add(expr!("b"));
+        assert!(transaction.replace(&expr!("a"), expr!("c")));
+        assert!(space.common().in_transaction());
+        assert!(observer.borrow().events.is_empty());
+        transaction.commit();
+
+        assert!(!space.common().in_transaction());
+        assert_dyn_space_atoms(&space, vec![expr!("b"), expr!("c")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("b")),
+            SpaceEvent::Replace(sym!("a"), sym!("c"))]);
+    }
+
+    #[test]
+    fn transaction_rollback() {
+        let space = DynSpace::new(GroundingSpace::from_vec(vec![expr!("a"), expr!("b")]));
+        let observer = space.common().register_observer(SpaceEventCollector::new());
+
+        let transaction = space.begin_transaction();
+        transaction.add(expr!("c"));
+        assert!(transaction.remove(&expr!("a")));
+        assert!(transaction.replace(&expr!("b"), expr!("d")));
+        transaction.rollback();
+
+        assert_dyn_space_atoms(&space, vec![expr!("a"), expr!("b")]);
+        assert!(observer.borrow().events.is_empty());
+
+        {
+            let transaction = space.begin_transaction();
+            transaction.add(expr!("c"));
+        }
+        assert_dyn_space_atoms(&space, vec![expr!("a"), expr!("b")]);
+        assert!(observer.borrow().events.is_empty());
+    }
+
+    #[test]
+    fn transaction_nested() {
+        let space = DynSpace::new(GroundingSpace::new());
+        let observer = space.common().register_observer(SpaceEventCollector::new());
+
+        let outer = space.begin_transaction();
+        outer.add(expr!("a"));
+        let inner = space.begin_transaction();
+        inner.add(expr!("b"));
+        inner.commit();
+        let inner = space.begin_transaction();
+        inner.add(expr!("c"));
+        inner.rollback();
+        assert!(observer.borrow().events.is_empty());
+        outer.commit();
+
+        assert_dyn_space_atoms(&space, vec![expr!("a"), expr!("b")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
+            SpaceEvent::Add(sym!("b"))]);
+    }
+
     #[test]
     fn mut_cloned_atomspace() {
         let mut first = GroundingSpace::new();
@@ -533,6 +1103,139 @@ mod test {
         assert_eq!(result.resolve(&VariableAtom::new("b")), Some(expr!({4})));
     }
 
+    #[test]
+    fn complex_query_planned_using_statistics() {
+        let mut space = GroundingSpace::new();
+        for i in 0..10 {
+            space.add(expr!("edge" {Number::Integer(i)} {Number::Integer(i + 1)}));
+        }
+        space.add(expr!("start" {Number::Integer(5)}));
+
+        let queries = std::cell::RefCell::new(Vec::new());
+        let result = complex_query_with_statistics(&expr!("," ("edge" x y) ("start" x)),
+            space.statistics(), |query| {
+                queries.borrow_mut().push(query.clone());
+                space.query(query)
+            });
+
+        assert_eq!(result, bind_set![{x: expr!({Number::Integer(5)}), y: expr!({Number::Integer(6)})}]);
+        assert_eq!(queries.into_inner(), vec![expr!("start" x),
+            expr!("edge" {Number::Integer(5)} y)]);
+    }
+
+    #[test]
+    fn complex_query_not_or_optional() {
+        let space = GroundingSpace::from_vec(vec![
+            expr!("person" "Alice"), expr!("person" "Bob"), expr!("person" "Carol"),
+            expr!("likes" "Alice" "Bob"), expr!("hates" "Bob" "Carol"),
+        ]);
+
+        assert_eq!(space.query(&expr!("," ("query:not" ("likes" x y)) ("person" x))),
+            bind_set![{x: sym!("Bob")}, {x: sym!("Carol")}]);
+        assert_eq!(space.query(&expr!("query:or" ("likes" x y) ("hates" x y))),
+            bind_set![{x: sym!("Alice"), y: sym!("Bob")}, {x: sym!("Bob"), y: sym!("Carol")}]);
+        assert_eq!(space.query(&expr!("," ("person" x) ("query:optional" ("likes" x y)))),
+            bind_set![{x: sym!("Alice"), y: sym!("Bob")}, {x: sym!("Bob")}, {x: sym!("Carol")}]);
+        assert_eq!(space.query(&expr!("query:not" ("person" "Dave"))), BindingsSet::single());
+    }
+
+    #[test]
+    fn complex_query_operators_without_prefix_are_patterns() {
+        let space = GroundingSpace::from_vec(vec![
+            expr!("not" "A"), expr!("or" "A" "B"), expr!("optional" "C"),
+        ]);
+
+        assert_eq!(space.query(&expr!("not" x)), bind_set![{x: sym!("A")}]);
+        assert_eq!(space.query(&expr!("or" x y)), bind_set![{x: sym!("A"), y: sym!("B")}]);
+        assert_eq!(space.query(&expr!("optional" x)), bind_set![{x: sym!("C")}]);
+    }
+
+    #[test]
+    fn complex_query_filter() {
+        use crate::metta::runner::stdlib::arithmetics::GreaterOp;
+        let space = GroundingSpace::from_vec(vec![
+            expr!("age" "Alice" {Number::Integer(30)}), expr!("age" "Bob" {Number::Integer(20)}),
+        ]);
+
+        assert_eq!(space.query(&expr!("," ("query:filter" ({GreaterOp{}} a {Number::Integer(25)})) ("age" x a))),
+            bind_set![{x: sym!("Alice"), a: expr!({Number::Integer(30)})}]);
+        assert_eq!(space.query(&expr!("," ("age" x a) ("query:filter" ({GreaterOp{}} b {Number::Integer(25)})))),
+            BindingsSet::empty());
+    }
+
+    #[test]
+    fn standing_query_conjunction_delta() {
+        use hyperon_space::reactive::{StandingQuery, QueryDelta};
+        let space = DynSpace::new(GroundingSpace::from_vec(vec![
+            expr!("parent" "Tom" "Bob"), expr!("parent" "Bob" "Ann"),
+        ]));
+        let query = StandingQuery::register(&space,
+            expr!("," ("parent" x y) ("parent" y z))).unwrap();
+        assert_eq!(query.borrow().results(), &[bind!{x: sym!("Tom"), y: sym!("Bob"), z: sym!("Ann")}]);
+
+        space.borrow_mut().add(expr!("parent" "Ann" "Eve"));
+        space.borrow_mut().add(expr!("unrelated" "A"));
+        assert_eq!(query.borrow_mut().take_delta(), QueryDelta{
+            added: vec![bind!{x: sym!("Bob"), y: sym!("Ann"), z: sym!("Eve")}],
+            removed: vec![],
+        });
+
+        space.borrow_mut().remove(&expr!("parent" "Tom" "Bob"));
+        assert_eq!(query.borrow_mut().take_delta(), QueryDelta{
+            added: vec![],
+            removed: vec![bind!{x: sym!("Tom"), y: sym!("Bob"), z: sym!("Ann")}],
+        });
+        assert_eq!(query.borrow().results(), &[bind!{x: sym!("Bob"), y: sym!("Ann"), z: sym!("Eve")}]);
+    }
+
+    #[test]
+    fn standing_query_added_and_removed_cancel_out() {
+        use hyperon_space::reactive::StandingQuery;
+        let space = DynSpace::new(GroundingSpace::new());
+        let query = StandingQuery::register(&space, expr!("A" x)).unwrap();
+
+        space.borrow_mut().add(expr!("A" "B"));
+        space.borrow_mut().replace(&expr!("A" "B"), expr!("A" "C"));
+        space.borrow_mut().remove(&expr!("A" "C"));
+
+        assert!(query.borrow_mut().take_delta().is_empty());
+        assert!(query.borrow().results().is_empty());
+    }
+
+    #[test]
+    fn standing_query_with_negation() {
+        use hyperon_space::reactive::{StandingQuery, QueryDelta};
+        let space = DynSpace::new(GroundingSpace::from_vec(vec![
+            expr!("task" "T1"), expr!("task" "T2"), expr!("done" "T1"),
+        ]));
+        let query = StandingQuery::register(&space,
+            expr!("," ("task" x) ("query:not" ("done" x)))).unwrap();
+        assert_eq!(query.borrow().results(), &[bind!{x: sym!("T2")}]);
+
+        space.borrow_mut().add_all(vec![expr!("done" "T2")]);
+        space.borrow_mut().remove(&expr!("done" "T1"));
+        assert_eq!(query.borrow_mut().take_delta(), QueryDelta{
+            added: vec![bind!{x: sym!("T1")}],
+            removed: vec![bind!{x: sym!("T2")}],
+        });
+    }
+
+    #[test]
+    fn standing_query_callback() {
+        use hyperon_space::reactive::{StandingQuery, QueryDelta};
+        let space = DynSpace::new(GroundingSpace::new());
+        let deltas = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let deltas_ref = deltas.clone();
+        let query = StandingQuery::new(&*space.borrow(), expr!("A" x)).unwrap()
+            .with_callback(move |delta| deltas_ref.borrow_mut().push(delta.clone()));
+        let _query = space.common().register_observer(query);
+
+        space.borrow_mut().add(expr!("A" "B"));
+        space.borrow_mut().add(expr!("C" "D"));
+
+        assert_eq!(*deltas.borrow(), vec![QueryDelta{ added: vec![bind!{x: sym!("B")}], removed: vec![] }]);
+    }
+
     #[test]
     fn complex_query_chain_of_bindings() {
         let mut space = GroundingSpace::new();
@@ -568,4 +1271,68 @@ mod test {
         let result: BindingsSet = match_atoms(&Atom::gnd(DynSpace::new(space)), &expr!("A" {1} x x)).collect();
         assert_eq!(result, bind_set![{x: sym!("a")}]);
     }
+
+    #[test]
+    fn add_with_ttl_expires_atom() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut space = GroundingSpace::new();
+        space.set_clock(Arc::new(clock.clone()));
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("fact" "A"));
+        space.add_with_ttl(expr!("fact" "B"), Duration::from_secs(5));
+        space.add_with_ttl(expr!("fact" "C"), Duration::from_secs(10));
+        assert_eq!(space.atom_count(), Some(3));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq_no_order!(space.query(&expr!("fact" x)),
+            vec![bind!{x: sym!("A")}, bind!{x: sym!("C")}]);
+        assert_eq!(space.atom_count(), Some(2));
+        assert_eq!(space.into_vec().len(), 3);
+
+        assert_eq!(space.sweep(), vec![expr!("fact" "B")]);
+        assert_eq!(space.sweep(), vec![]);
+        assert_eq!(observer.borrow().events[3..], [
+            SpaceEvent::Batch(vec![SpaceEvent::Remove(expr!("fact" "B"))])]);
+
+        clock.advance(Duration::from_secs(5));
+        space.add(expr!("fact" "D"));
+        assert_eq_no_order!(space.into_vec(), vec![expr!("fact" "A"), expr!("fact" "D")]);
+        assert_eq!(observer.borrow().events[4..], [
+            SpaceEvent::Batch(vec![SpaceEvent::Remove(expr!("fact" "C"))]),
+            SpaceEvent::Add(expr!("fact" "D"))]);
+    }
+
+    #[test]
+    fn removed_atom_does_not_expire_when_added_again() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut space = GroundingSpace::new();
+        space.set_clock(Arc::new(clock.clone()));
+
+        space.add_with_ttl(expr!("fact" "A"), Duration::from_secs(5));
+        assert!(space.remove(&expr!("fact" "A")));
+        space.add(expr!("fact" "A"));
+        space.add_with_ttl(expr!("fact" "B"), Duration::from_secs(5));
+        assert!(space.replace(&expr!("fact" "B"), expr!("fact" "C")));
+        space.add(expr!("fact" "B"));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(space.sweep(), vec![]);
+        assert_eq_no_order!(space.into_vec(),
+            vec![expr!("fact" "A"), expr!("fact" "B"), expr!("fact" "C")]);
+    }
+
+    #[test]
+    fn expired_atom_is_not_visited() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut space = GroundingSpace::new();
+        space.set_clock(Arc::new(clock.clone()));
+        space.add_with_expiry(sym!("A"), SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        space.add(sym!("B"));
+
+        clock.set(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        let mut atoms = Vec::new();
+        space.visit(&mut |atom: Cow<Atom>| atoms.push(atom.into_owned())).unwrap();
+        assert_eq!(atoms, vec![sym!("B")]);
+    }
 }
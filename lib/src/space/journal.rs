@@ -0,0 +1,558 @@
+//! Append-only journal of the space modifications. [JournalSpace] wraps any
+//! [SpaceMut] and writes each add, remove and replace operation into the
+//! journal file together with its sequence number, time and optional
+//! context atom, for instance the author of the change. Journal can be read
+//! by [read_journal] and replayed up to some sequence number by
+//! [replay_journal] to reconstruct the past states of the space.
+//!
+//! Journal records are framed in the same way as the records of the
+//! [persistent](super::persistent) space log. Payload of the record
+//! contains operation code (1 byte), sequence number (8 bytes little
+//! endian), seconds and nanoseconds since Unix epoch (8 and 4 bytes little
+//! endian), context flag (1 byte) followed by the encoded context atom if
+//! flag is set, expiration time (12 bytes as above) for atoms added with
+//! TTL, and the encoded atoms of the operation.
+//!
+//! Record is written after the wrapped space is changed and the change is
+//! rolled back if record cannot be written. Expired atoms are removed from
+//! the wrapped space without the wrapper, thus removals of the expired atoms
+//! are not written. Instead expiration time is written together with the
+//! added atom and [replay_journal] removes atoms which are expired at the
+//! time of the replayed record.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::BindingsSet;
+use hyperon_common::FlexRef;
+use hyperon_space::{Space, SpaceCommon, SpaceEvent, SpaceMut, SpaceStatistics, SpaceVisitor};
+use hyperon_space::aggregate::Aggregate;
+use hyperon_space::index::MemoryStats;
+
+use super::clock::{Clock, SystemClock};
+use super::codec::{encode_atom, decode_atom};
+use super::persistent::{append_frame, append_log, open_log, read_frames, truncate_log};
+
+use std::fmt::{Debug, Display};
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const MAGIC: &[u8; 8] = b"MeTTaJnl";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 12;
+
+const OP_ADD: u8 = 0;
+const OP_REMOVE: u8 = 1;
+const OP_REPLACE: u8 = 2;
+const OP_ADD_EXPIRING: u8 = 3;
+
+/// Single record of the journal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    /// Sequence number of the record, records are numbered starting from 1.
+    pub seq: u64,
+    /// Time when the operation was made.
+    pub time: SystemTime,
+    /// Context of the operation set by [JournalSpace::set_context].
+    pub context: Option<Atom>,
+    /// Expiration time of the atom added by [SpaceMut::add_with_ttl].
+    pub expires_at: Option<SystemTime>,
+    /// Operation: [SpaceEvent::Add], [SpaceEvent::Remove] or
+    /// [SpaceEvent::Replace].
+    pub event: SpaceEvent,
+}
+
+impl JournalEntry {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        append_frame(buf, |buf| {
+            let atoms = match &self.event {
+                SpaceEvent::Add(atom) if self.expires_at.is_some() => { buf.push(OP_ADD_EXPIRING); vec![atom] },
+                SpaceEvent::Add(atom) => { buf.push(OP_ADD); vec![atom] },
+                SpaceEvent::Remove(atom) => { buf.push(OP_REMOVE); vec![atom] },
+                SpaceEvent::Replace(from, to) => { buf.push(OP_REPLACE); vec![from, to] },
+                SpaceEvent::Batch(_) => return Err("Batch event cannot be written into journal".into()),
+                SpaceEvent::RemoveCount(_, _) => return Err("RemoveCount event cannot be written into journal".into()),
+            };
+            buf.extend(self.seq.to_le_bytes());
+            encode_time(self.time, buf);
+            match &self.context {
+                Some(context) => {
+                    buf.push(1);
+                    encode_atom(context, buf)?;
+                },
+                None => buf.push(0),
+            }
+            if let (SpaceEvent::Add(_), Some(expires_at)) = (&self.event, self.expires_at) {
+                encode_time(expires_at, buf);
+            }
+            atoms.into_iter().try_for_each(|atom| encode_atom(atom, buf))
+        })
+    }
+
+    fn decode(mut payload: &[u8]) -> Result<Self, String> {
+        let (&op, rest) = payload.split_first().ok_or("empty record")?;
+        payload = rest;
+        let seq = u64::from_le_bytes(take_bytes(&mut payload)?);
+        let time = decode_time(&mut payload)?;
+        let context = match take_bytes::<1>(&mut payload)? {
+            [0] => None,
+            _ => Some(decode_atom(&mut payload)?),
+        };
+        let mut expires_at = None;
+        let event = match op {
+            OP_ADD => SpaceEvent::Add(decode_atom(&mut payload)?),
+            OP_ADD_EXPIRING => {
+                expires_at = Some(decode_time(&mut payload)?);
+                SpaceEvent::Add(decode_atom(&mut payload)?)
+            },
+            OP_REMOVE => SpaceEvent::Remove(decode_atom(&mut payload)?),
+            OP_REPLACE => {
+                let from = decode_atom(&mut payload)?;
+                let to = decode_atom(&mut payload)?;
+                SpaceEvent::Replace(from, to)
+            },
+            op => return Err(format!("unexpected operation code: {}", op)),
+        };
+        Ok(Self{ seq, time, context, expires_at, event })
+    }
+}
+
+fn encode_time(time: SystemTime, buf: &mut Vec<u8>) {
+    let time = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    buf.extend(time.as_secs().to_le_bytes());
+    buf.extend(time.subsec_nanos().to_le_bytes());
+}
+
+fn decode_time(buf: &mut &[u8]) -> Result<SystemTime, String> {
+    let secs = u64::from_le_bytes(take_bytes(buf)?);
+    let nanos = u32::from_le_bytes(take_bytes(buf)?);
+    Ok(SystemTime::UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+fn take_bytes<const N: usize>(buf: &mut &[u8]) -> Result<[u8; N], String> {
+    if buf.len() < N {
+        return Err("unexpected end of record".into());
+    }
+    let (bytes, rest) = buf.split_at(N);
+    *buf = rest;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn header() -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..].copy_from_slice(&VERSION.to_le_bytes());
+    header
+}
+
+const NOT_A_JOURNAL: &str = "file is not a space journal or journal version is not supported";
+
+/// Decodes journal entries from the `reader` positioned after the header
+/// and passes them to `f`. Returns length of the correctly written part of
+/// the journal.
+fn decode_entries<R: Read, F: FnMut(JournalEntry)>(reader: R, mut f: F) -> Result<u64, String> {
+    read_frames(reader, HEADER_LEN as u64, |pos, payload| {
+        let entry = JournalEntry::decode(payload)
+            .map_err(|e| format!("record at {} is corrupted: {}", pos, e))?;
+        f(entry);
+        Ok(())
+    })
+}
+
+/// Reads all entries of the journal file written by [JournalSpace].
+/// Partially written record at the end of the file is ignored.
+pub fn read_journal<P: AsRef<Path>>(path: P) -> Result<Vec<JournalEntry>, String> {
+    let error = |e: String| format!("Cannot read journal {}: {}", path.as_ref().display(), e);
+    let mut file = File::open(path.as_ref()).map_err(|e| error(e.to_string()))?;
+    let mut actual = [0; HEADER_LEN];
+    match file.read_exact(&mut actual) {
+        Ok(()) if actual == header() => {},
+        Ok(()) => return Err(error(NOT_A_JOURNAL.into())),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Err(error(NOT_A_JOURNAL.into())),
+        Err(e) => return Err(error(e.to_string())),
+    }
+    let mut entries = Vec::new();
+    decode_entries(file, |entry| entries.push(entry)).map_err(error)?;
+    Ok(entries)
+}
+
+/// Applies `entries` which sequence number is not greater than `up_to` to
+/// the `space`. Replaying the journal on the space which was wrapped into
+/// [JournalSpace] initially reconstructs the state of the space after the
+/// `up_to` operation. Atoms added with TTL are removed when they are expired
+/// at the time of the next applied entry. Returns number of the applied
+/// entries.
+pub fn replay_journal<S: SpaceMut + ?Sized>(entries: &[JournalEntry], space: &mut S, up_to: u64) -> usize {
+    let mut applied = 0;
+    let mut expiring = Vec::new();
+    let mut last_time = None;
+    for entry in entries.iter().filter(|entry| entry.seq <= up_to) {
+        remove_expired(&mut expiring, space, entry.time);
+        last_time = Some(entry.time);
+        match &entry.event {
+            SpaceEvent::Add(atom) => {
+                if let Some(expires_at) = entry.expires_at {
+                    expiring.push((expires_at, atom.clone()));
+                }
+                space.add(atom.clone())
+            },
+            SpaceEvent::Remove(atom) => { space.remove(atom); },
+            SpaceEvent::Replace(from, to) => { space.replace(from, to.clone()); },
+            SpaceEvent::Batch(_) | SpaceEvent::RemoveCount(_, _) => continue,
+        }
+        applied += 1;
+    }
+    if let Some(time) = last_time {
+        remove_expired(&mut expiring, space, time);
+    }
+    applied
+}
+
+fn remove_expired<S: SpaceMut + ?Sized>(expiring: &mut Vec<(SystemTime, Atom)>, space: &mut S, now: SystemTime) {
+    expiring.sort_by_key(|(expires_at, _)| *expires_at);
+    let expired = expiring.partition_point(|(expires_at, _)| *expires_at <= now);
+    for (_, atom) in expiring.drain(..expired) {
+        space.remove(&atom);
+    }
+}
+
+/// Space wrapper which writes all modifications of the wrapped space into
+/// the append-only journal file. Only operations which change the space
+/// are written. Only atoms which can be encoded by the
+/// [codec](super::codec) can be added into the space.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::{Space, SpaceEvent};
+/// use hyperon::space::grounding::GroundingSpace;
+/// use hyperon::space::journal::{JournalSpace, read_journal, replay_journal};
+///
+/// let path = std::env::temp_dir().join("journal-space-doc-example.log");
+/// # let _ = std::fs::remove_file(&path);
+/// let mut space = JournalSpace::open(GroundingSpace::new(), &path).unwrap();
+/// space.set_context(Some(sym!("Alice")));
+/// space.try_add(expr!("age" "Bob" "35")).unwrap();
+/// space.try_replace(&expr!("age" "Bob" "35"), expr!("age" "Bob" "36")).unwrap();
+///
+/// let entries = read_journal(&path).unwrap();
+/// assert_eq!(entries[0].seq, 1);
+/// assert_eq!(entries[0].context, Some(sym!("Alice")));
+/// assert_eq!(entries[1].event, SpaceEvent::Replace(expr!("age" "Bob" "35"), expr!("age" "Bob" "36")));
+///
+/// let mut past = GroundingSpace::new();
+/// replay_journal(&entries, &mut past, 1);
+/// assert_eq!(past.query(&expr!("age" "Bob" x)), bind_set![{x: sym!("35")}]);
+/// # let _ = std::fs::remove_file(&path);
+/// ```
+pub struct JournalSpace<S: SpaceMut> {
+    space: S,
+    path: PathBuf,
+    log: File,
+    next_seq: u64,
+    clock: Arc<dyn Clock>,
+    context: Option<Atom>,
+    sync_on_write: bool,
+}
+
+impl<S: SpaceMut> JournalSpace<S> {
+    /// Wraps `space` and opens journal file at `path` or creates new journal
+    /// if file doesn't exist or it is empty. New records are appended to the
+    /// existing journal and continue its numbering. Journal is not replayed
+    /// on the `space`. Returns an error if file is not a journal. Partially
+    /// written record at the end of the file is removed.
+    pub fn open<P: AsRef<Path>>(space: S, path: P) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let io_error = |e: std::io::Error| format!("Cannot open journal {}: {}", path.display(), e);
+        let mut log = open_log(&path, &header(), NOT_A_JOURNAL).map_err(io_error)?;
+
+        let mut next_seq = 1;
+        let valid_len = decode_entries(&mut log, |entry| next_seq = entry.seq + 1)
+            .map_err(|e| format!("Cannot open journal {}: {}", path.display(), e))?;
+        if truncate_log(&log, valid_len).map_err(io_error)? {
+            log::warn!("JournalSpace::open: {}: truncate partially written record at {}", path.display(), valid_len);
+        }
+        drop(log);
+        let log = OpenOptions::new().append(true).open(&path).map_err(io_error)?;
+        Ok(Self{ space, path, log, next_seq, clock: Arc::new(SystemClock), context: None, sync_on_write: true })
+    }
+
+    /// Returns path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns wrapped space.
+    pub fn inner(&self) -> &S {
+        &self.space
+    }
+
+    /// Returns sequence number of the next record.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Sets the clock which is used to timestamp records. [SystemClock] is
+    /// used by default.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Sets the context atom, for instance the author of the changes, which
+    /// is written with the next records.
+    pub fn set_context(&mut self, context: Option<Atom>) {
+        self.context = context;
+    }
+
+    /// Sets whether each write is synchronized with disk, see
+    /// [PersistentSpace::set_sync_on_write](super::persistent::PersistentSpace::set_sync_on_write).
+    pub fn set_sync_on_write(&mut self, sync: bool) {
+        self.sync_on_write = sync;
+    }
+
+    /// Adds `atom` into space. Returns an error if atom cannot be encoded or
+    /// record cannot be written, in this case space is not changed.
+    pub fn try_add(&mut self, atom: Atom) -> Result<(), String> {
+        let record = self.encode(SpaceEvent::Add(atom.clone()), None)?;
+        self.space.add(atom.clone());
+        self.write(record).inspect_err(|_| { self.space.remove(&atom); })
+    }
+
+    /// Adds `atom` which expires after `ttl` passes into space, see
+    /// [SpaceMut::add_with_ttl]. Expiration time is calculated using the
+    /// clock of the journal, see [JournalSpace::set_clock].
+    pub fn try_add_with_ttl(&mut self, atom: Atom, ttl: Duration) -> Result<(), String> {
+        let record = self.encode(SpaceEvent::Add(atom.clone()), Some(ttl))?;
+        self.space.add_with_ttl(atom.clone(), ttl)?;
+        self.write(record).inspect_err(|_| { self.space.remove(&atom); })
+    }
+
+    /// Removes `atom` from space. Returns `Ok(true)` if atom was found and
+    /// removed. Record is written only when atom is removed. Removed atom is
+    /// added back if record cannot be written.
+    pub fn try_remove(&mut self, atom: &Atom) -> Result<bool, String> {
+        let record = self.encode(SpaceEvent::Remove(atom.clone()), None)?;
+        let removed = self.space.remove(atom);
+        if removed {
+            self.write(record).inspect_err(|_| self.space.add(atom.clone()))?;
+        }
+        Ok(removed)
+    }
+
+    /// Replaces `from` atom by `to` atom. Returns `Ok(true)` if `from` was
+    /// found and replaced. Record is written only when atom is replaced.
+    /// Replacement is reverted if record cannot be written.
+    pub fn try_replace(&mut self, from: &Atom, to: Atom) -> Result<bool, String> {
+        let record = self.encode(SpaceEvent::Replace(from.clone(), to.clone()), None)?;
+        let replaced = self.space.replace(from, to.clone());
+        if replaced {
+            self.write(record).inspect_err(|_| { self.space.replace(&to, from.clone()); })?;
+        }
+        Ok(replaced)
+    }
+
+    fn encode(&self, event: SpaceEvent, ttl: Option<Duration>) -> Result<Vec<u8>, String> {
+        let time = self.clock.now();
+        let entry = JournalEntry{ seq: self.next_seq, time, context: self.context.clone(),
+            expires_at: ttl.map(|ttl| time + ttl), event };
+        let mut record = Vec::new();
+        entry.encode(&mut record)?;
+        Ok(record)
+    }
+
+    fn write(&mut self, record: Vec<u8>) -> Result<(), String> {
+        append_log(&mut self.log, &record).map_err(|e| e.to_string())?;
+        self.next_seq += 1;
+        if self.sync_on_write {
+            self.log.sync_data().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: SpaceMut + 'static> Space for JournalSpace<S> {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        self.space.common()
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        self.space.query(query)
+    }
+    fn atom_count(&self) -> Option<usize> {
+        self.space.atom_count()
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.space.visit(v)
+    }
+    fn aggregate(&self, query: &Atom, aggregate: &Aggregate) -> Result<Option<Atom>, String> {
+        self.space.aggregate(query, aggregate)
+    }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        self.space.statistics()
+    }
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        self.space.memory_stats()
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl<S: SpaceMut + 'static> SpaceMut for JournalSpace<S> {
+    fn add(&mut self, atom: Atom) {
+        if let Err(e) = self.try_add(atom) {
+            log::error!("JournalSpace::add: {}: {}", self, e);
+        }
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        self.try_remove(atom).unwrap_or_else(|e| {
+            log::error!("JournalSpace::remove: {}: {}", self, e);
+            false
+        })
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.try_replace(from, to).unwrap_or_else(|e| {
+            log::error!("JournalSpace::replace: {}: {}", self, e);
+            false
+        })
+    }
+    fn add_with_ttl(&mut self, atom: Atom, ttl: Duration) -> Result<(), String> {
+        self.try_add_with_ttl(atom, ttl)
+    }
+    fn compact_memory(&mut self) {
+        self.space.compact_memory()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl<S: SpaceMut> Debug for JournalSpace<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JournalSpace-{} ({self:p})", self.path.display())
+    }
+}
+
+impl<S: SpaceMut> Display for JournalSpace<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JournalSpace-{}", self.path.display())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::space::clock::ManualClock;
+    use crate::space::grounding::GroundingSpace;
+    use hyperon_common::assert_eq_no_order;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hyperon-journal-space-{}-{}.log", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn atoms<S: Space>(space: &S) -> Vec<Atom> {
+        let mut atoms = Vec::new();
+        space.visit(&mut |atom: std::borrow::Cow<Atom>| atoms.push(atom.into_owned())).unwrap();
+        atoms
+    }
+
+    #[test]
+    fn journal_space_records_operations() {
+        let path = temp_path("records");
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(100));
+        let mut space = JournalSpace::open(GroundingSpace::new(), &path).unwrap();
+        space.set_clock(Arc::new(clock.clone()));
+        space.set_context(Some(sym!("Alice")));
+        space.add(expr!("A"));
+        clock.advance(Duration::from_millis(1500));
+        space.set_context(None);
+        assert!(space.replace(&expr!("A"), expr!("B" "b")));
+        assert!(!space.remove(&expr!("C")));
+        assert!(space.remove(&expr!("B" "b")));
+
+        assert_eq!(read_journal(&path).unwrap(), vec![
+            JournalEntry{ seq: 1, time: SystemTime::UNIX_EPOCH + Duration::from_secs(100),
+                context: Some(sym!("Alice")), expires_at: None, event: SpaceEvent::Add(expr!("A")) },
+            JournalEntry{ seq: 2, time: SystemTime::UNIX_EPOCH + Duration::from_millis(101500),
+                context: None, expires_at: None, event: SpaceEvent::Replace(expr!("A"), expr!("B" "b")) },
+            JournalEntry{ seq: 3, time: SystemTime::UNIX_EPOCH + Duration::from_millis(101500),
+                context: None, expires_at: None, event: SpaceEvent::Remove(expr!("B" "b")) },
+        ]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn journal_space_replay() {
+        let path = temp_path("replay");
+        {
+            let mut space = JournalSpace::open(GroundingSpace::new(), &path).unwrap();
+            space.add(expr!("A"));
+            space.add(expr!("B"));
+            space.remove(&expr!("A"));
+        }
+        let mut space = JournalSpace::open(GroundingSpace::new(), &path).unwrap();
+        assert_eq!(space.next_seq(), 4);
+        space.add(expr!("C"));
+
+        let entries = read_journal(&path).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        let mut past = GroundingSpace::new();
+        assert_eq!(replay_journal(&entries, &mut past, 2), 2);
+        assert_eq_no_order!(atoms(&past), vec![expr!("A"), expr!("B")]);
+        let mut current = GroundingSpace::new();
+        assert_eq!(replay_journal(&entries, &mut current, u64::MAX), 4);
+        assert_eq_no_order!(atoms(&current), vec![expr!("B"), expr!("C")]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn journal_space_replays_expired_atoms() {
+        let path = temp_path("ttl");
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut inner = GroundingSpace::new();
+        inner.set_clock(Arc::new(clock.clone()));
+        let mut space = JournalSpace::open(inner, &path).unwrap();
+        space.set_clock(Arc::new(clock.clone()));
+        space.add_with_ttl(expr!("A"), Duration::from_secs(10)).unwrap();
+        space.add(expr!("B"));
+        clock.advance(Duration::from_secs(10));
+        space.add(expr!("C"));
+        assert_eq_no_order!(atoms(space.inner()), vec![expr!("B"), expr!("C")]);
+
+        let entries = read_journal(&path).unwrap();
+        assert_eq!(entries[0].expires_at, Some(SystemTime::UNIX_EPOCH + Duration::from_secs(10)));
+        assert_eq!(entries[0].event, SpaceEvent::Add(expr!("A")));
+        let mut past = GroundingSpace::new();
+        replay_journal(&entries, &mut past, 2);
+        assert_eq_no_order!(atoms(&past), vec![expr!("A"), expr!("B")]);
+        let mut current = GroundingSpace::new();
+        replay_journal(&entries, &mut current, 3);
+        assert_eq_no_order!(atoms(&current), vec![expr!("B"), expr!("C")]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn journal_space_rejects_foreign_file() {
+        let path = temp_path("foreign");
+        std::fs::write(&path, b"text").unwrap();
+        assert_eq!(JournalSpace::open(GroundingSpace::new(), &path).err(), Some(format!(
+            "Cannot open journal {}: {}", path.display(), NOT_A_JOURNAL)));
+        assert_eq!(std::fs::read(&path).unwrap(), b"text");
+        assert!(read_journal(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn journal_space_rejects_unsupported_atom() {
+        let path = temp_path("unsupported");
+        let mut space = JournalSpace::open(GroundingSpace::new(), &path).unwrap();
+        let atom = expr!("A" {crate::metta::runner::stdlib::arithmetics::SumOp{}});
+        assert_eq!(space.try_add(atom), Err("Grounded atom + cannot be encoded".into()));
+        assert!(atoms(space.inner()).is_empty());
+        assert!(read_journal(&path).unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -3,3 +3,10 @@
 
 pub mod grounding;
 pub mod module;
+pub mod codec;
+pub mod dump;
+pub mod persistent;
+pub mod sync;
+pub mod clock;
+pub mod journal;
+pub mod remote;
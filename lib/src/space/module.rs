@@ -2,7 +2,8 @@ use std::fmt::{Debug, Display};
 
 use hyperon_atom::{matcher::BindingsSet, Atom};
 use hyperon_common::FlexRef;
-use hyperon_space::{complex_query, DynSpace, Space, SpaceCommon, SpaceMut, SpaceVisitor};
+use hyperon_space::{complex_query_with_statistics, DynSpace, Space, SpaceCommon, SpaceMut, SpaceStatistics, SpaceVisitor};
+use hyperon_space::index::MemoryStats;
 
 pub struct ModuleSpace {
     main: DynSpace,
@@ -31,7 +32,7 @@ impl ModuleSpace {
     }
 
     pub fn query(&self, query: &Atom) -> BindingsSet {
-        complex_query(query, |query| self.single_query(query))
+        complex_query_with_statistics(query, Some(self), |query| self.single_query(query))
     }
  
     fn single_query(&self, query: &Atom) -> BindingsSet {
@@ -41,7 +42,7 @@ impl ModuleSpace {
             if let Some(space) = dep.borrow().as_any().downcast_ref::<Self>()  {
                 results.extend(space.query_no_deps(query));
             } else {
-                panic!("Only ModuleSpace is expected inside dependencies collection");
+                results.extend(dep.borrow().query(query));
             }
         }
         results
@@ -61,6 +62,28 @@ impl ModuleSpace {
     }
 }
 
+/// Sums estimations of the main space and dependencies. When one of the
+/// spaces has no statistics the number of matches is unknown and `limit`
+/// is returned.
+impl SpaceStatistics for ModuleSpace {
+    fn estimate_matches(&self, pattern: &Atom, limit: usize) -> usize {
+        let estimate = |space: &DynSpace, limit: usize| {
+            space.borrow().statistics().map_or(limit, |stat| stat.estimate_matches(pattern, limit))
+        };
+        let mut count = estimate(&self.main, limit);
+        for dep in &self.deps {
+            if count >= limit {
+                break;
+            }
+            count += match dep.borrow().as_any().downcast_ref::<Self>() {
+                Some(space) => estimate(&space.main, limit - count),
+                None => estimate(dep, limit - count),
+            };
+        }
+        count.min(limit)
+    }
+}
+
 impl Space for ModuleSpace {
     fn common(&self) -> FlexRef<'_, SpaceCommon> {
         self.main.common()
@@ -74,6 +97,12 @@ impl Space for ModuleSpace {
     fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
         self.main.borrow().visit(v)
     }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        Some(self)
+    }
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        self.main.borrow().memory_stats()
+    }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -89,6 +118,12 @@ impl SpaceMut for ModuleSpace {
     fn replace(&mut self, from: &Atom, to: Atom) -> bool {
         self.main.borrow_mut().replace(from, to)
     }
+    fn compact_memory(&mut self) {
+        self.main.borrow_mut().compact_memory()
+    }
+    fn add_with_ttl(&mut self, atom: Atom, ttl: std::time::Duration) -> Result<(), String> {
+        self.main.borrow_mut().add_with_ttl(atom, ttl)
+    }
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -115,5 +150,22 @@ mod test {
         assert_eq_no_order!(main.query(&expr!("," (a "b") ("b" c))), vec![bind!{ a: sym!("a"), c: sym!("c") }]);
         assert_eq_no_order!(main.query(&expr!("," ("a" b) (b "c"))), vec![bind!{ b: sym!("b") }]);
     }
+
+    #[test]
+    fn module_space_statistics() {
+        let mut a = GroundingSpace::new();
+        a.add(expr!("a" "b"));
+        a.add(expr!("a" "c"));
+        let mut b = GroundingSpace::new();
+        b.add(expr!("a" "d"));
+
+        let mut main = ModuleSpace::new(GroundingSpace::new().into());
+        main.add_dep(ModuleSpace::new(a.into()).into());
+        main.add_dep(ModuleSpace::new(b.into()).into());
+
+        assert_eq!(main.estimate_matches(&expr!("a" x), usize::MAX), 3);
+        assert_eq!(main.estimate_matches(&expr!("a" x), 2), 2);
+        assert_eq!(main.estimate_matches(&expr!("a" "d"), usize::MAX), 1);
+    }
 }
 
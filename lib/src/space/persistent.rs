@@ -0,0 +1,526 @@
+//! Atomspace which keeps atoms on disk. Space modifications are appended to
+//! the log file and atoms are indexed in memory. When space is reopened the
+//! log is replayed decoding atoms from the binary representation (see
+//! [codec](super::codec)) thus no MeTTa parsing is required.
+//!
+//! Each log record has the following format: length of the payload (4 bytes
+//! little endian), checksum of the payload (8 bytes little endian FNV-1a
+//! hash), payload. Payload starts from the operation code which is followed
+//! by the encoded atoms. Record is written by a single write call, partially
+//! written record at the end of the log is detected by the length and
+//! checksum and it is truncated when space is opened. Thus the log is kept
+//! consistent after a crash. Corrupted record in the middle of the log is
+//! reported as an error. Log is read by a buffered reader when space is
+//! opened thus it is not loaded into memory as a whole. Log can be compacted using
+//! [PersistentSpace::compact] which writes the current atoms into a new file
+//! and atomically replaces the log by it.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::BindingsSet;
+use hyperon_common::FlexRef;
+use hyperon_space::{Space, SpaceCommon, SpaceMut, SpaceStatistics, SpaceVisitor};
+use hyperon_space::aggregate::Aggregate;
+use hyperon_space::index::MemoryStats;
+
+use super::grounding::GroundingSpace;
+use super::codec::{encode_atom, decode_atom};
+
+use std::fmt::{Debug, Display};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"MeTTaLog";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 12;
+const RECORD_HEADER_LEN: usize = 12;
+
+const OP_ADD: u8 = 0;
+const OP_REMOVE: u8 = 1;
+const OP_REPLACE: u8 = 2;
+
+/// Space which keeps atoms in a log file on disk and indexes them in memory.
+/// Query semantics is the same as the one of the [GroundingSpace].
+/// Only atoms which can be encoded by the [codec](super::codec) can be
+/// added into the space.
+pub struct PersistentSpace {
+    space: GroundingSpace,
+    path: PathBuf,
+    log: File,
+    sync_on_write: bool,
+}
+
+impl PersistentSpace {
+    /// Opens space stored in the file at `path` or creates new empty space
+    /// if file doesn't exist or it is empty. Returns an error if file is not
+    /// a space log. Partially written record at the end of the file is
+    /// removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon_atom::*;
+    /// use hyperon_space::Space;
+    /// use hyperon::space::persistent::PersistentSpace;
+    ///
+    /// let path = std::env::temp_dir().join("persistent-space-doc-example.log");
+    /// # let _ = std::fs::remove_file(&path);
+    /// {
+    ///     let mut space = PersistentSpace::open(&path).unwrap();
+    ///     space.try_add(expr!("A" "B")).unwrap();
+    /// }
+    /// let space = PersistentSpace::open(&path).unwrap();
+    ///
+    /// assert_eq!(space.query(&expr!("A" x)), bind_set![{x: sym!("B")}]);
+    /// # let _ = std::fs::remove_file(&path);
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let io_error = |e: std::io::Error| format!("Cannot open space {}: {}", path.display(), e);
+        let mut log = open_log(&path, &header(), "file is not a space log or log version is not supported")
+            .map_err(io_error)?;
+
+        let mut space = GroundingSpace::new();
+        space.set_name(path.display().to_string());
+        let valid_len = read_frames(&mut log, HEADER_LEN as u64, |pos, payload| {
+            apply_record(&mut space, payload)
+                .map_err(|e| format!("record at {} is corrupted: {}", pos, e))
+        }).map_err(|e| format!("Cannot open space {}: {}", path.display(), e))?;
+        if truncate_log(&log, valid_len).map_err(io_error)? {
+            log::warn!("PersistentSpace::open: {}: truncate partially written record at {}", path.display(), valid_len);
+        }
+        drop(log);
+        let log = OpenOptions::new().append(true).open(&path).map_err(io_error)?;
+        Ok(Self{ space, path, log, sync_on_write: true })
+    }
+
+    /// Returns path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets whether each write is synchronized with disk. It is `true` by
+    /// default. Switching synchronization off speeds up bulk loading but
+    /// records which are not synchronized can be lost after system crash.
+    /// Use [PersistentSpace::sync] to synchronize them explicitly.
+    pub fn set_sync_on_write(&mut self, sync: bool) {
+        self.sync_on_write = sync;
+    }
+
+    /// Returns in-memory fork of the space, see [GroundingSpace::fork].
+    /// Changes of the fork are not written to the file.
+    pub fn fork(&self) -> GroundingSpace {
+        self.space.fork()
+    }
+
+    /// Synchronizes the log file with disk.
+    pub fn sync(&self) -> Result<(), String> {
+        self.log.sync_data().map_err(|e| e.to_string())
+    }
+
+    /// Adds `atom` into space. Returns an error if atom cannot be encoded or
+    /// written, in this case space is not changed.
+    pub fn try_add(&mut self, atom: Atom) -> Result<(), String> {
+        self.write(OP_ADD, &[&atom])?;
+        self.space.add(atom);
+        Ok(())
+    }
+
+    /// Removes `atom` from space. Returns `Ok(true)` if atom was found and
+    /// removed. Operation is written into the log before the space is
+    /// changed, replaying it is a no-op when atom is not found.
+    pub fn try_remove(&mut self, atom: &Atom) -> Result<bool, String> {
+        self.write(OP_REMOVE, &[atom])?;
+        Ok(self.space.remove(atom))
+    }
+
+    /// Replaces `from` atom by `to` atom. Returns `Ok(true)` if `from` was
+    /// found and replaced.
+    pub fn try_replace(&mut self, from: &Atom, to: Atom) -> Result<bool, String> {
+        self.write(OP_REPLACE, &[from, &to])?;
+        Ok(self.space.replace(from, to))
+    }
+
+    /// Rewrites the log keeping only current atoms of the space. New log is
+    /// written into a temporary file which then replaces the log, so the
+    /// space is not corrupted if compaction is interrupted.
+    pub fn compact(&mut self) -> Result<(), String> {
+        let io_error = |e: std::io::Error| format!("Cannot compact space {}: {}", self.path.display(), e);
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut data = header().to_vec();
+        let mut error = None;
+        self.space.visit(&mut |atom: std::borrow::Cow<Atom>| {
+            if error.is_none() {
+                error = append_record(&mut data, OP_ADD, &[atom.as_ref()]).err();
+            }
+        }).map_err(|()| "Cannot visit space atoms".to_string())?;
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let mut tmp = File::create(&tmp_path).map_err(io_error)?;
+        tmp.write_all(&data).and_then(|()| tmp.sync_all()).map_err(io_error)?;
+        drop(tmp);
+        std::fs::rename(&tmp_path, &self.path).map_err(io_error)?;
+        self.log = OpenOptions::new().append(true).open(&self.path).map_err(io_error)?;
+        Ok(())
+    }
+
+    fn write(&mut self, op: u8, atoms: &[&Atom]) -> Result<(), String> {
+        let mut record = Vec::new();
+        append_record(&mut record, op, atoms)?;
+        append_log(&mut self.log, &record).map_err(|e| e.to_string())?;
+        if self.sync_on_write {
+            self.sync()?;
+        }
+        Ok(())
+    }
+}
+
+fn header() -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..].copy_from_slice(&VERSION.to_le_bytes());
+    header
+}
+
+fn append_record(buf: &mut Vec<u8>, op: u8, atoms: &[&Atom]) -> Result<(), String> {
+    append_frame(buf, |buf| {
+        buf.push(op);
+        atoms.iter().try_for_each(|atom| encode_atom(atom, buf))
+    })
+}
+
+/// Appends record which payload is written by `write` to the `buf`. Record
+/// is framed by the length and checksum of the payload. Nothing is appended
+/// when `write` returns an error.
+pub(super) fn append_frame<F>(buf: &mut Vec<u8>, write: F) -> Result<(), String>
+    where F: FnOnce(&mut Vec<u8>) -> Result<(), String>
+{
+    let start = buf.len();
+    buf.extend([0; RECORD_HEADER_LEN]);
+    let len = write(buf).and_then(|()| u32::try_from(buf.len() - start - RECORD_HEADER_LEN)
+        .map_err(|_| "Atom is too big to be written".to_string()));
+    let len = match len {
+        Ok(len) => len,
+        Err(e) => {
+            buf.truncate(start);
+            return Err(e);
+        },
+    };
+    let checksum = fnv1a(&buf[start + RECORD_HEADER_LEN..]);
+    buf[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    buf[start + 4..start + RECORD_HEADER_LEN].copy_from_slice(&checksum.to_le_bytes());
+    Ok(())
+}
+
+/// Opens log file at `path` for reading and writing or creates it. The
+/// `header` is written into the new or empty file. Returns file positioned
+/// after the header or [ErrorKind::InvalidData] error with `not_a_log`
+/// message if the file starts from the other header.
+pub(super) fn open_log(path: &Path, header: &[u8], not_a_log: &str) -> std::io::Result<File> {
+    let mut log = OpenOptions::new().read(true).write(true).create(true).open(path)?;
+    let mut actual = Vec::new();
+    (&mut log).take(header.len() as u64).read_to_end(&mut actual)?;
+    if actual.len() < header.len() && header.starts_with(&actual) {
+        // New file or header was not written completely
+        log.set_len(0)?;
+        log.seek(SeekFrom::Start(0))?;
+        log.write_all(header)?;
+        log.sync_data()?;
+    } else if actual != header {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, not_a_log));
+    }
+    Ok(log)
+}
+
+/// Removes the part of the `log` after `valid_len` bytes which is left by the
+/// partially written record. Returns `true` if log is truncated.
+pub(super) fn truncate_log(log: &File, valid_len: u64) -> std::io::Result<bool> {
+    if valid_len >= log.metadata()?.len() {
+        return Ok(false);
+    }
+    log.set_len(valid_len)?;
+    log.sync_data()?;
+    Ok(true)
+}
+
+/// Appends `record` to the end of the `file`. If record cannot be written
+/// the file is truncated back to its length before writing, thus partially
+/// written record doesn't precede the records appended later.
+pub(super) fn append_log(file: &mut File, record: &[u8]) -> std::io::Result<()> {
+    let len = file.metadata()?.len();
+    match file.write_all(record) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            if let Err(truncate) = file.set_len(len) {
+                log::error!("append_log: cannot truncate partially written record at {}: {}", len, truncate);
+            }
+            Err(e)
+        },
+    }
+}
+
+/// Reads records from the `reader` and passes their positions and payloads
+/// to `apply`. `start` is the position of the first record. Returns length
+/// of the correctly written part of the log. Partially written record at
+/// the end of the log is ignored while corrupted record followed by other
+/// data is reported as an error.
+pub(super) fn read_frames<R, F>(reader: R, start: u64, mut apply: F) -> Result<u64, String>
+    where R: Read, F: FnMut(u64, &[u8]) -> Result<(), String>
+{
+    let mut reader = BufReader::new(reader);
+    let mut pos = start;
+    let mut payload = Vec::new();
+    loop {
+        let mut header = [0; RECORD_HEADER_LEN];
+        if read_up_to(&mut reader, &mut header)? < RECORD_HEADER_LEN {
+            return Ok(pos);
+        }
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+        let checksum = u64::from_le_bytes(header[4..].try_into().unwrap());
+        payload.clear();
+        (&mut reader).take(len).read_to_end(&mut payload).map_err(|e| e.to_string())?;
+        if (payload.len() as u64) < len {
+            return Ok(pos);
+        }
+        if fnv1a(&payload) != checksum {
+            let is_last = reader.fill_buf().map_err(|e| e.to_string())?.is_empty();
+            if is_last {
+                return Ok(pos);
+            }
+            return Err(format!("record at {} is corrupted: checksum mismatch", pos));
+        }
+        apply(pos, &payload)?;
+        pos += (RECORD_HEADER_LEN + payload.len()) as u64;
+    }
+}
+
+/// Reads bytes into `buf` until it is filled or the end of the data is
+/// reached. Returns number of bytes read.
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, String> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {},
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(read)
+}
+
+fn apply_record(space: &mut GroundingSpace, mut payload: &[u8]) -> Result<(), String> {
+    let (&op, rest) = payload.split_first().ok_or("empty record")?;
+    payload = rest;
+    match op {
+        OP_ADD => space.add(decode_atom(&mut payload)?),
+        OP_REMOVE => { space.remove(&decode_atom(&mut payload)?); },
+        OP_REPLACE => {
+            let from = decode_atom(&mut payload)?;
+            let to = decode_atom(&mut payload)?;
+            space.replace(&from, to);
+        },
+        op => return Err(format!("unexpected operation code: {}", op)),
+    }
+    Ok(())
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+impl Space for PersistentSpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        self.space.common()
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        self.space.query(query)
+    }
+    fn atom_count(&self) -> Option<usize> {
+        self.space.atom_count()
+    }
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.space.visit(v)
+    }
+    fn aggregate(&self, query: &Atom, aggregate: &Aggregate) -> Result<Option<Atom>, String> {
+        self.space.aggregate(query, aggregate)
+    }
+    fn statistics(&self) -> Option<&dyn SpaceStatistics> {
+        self.space.statistics()
+    }
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        self.space.memory_stats()
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for PersistentSpace {
+    fn add(&mut self, atom: Atom) {
+        if let Err(e) = self.try_add(atom) {
+            log::error!("PersistentSpace::add: {}: {}", self, e);
+        }
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        self.try_remove(atom).unwrap_or_else(|e| {
+            log::error!("PersistentSpace::remove: {}: {}", self, e);
+            false
+        })
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.try_replace(from, to).unwrap_or_else(|e| {
+            log::error!("PersistentSpace::replace: {}: {}", self, e);
+            false
+        })
+    }
+    fn compact_memory(&mut self) {
+        self.space.compact_memory()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Debug for PersistentSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PersistentSpace-{} ({self:p})", self.path.display())
+    }
+}
+
+impl Display for PersistentSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PersistentSpace-{}", self.path.display())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hyperon_atom::gnd::number::Number;
+    use hyperon_common::assert_eq_no_order;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hyperon-persistent-space-{}-{}.log", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn atoms(space: &PersistentSpace) -> Vec<Atom> {
+        let mut atoms = Vec::new();
+        space.visit(&mut |atom: std::borrow::Cow<Atom>| atoms.push(atom.into_owned())).unwrap();
+        atoms
+    }
+
+    #[test]
+    fn persistent_space_reopen() {
+        let path = temp_path("reopen");
+        {
+            let mut space = PersistentSpace::open(&path).unwrap();
+            space.try_add(expr!("A" {Number::Integer(1)})).unwrap();
+            space.try_add(expr!("B" x)).unwrap();
+            space.try_add(expr!("C")).unwrap();
+            assert_eq!(space.try_remove(&expr!("C")), Ok(true));
+            assert_eq!(space.try_remove(&expr!("D")), Ok(false));
+            assert_eq!(space.try_replace(&expr!("B" x), expr!("B" "b")), Ok(true));
+        }
+        let space = PersistentSpace::open(&path).unwrap();
+        assert_eq_no_order!(atoms(&space), vec![expr!("A" {Number::Integer(1)}), expr!("B" "b")]);
+        assert_eq!(space.query(&expr!("A" y)), bind_set![{y: expr!({Number::Integer(1)})}]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_truncates_partial_record() {
+        let path = temp_path("partial");
+        {
+            let mut space = PersistentSpace::open(&path).unwrap();
+            space.try_add(expr!("A")).unwrap();
+            space.try_add(expr!("B" "C")).unwrap();
+        }
+        let len = std::fs::metadata(&path).unwrap().len();
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 2).unwrap();
+        {
+            let mut space = PersistentSpace::open(&path).unwrap();
+            assert_eq!(atoms(&space), vec![expr!("A")]);
+            space.try_add(expr!("D")).unwrap();
+        }
+        let space = PersistentSpace::open(&path).unwrap();
+        assert_eq_no_order!(atoms(&space), vec![expr!("A"), expr!("D")]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_initializes_empty_file_only() {
+        let path = temp_path("not-a-log");
+        std::fs::write(&path, b"").unwrap();
+        assert!(PersistentSpace::open(&path).is_ok());
+        assert_eq!(std::fs::read(&path).unwrap(), header());
+
+        std::fs::write(&path, b"text").unwrap();
+        assert_eq!(PersistentSpace::open(&path).err(), Some(format!(
+            "Cannot open space {}: file is not a space log or log version is not supported", path.display())));
+        assert_eq!(std::fs::read(&path).unwrap(), b"text");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_reports_corrupted_record() {
+        let path = temp_path("corrupted");
+        {
+            let mut space = PersistentSpace::open(&path).unwrap();
+            space.try_add(expr!("A")).unwrap();
+            space.try_add(expr!("B")).unwrap();
+        }
+        let mut data = std::fs::read(&path).unwrap();
+        data[HEADER_LEN + RECORD_HEADER_LEN + 1] ^= 0xff;
+        std::fs::write(&path, &data).unwrap();
+        assert_eq!(PersistentSpace::open(&path).err(), Some(format!(
+            "Cannot open space {}: record at {} is corrupted: checksum mismatch", path.display(), HEADER_LEN)));
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_compact() {
+        let path = temp_path("compact");
+        {
+            let mut space = PersistentSpace::open(&path).unwrap();
+            for i in 0..10 {
+                space.try_add(expr!("A" {Number::Integer(i)})).unwrap();
+            }
+            for i in 0..9 {
+                space.try_remove(&expr!("A" {Number::Integer(i)})).unwrap();
+            }
+            let len = std::fs::metadata(&path).unwrap().len();
+            space.compact().unwrap();
+            assert!(std::fs::metadata(&path).unwrap().len() < len);
+            space.try_add(expr!("B")).unwrap();
+        }
+        let space = PersistentSpace::open(&path).unwrap();
+        assert_eq_no_order!(atoms(&space), vec![expr!("A" {Number::Integer(9)}), expr!("B")]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_rejects_unsupported_atom() {
+        let path = temp_path("unsupported");
+        let mut space = PersistentSpace::open(&path).unwrap();
+        let atom = expr!("A" {crate::metta::runner::stdlib::arithmetics::SumOp{}});
+        assert_eq!(space.try_add(atom), Err("Grounded atom + cannot be encoded".into()));
+        assert!(atoms(&space).is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn persistent_space_rejects_other_file() {
+        let path = temp_path("other");
+        std::fs::write(&path, "(some metta code)").unwrap();
+        assert!(PersistentSpace::open(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,592 @@
+//! Access to the space of another process via TCP or Unix socket.
+//! [SpaceServer] exposes a [DynSpace] on a socket and [RemoteSpace] is a
+//! client which implements [SpaceMut] by sending requests to the server.
+//! Space is addressed by URL: `tcp://<host>:<port>` or `unix://<path>`.
+//!
+//! Each message is framed by the length of its payload (4 bytes little
+//! endian). Request payload starts from the operation code which is followed
+//! by the atoms encoded using [codec](super::codec):
+//! - add: `0`, atom;
+//! - remove: `1`, atom;
+//! - replace: `2`, atom to replace, new atom;
+//! - query: `3`, query atom;
+//! - subst: `4`, pattern, template;
+//! - atom count: `5`.
+//!
+//! Response payload starts from status: `0` - success, `1` - error followed
+//! by the UTF-8 error message. Successful response contains: nothing for
+//! add, `0` or `1` for remove and replace, number of bindings and bindings
+//! for query where each bindings is a number of pairs followed by pairs of
+//! variable and its value, number of atoms and atoms for subst, `0` or `1`
+//! followed by the count for atom count. Numbers are LEB128 integers.
+//!
+//! [DynSpace] is not thread safe, thus server handles connections one by one
+//! on the thread which owns the space. Each connection carries a single
+//! request and is closed after the response is sent. Reading the request
+//! and writing the response time out, see [SpaceServer::set_timeout], thus
+//! a stalled client cannot block other clients. Client requests time out
+//! as well, thus a stalled server doesn't block the client forever.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::{Bindings, BindingsSet};
+use hyperon_common::FlexRef;
+use hyperon_space::{DynSpace, Space, SpaceCommon, SpaceEvent, SpaceMut, SpaceVisitor};
+
+use super::codec::{encode_atom, decode_atom, encode_len, decode_len};
+
+use std::fmt::{Debug, Display};
+use std::io::{Read, Write, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+
+const MAX_FRAME_LEN: usize = 64 << 20;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const OP_ADD: u8 = 0;
+const OP_REMOVE: u8 = 1;
+const OP_REPLACE: u8 = 2;
+const OP_QUERY: u8 = 3;
+const OP_SUBST: u8 = 4;
+const OP_ATOM_COUNT: u8 = 5;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+trait Stream: Read + Write {}
+
+impl<T: Read + Write> Stream for T {}
+
+/// Address of the space server parsed from the URL.
+#[derive(Clone, Debug, PartialEq)]
+enum Endpoint {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> Result<Self, String> {
+        if let Some(addr) = url.strip_prefix("tcp://") {
+            return Ok(Endpoint::Tcp(addr.into()));
+        }
+        if let Some(_path) = url.strip_prefix("unix://") {
+            #[cfg(unix)]
+            return Ok(Endpoint::Unix(_path.into()));
+            #[cfg(not(unix))]
+            return Err(format!("Unix sockets are not supported on this platform: {}", url));
+        }
+        Err(format!("Unsupported space URL {}, expected tcp://<host>:<port> or unix://<path>", url))
+    }
+
+    fn connect(&self) -> std::io::Result<Box<dyn Stream>> {
+        Ok(match self {
+            Endpoint::Tcp(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(DEFAULT_TIMEOUT))?;
+                stream.set_write_timeout(Some(DEFAULT_TIMEOUT))?;
+                Box::new(stream)
+            },
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(DEFAULT_TIMEOUT))?;
+                stream.set_write_timeout(Some(DEFAULT_TIMEOUT))?;
+                Box::new(stream)
+            },
+        })
+    }
+}
+
+fn write_frame(stream: &mut dyn Stream, payload: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(payload.len()).ok().filter(|len| *len as usize <= MAX_FRAME_LEN)
+        .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "message is too big"))?;
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend(len.to_le_bytes());
+    frame.extend(payload);
+    stream.write_all(&frame)?;
+    stream.flush()
+}
+
+/// Reads next message from the `stream`. Returns `Ok(None)` when stream is
+/// closed before the message. Buffer grows while the payload is received
+/// instead of being allocated using the length from the frame header.
+fn read_frame(stream: &mut dyn Stream) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {},
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, "message is too big"));
+    }
+    let mut payload = Vec::new();
+    (&mut *stream).take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() < len {
+        return Err(std::io::Error::new(ErrorKind::UnexpectedEof, "message is truncated"));
+    }
+    Ok(Some(payload))
+}
+
+fn decode_bool(buf: &mut &[u8]) -> Result<bool, String> {
+    let (&value, rest) = buf.split_first().ok_or("unexpected end of message")?;
+    *buf = rest;
+    Ok(value != 0)
+}
+
+/// Server which exposes a space on a TCP or Unix socket.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon_space::{DynSpace, Space, SpaceMut};
+/// use hyperon::space::grounding::GroundingSpace;
+/// use hyperon::space::remote::{SpaceServer, RemoteSpace};
+///
+/// let server = SpaceServer::bind("tcp://127.0.0.1:0").unwrap();
+/// let url = server.local_url().unwrap();
+/// let handle = std::thread::spawn(move || {
+///     let space = DynSpace::new(GroundingSpace::new());
+///     server.handle_connection(&space).unwrap();
+///     server.handle_connection(&space).unwrap();
+/// });
+///
+/// let mut remote = RemoteSpace::new(&url).unwrap();
+/// remote.add(expr!("A" "B"));
+/// assert_eq!(remote.query(&expr!("A" x)), bind_set![{x: sym!("B")}]);
+/// handle.join().unwrap();
+/// ```
+pub struct SpaceServer {
+    listener: Listener,
+    timeout: Duration,
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl SpaceServer {
+    /// Starts listening on the socket addressed by `url`. Port `0` can be
+    /// used to select a free port, see [SpaceServer::local_url].
+    pub fn bind(url: &str) -> Result<Self, String> {
+        let io_error = |e: std::io::Error| format!("Cannot listen on {}: {}", url, e);
+        let listener = match Endpoint::parse(url)? {
+            Endpoint::Tcp(addr) => Listener::Tcp(TcpListener::bind(addr).map_err(io_error)?),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => Listener::Unix(UnixListener::bind(&path).map_err(io_error)?, path),
+        };
+        Ok(Self{ listener, timeout: DEFAULT_TIMEOUT })
+    }
+
+    /// Sets timeout of reading the request and writing the response. Default
+    /// timeout is 10 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns URL of the socket the server is listening on.
+    pub fn local_url(&self) -> Result<String, String> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr()
+                .map(|addr| format!("tcp://{}", addr))
+                .map_err(|e| e.to_string()),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => Ok(format!("unix://{}", path.display())),
+        }
+    }
+
+    fn accept(&self) -> std::io::Result<Box<dyn Stream>> {
+        let timeout = Some(self.timeout);
+        Ok(match &self.listener {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)?;
+                Box::new(stream)
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)?;
+                Box::new(stream)
+            },
+        })
+    }
+
+    /// Accepts single connection, handles its request using `space` and
+    /// closes the connection.
+    pub fn handle_connection(&self, space: &DynSpace) -> Result<(), String> {
+        let mut stream = self.accept().map_err(|e| e.to_string())?;
+        let request = match read_frame(&mut *stream).map_err(|e| e.to_string())? {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        let mut response = Vec::new();
+        match handle_request(space, &request, &mut response) {
+            Ok(()) => response.insert(0, STATUS_OK),
+            Err(e) => {
+                log::debug!("SpaceServer::handle_connection: {}: {}", space, e);
+                response.clear();
+                response.push(STATUS_ERROR);
+                response.extend(e.into_bytes());
+            },
+        }
+        write_frame(&mut *stream, &response).map_err(|e| e.to_string())
+    }
+
+    /// Handles connections until an error occurs while accepting a
+    /// connection. Errors of the single connection are logged and don't stop
+    /// the server.
+    pub fn serve(&self, space: &DynSpace) -> Result<(), String> {
+        loop {
+            if let Err(e) = self.handle_connection(space) {
+                log::warn!("SpaceServer::serve: {}: {}", space, e);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SpaceServer {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn handle_request(space: &DynSpace, mut request: &[u8], response: &mut Vec<u8>) -> Result<(), String> {
+    let (&op, rest) = request.split_first().ok_or("empty request")?;
+    request = rest;
+    match op {
+        OP_ADD => space.borrow_mut().add(decode_atom(&mut request)?),
+        OP_REMOVE => {
+            let removed = space.borrow_mut().remove(&decode_atom(&mut request)?);
+            response.push(removed as u8);
+        },
+        OP_REPLACE => {
+            let from = decode_atom(&mut request)?;
+            let to = decode_atom(&mut request)?;
+            let replaced = space.borrow_mut().replace(&from, to);
+            response.push(replaced as u8);
+        },
+        OP_QUERY => {
+            let results = space.borrow().query(&decode_atom(&mut request)?);
+            encode_len(results.len(), response);
+            for bindings in results {
+                let pairs: Vec<(&VariableAtom, Atom)> = bindings.iter()
+                    .filter(|(var, value)| *value != Atom::Variable((*var).clone()))
+                    .collect();
+                encode_len(pairs.len(), response);
+                for (var, value) in pairs {
+                    encode_atom(&Atom::Variable(var.clone()), response)?;
+                    encode_atom(&value, response)?;
+                }
+            }
+        },
+        OP_SUBST => {
+            let pattern = decode_atom(&mut request)?;
+            let template = decode_atom(&mut request)?;
+            let results = space.borrow().subst(&pattern, &template);
+            encode_len(results.len(), response);
+            results.iter().try_for_each(|atom| encode_atom(atom, response))?;
+        },
+        OP_ATOM_COUNT => match space.borrow().atom_count() {
+            Some(count) => {
+                response.push(1);
+                encode_len(count, response);
+            },
+            None => response.push(0),
+        },
+        op => return Err(format!("Unexpected operation code: {}", op)),
+    }
+    Ok(())
+}
+
+/// Client of the space exposed by [SpaceServer]. Only atoms which can be
+/// encoded by the [codec](super::codec) can be sent to the remote space.
+/// Observers of the space are notified only about the changes made via this
+/// instance.
+pub struct RemoteSpace {
+    url: String,
+    endpoint: Endpoint,
+    common: SpaceCommon,
+}
+
+impl RemoteSpace {
+    /// Constructs client of the space addressed by `url`. Connection is not
+    /// checked, use [RemoteSpace::connect] to check it.
+    pub fn new(url: &str) -> Result<Self, String> {
+        let endpoint = Endpoint::parse(url)?;
+        Ok(Self{ url: url.into(), endpoint, common: SpaceCommon::default() })
+    }
+
+    /// Constructs client of the space addressed by `url` and checks the
+    /// server responds.
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let space = Self::new(url)?;
+        space.try_atom_count()?;
+        Ok(space)
+    }
+
+    /// Returns URL of the remote space.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Adds `atom` into remote space.
+    pub fn try_add(&mut self, atom: Atom) -> Result<(), String> {
+        self.request(OP_ADD, &[&atom])?;
+        self.common.notify_all_observers(&SpaceEvent::Add(atom));
+        Ok(())
+    }
+
+    /// Removes `atom` from remote space. Returns `Ok(true)` if atom was found
+    /// and removed.
+    pub fn try_remove(&mut self, atom: &Atom) -> Result<bool, String> {
+        let removed = decode_bool(&mut &self.request(OP_REMOVE, &[atom])?[..])?;
+        if removed {
+            self.common.notify_all_observers(&SpaceEvent::Remove(atom.clone()));
+        }
+        Ok(removed)
+    }
+
+    /// Replaces `from` atom by `to` atom in remote space. Returns `Ok(true)`
+    /// if `from` was found and replaced.
+    pub fn try_replace(&mut self, from: &Atom, to: Atom) -> Result<bool, String> {
+        let replaced = decode_bool(&mut &self.request(OP_REPLACE, &[from, &to])?[..])?;
+        if replaced {
+            self.common.notify_all_observers(&SpaceEvent::Replace(from.clone(), to));
+        }
+        Ok(replaced)
+    }
+
+    /// Queries remote space, see [Space::query].
+    pub fn try_query(&self, query: &Atom) -> Result<BindingsSet, String> {
+        let response = self.request(OP_QUERY, &[query])?;
+        let mut buf = &response[..];
+        let mut results = BindingsSet::empty();
+        for _ in 0..decode_len(&mut buf)? {
+            let mut bindings = Bindings::new();
+            for _ in 0..decode_len(&mut buf)? {
+                let var = match decode_atom(&mut buf)? {
+                    Atom::Variable(var) => var,
+                    atom => return Err(format!("Variable expected in query result: {}", atom)),
+                };
+                bindings = match decode_atom(&mut buf)? {
+                    Atom::Variable(other) => bindings.add_var_equality(&var, &other),
+                    value => bindings.add_var_binding(var, value),
+                }.map_err(|e| format!("Incorrect query result: {}", e))?;
+            }
+            results.push(bindings);
+        }
+        Ok(results)
+    }
+
+    /// Substitutes results of the `pattern` query into `template` on the
+    /// server side, see [Space::subst].
+    pub fn try_subst(&self, pattern: &Atom, template: &Atom) -> Result<Vec<Atom>, String> {
+        let response = self.request(OP_SUBST, &[pattern, template])?;
+        let mut buf = &response[..];
+        (0..decode_len(&mut buf)?).map(|_| decode_atom(&mut buf)).collect()
+    }
+
+    /// Returns number of atoms in remote space if it is known.
+    pub fn try_atom_count(&self) -> Result<Option<usize>, String> {
+        let response = self.request(OP_ATOM_COUNT, &[])?;
+        let mut buf = &response[..];
+        match decode_bool(&mut buf)? {
+            true => decode_len(&mut buf).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    fn request(&self, op: u8, atoms: &[&Atom]) -> Result<Vec<u8>, String> {
+        let mut request = vec![op];
+        atoms.iter().try_for_each(|atom| encode_atom(atom, &mut request))?;
+        let io_error = |e: std::io::Error| format!("Cannot send request to {}: {}", self.url, e);
+        let mut stream = self.endpoint.connect().map_err(io_error)?;
+        write_frame(&mut *stream, &request).map_err(io_error)?;
+        let response = read_frame(&mut *stream).map_err(io_error)?
+            .ok_or_else(|| format!("Connection to {} is closed", self.url))?;
+        match response.split_first() {
+            Some((&STATUS_OK, body)) => Ok(body.to_vec()),
+            Some((&STATUS_ERROR, message)) => Err(String::from_utf8_lossy(message).into_owned()),
+            _ => Err(format!("Unexpected response from {}", self.url)),
+        }
+    }
+}
+
+impl Space for RemoteSpace {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        FlexRef::from_simple(&self.common)
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        self.try_query(query).unwrap_or_else(|e| {
+            log::error!("RemoteSpace::query: {}: {}", self, e);
+            BindingsSet::empty()
+        })
+    }
+    fn subst(&self, pattern: &Atom, template: &Atom) -> Vec<Atom> {
+        self.try_subst(pattern, template).unwrap_or_else(|e| {
+            log::error!("RemoteSpace::subst: {}: {}", self, e);
+            Vec::new()
+        })
+    }
+    fn atom_count(&self) -> Option<usize> {
+        self.try_atom_count().unwrap_or_else(|e| {
+            log::error!("RemoteSpace::atom_count: {}: {}", self, e);
+            None
+        })
+    }
+    fn visit(&self, _v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        Err(())
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for RemoteSpace {
+    fn add(&mut self, atom: Atom) {
+        if let Err(e) = self.try_add(atom) {
+            log::error!("RemoteSpace::add: {}: {}", self, e);
+        }
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        self.try_remove(atom).unwrap_or_else(|e| {
+            log::error!("RemoteSpace::remove: {}: {}", self, e);
+            false
+        })
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        self.try_replace(from, to).unwrap_or_else(|e| {
+            log::error!("RemoteSpace::replace: {}: {}", self, e);
+            false
+        })
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Debug for RemoteSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RemoteSpace-{} ({self:p})", self.url)
+    }
+}
+
+impl Display for RemoteSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RemoteSpace-{}", self.url)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::space::grounding::GroundingSpace;
+    use hyperon_atom::gnd::number::Number;
+    use hyperon_common::assert_eq_no_order;
+
+    /// Starts server of a new space which handles `connections` connections
+    /// and returns atoms of the space.
+    fn start_server(url: &str, connections: usize) -> (String, std::thread::JoinHandle<Vec<Atom>>) {
+        let server = SpaceServer::bind(url).unwrap();
+        let url = server.local_url().unwrap();
+        let handle = std::thread::spawn(move || {
+            let space = DynSpace::new(GroundingSpace::new());
+            for _ in 0..connections {
+                server.handle_connection(&space).unwrap();
+            }
+            let mut atoms = Vec::new();
+            space.borrow().visit(&mut |atom: std::borrow::Cow<Atom>| atoms.push(atom.into_owned())).unwrap();
+            atoms
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn read_frame_truncated() {
+        let mut frame = (MAX_FRAME_LEN as u32).to_le_bytes().to_vec();
+        frame.extend([OP_ATOM_COUNT]);
+        let mut stream = std::io::Cursor::new(frame);
+        let error = read_frame(&mut stream).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(read_frame(&mut std::io::Cursor::new(Vec::new())).unwrap(), None);
+    }
+
+    #[test]
+    fn remote_space_tcp() {
+        let (url, server) = start_server("tcp://127.0.0.1:0", 9);
+        let mut space = RemoteSpace::connect(&url).unwrap();
+
+        space.add(expr!("age" "Bob" {Number::Integer(35)}));
+        space.add(expr!("age" "Ann" {Number::Integer(30)}));
+        space.add(expr!("C"));
+        assert!(space.replace(&expr!("C"), expr!("D" x)));
+        assert!(!space.remove(&expr!("E")));
+        assert_eq!(space.query(&expr!("age" x y)), bind_set![
+            {x: sym!("Bob"), y: expr!({Number::Integer(35)})},
+            {x: sym!("Ann"), y: expr!({Number::Integer(30)})},
+        ]);
+        assert_eq!(space.subst(&expr!("age" x {Number::Integer(30)}), &expr!("young" x)), vec![expr!("young" "Ann")]);
+        assert_eq!(space.atom_count(), Some(3));
+
+        assert_eq_no_order!(server.join().unwrap(), vec![expr!("age" "Bob" {Number::Integer(35)}),
+            expr!("age" "Ann" {Number::Integer(30)}), expr!("D" x)]);
+    }
+
+    #[test]
+    fn remote_space_reports_errors() {
+        let (url, server) = start_server("tcp://127.0.0.1:0", 1);
+        let mut space = RemoteSpace::new(&url).unwrap();
+
+        let atom = expr!("A" {crate::metta::runner::stdlib::arithmetics::SumOp{}});
+        assert_eq!(space.try_add(atom), Err("Grounded atom + cannot be encoded".into()));
+        assert_eq!(space.try_atom_count(), Ok(Some(0)));
+        assert!(server.join().unwrap().is_empty());
+        assert!(RemoteSpace::new("http://localhost").is_err());
+    }
+
+    #[test]
+    fn space_server_drops_stalled_connection() {
+        let mut server = SpaceServer::bind("tcp://127.0.0.1:0").unwrap();
+        server.set_timeout(Duration::from_millis(100));
+        let url = server.local_url().unwrap();
+        let handle = std::thread::spawn(move || {
+            let space = DynSpace::new(GroundingSpace::new());
+            let stalled = server.handle_connection(&space);
+            server.handle_connection(&space).unwrap();
+            stalled
+        });
+
+        let mut stalled = TcpStream::connect(url.strip_prefix("tcp://").unwrap()).unwrap();
+        stalled.write_all(&[1, 0]).unwrap();
+        assert_eq!(RemoteSpace::new(&url).unwrap().try_atom_count(), Ok(Some(0)));
+        assert!(handle.join().unwrap().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_space_unix() {
+        let path = std::env::temp_dir().join(format!("hyperon-remote-space-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (url, server) = start_server(&format!("unix://{}", path.display()), 2);
+        let mut space = RemoteSpace::new(&url).unwrap();
+
+        space.add(expr!("A" "B"));
+        assert_eq!(space.query(&expr!("A" x)), bind_set![{x: sym!("B")}]);
+        assert_eq!(server.join().unwrap(), vec![expr!("A" "B")]);
+    }
+}
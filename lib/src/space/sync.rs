@@ -0,0 +1,321 @@
+//! Atomspace which can be shared between threads. [SyncSpace] keeps atoms
+//! under [RwLock] thus many threads can query the space concurrently while
+//! writers are serialized. Grounded atoms in general are neither [Send] nor
+//! [Sync], thus the space accepts only atoms which grounded sub-atoms are
+//! numbers, booleans or strings.
+//!
+//! MeTTa runner itself cannot be shared between threads because atoms it
+//! works with can contain arbitrary grounded values. Each thread should use
+//! its own [Metta](crate::metta::runner::Metta) instance and access the
+//! shared atoms via [SyncSpaceRef] which implements [SpaceMut] and can be
+//! wrapped into [DynSpace]. [SyncMetta](crate::metta::runner::sync::SyncMetta)
+//! keeps such instances in a pool of worker threads and can be shared
+//! between threads.
+
+use hyperon_atom::*;
+use hyperon_atom::matcher::BindingsSet;
+use hyperon_atom::gnd::number::Number;
+use hyperon_atom::gnd::bool::Bool;
+use hyperon_atom::gnd::str::Str;
+use hyperon_common::FlexRef;
+use hyperon_space::*;
+
+use super::grounding::GroundingSpace;
+use super::clock::Clock;
+
+use std::fmt::{Debug, Display};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Thread-safe atomspace. Cloned instance refers to the same atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon_atom::*;
+/// use hyperon::space::sync::SyncSpace;
+///
+/// let space = SyncSpace::new();
+/// let writer = {
+///     let space = space.clone();
+///     std::thread::spawn(move || space.add(expr!("A" "B")).unwrap())
+/// };
+/// writer.join().unwrap();
+///
+/// assert_eq!(space.query(&expr!("A" x)).len(), 1);
+/// ```
+#[derive(Clone)]
+pub struct SyncSpace(Arc<RwLock<GroundingSpace>>);
+
+// SAFETY: GroundingSpace is neither Send nor Sync because it can contain
+// arbitrary grounded atoms and because SpaceCommon keeps observers in RefCell.
+// Fields of the inner GroundingSpace are sound to share for the following
+// reasons:
+// - index, deadlines and expiries are im collections which are built on Arc
+//   and contain atoms only; weights of the index are Number atoms. SyncSpace
+//   accepts only atoms which can be sent between threads (see is_sendable()):
+//   symbols, variables and strings are backed by &'static str or Arc, numbers,
+//   booleans and strings are owned values. None of them has interior
+//   mutability, thus they can be read from many threads under read lock;
+// - common is never exposed, thus no observers are registered and its
+//   RefCells are accessed only while the space is modified under write lock,
+//   snapshot() clones it into empty SpaceCommon;
+// - clock is Arc<dyn Clock> and Clock requires Send + Sync, see
+//   _assert_clock_is_thread_safe();
+// - name is a String.
+unsafe impl Send for SyncSpace {}
+unsafe impl Sync for SyncSpace {}
+
+#[allow(dead_code)]
+fn _assert_clock_is_thread_safe() {
+    fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+    assert_send_sync::<Arc<dyn Clock>>();
+}
+
+impl SyncSpace {
+    /// Constructs new empty space.
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(GroundingSpace::new())))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, GroundingSpace> {
+        self.0.read().expect("RwLock is poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, GroundingSpace> {
+        self.0.write().expect("RwLock is poisoned")
+    }
+
+    fn check_sendable(atom: &Atom) -> Result<(), String> {
+        if is_sendable(atom) {
+            Ok(())
+        } else {
+            Err(format!("Atom {} cannot be shared between threads", atom))
+        }
+    }
+
+    /// Adds `atom` into space. Returns an error if atom contains grounded
+    /// atoms which cannot be shared between threads.
+    pub fn add(&self, atom: Atom) -> Result<(), String> {
+        Self::check_sendable(&atom)?;
+        self.write().add(atom);
+        Ok(())
+    }
+
+    /// Removes `atom` from space. Returns true if atom was found and removed.
+    pub fn remove(&self, atom: &Atom) -> bool {
+        self.write().remove(atom)
+    }
+
+    /// Replaces `from` atom by `to` atom. Returns an error if `to` contains
+    /// grounded atoms which cannot be shared between threads.
+    pub fn replace(&self, from: &Atom, to: Atom) -> Result<bool, String> {
+        Self::check_sendable(&to)?;
+        Ok(self.write().replace(from, to))
+    }
+
+    /// Executes `query` on the space, see [GroundingSpace::query].
+    pub fn query(&self, query: &Atom) -> BindingsSet {
+        self.read().query(query)
+    }
+
+    /// Returns number of atoms in the space.
+    pub fn atom_count(&self) -> usize {
+        self.read().atom_count().unwrap_or_default()
+    }
+
+    /// Returns local copy of the space, see [GroundingSpace::fork]. Copy is
+    /// not synchronized with the space and cannot be sent to another thread.
+    pub fn snapshot(&self) -> GroundingSpace {
+        self.read().fork()
+    }
+
+    /// Returns reference to the space which can be used in a current thread
+    /// as an ordinary [SpaceMut] implementation.
+    pub fn handle(&self) -> SyncSpaceRef {
+        SyncSpaceRef{ space: self.clone(), common: SpaceCommon::default() }
+    }
+}
+
+impl Default for SyncSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for SyncSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SyncSpace-{:p}", Arc::as_ptr(&self.0))
+    }
+}
+
+impl Display for SyncSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+/// Returns `true` if `atom` can be sent between threads. Symbols, variables
+/// and expressions are thread-safe, grounded atoms are thread-safe when they
+/// are numbers, booleans or strings.
+pub fn is_sendable(atom: &Atom) -> bool {
+    atom.iter().all(|atom| match atom {
+        Atom::Grounded(_) => atom.as_gnd::<Number>().is_some()
+            || atom.as_gnd::<Bool>().is_some()
+            || atom.as_gnd::<Str>().is_some(),
+        _ => true,
+    })
+}
+
+/// Thread-local reference to the [SyncSpace]. Observers registered via
+/// [Space::common] are notified about modifications made through this
+/// reference only. Atoms which cannot be shared between threads are not
+/// added to the space, error is logged instead.
+#[derive(Clone)]
+pub struct SyncSpaceRef {
+    space: SyncSpace,
+    common: SpaceCommon,
+}
+
+impl SyncSpaceRef {
+    /// Returns shared space.
+    pub fn space(&self) -> &SyncSpace {
+        &self.space
+    }
+}
+
+impl Space for SyncSpaceRef {
+    fn common(&self) -> FlexRef<'_, SpaceCommon> {
+        FlexRef::from_simple(&self.common)
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        self.space.query(query)
+    }
+    fn atom_count(&self) -> Option<usize> {
+        Some(self.space.atom_count())
+    }
+    /// Visitor is called under the read lock, thus it should not modify the
+    /// space.
+    fn visit(&self, v: &mut dyn SpaceVisitor) -> Result<(), ()> {
+        self.space.read().visit(v)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl SpaceMut for SyncSpaceRef {
+    fn add(&mut self, atom: Atom) {
+        match self.space.add(atom.clone()) {
+            Ok(()) => self.common.notify_all_observers(&SpaceEvent::Add(atom)),
+            Err(e) => log::error!("SyncSpaceRef::add: {}: {}", self, e),
+        }
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        let is_removed = self.space.remove(atom);
+        if is_removed {
+            self.common.notify_all_observers(&SpaceEvent::Remove(atom.clone()));
+        }
+        is_removed
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        match self.space.replace(from, to.clone()) {
+            Ok(true) => {
+                self.common.notify_all_observers(&SpaceEvent::Replace(from.clone(), to));
+                true
+            },
+            Ok(false) => false,
+            Err(e) => {
+                log::error!("SyncSpaceRef::replace: {}: {}", self, e);
+                false
+            },
+        }
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl Debug for SyncSpaceRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.space, f)
+    }
+}
+
+impl Display for SyncSpaceRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.space, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metta::text::SExprParser;
+    use crate::metta::runner::Metta;
+    use crate::metta::runner::environment::EnvBuilder;
+    use crate::metta::runner::stdlib::arithmetics::SumOp;
+
+    #[test]
+    fn sync_space_concurrent_read_write() {
+        let space = SyncSpace::new();
+        space.add(expr!("value" {Number::Integer(0)})).unwrap();
+
+        let writer = {
+            let space = space.clone();
+            std::thread::spawn(move || {
+                for i in 1..100 {
+                    space.add(expr!("value" {Number::Integer(i)})).unwrap();
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4).map(|_| {
+            let space = space.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    assert!(!space.query(&expr!("value" x)).is_empty());
+                }
+            })
+        }).collect();
+        writer.join().unwrap();
+        readers.into_iter().for_each(|reader| reader.join().unwrap());
+
+        assert_eq!(space.atom_count(), 100);
+    }
+
+    #[test]
+    fn sync_space_rejects_non_sendable_atoms() {
+        let space = SyncSpace::new();
+        let atom = expr!("A" {SumOp{}});
+        assert_eq!(space.add(atom.clone()), Err("Atom (A +) cannot be shared between threads".into()));
+        assert_eq!(space.replace(&expr!("A"), atom), Err("Atom (A +) cannot be shared between threads".into()));
+        assert!(space.add(expr!("A" {Number::Float(1.5)} {Bool(true)} {Str::from_str("s")})).is_ok());
+        assert_eq!(space.atom_count(), 1);
+    }
+
+    #[test]
+    fn sync_space_ref_in_runner() {
+        let space = SyncSpace::new();
+        space.add(expr!("fact" "A")).unwrap();
+
+        let thread = {
+            let space = space.clone();
+            std::thread::spawn(move || {
+                let metta = Metta::new(Some(EnvBuilder::test_env()));
+                let shared = Atom::gnd(DynSpace::new(space.handle()));
+                metta.tokenizer().borrow_mut().register_token_with_regex_str("&shared", move |_| shared.clone());
+                let program = "
+                    !(add-atom &shared (fact B))
+                    !(match &shared (fact $x) $x)
+                ";
+                let result = metta.run(SExprParser::new(program)).unwrap();
+                // Atoms are converted to strings to be passed back to the main thread
+                result.iter().map(|atoms| atoms.iter().map(Atom::to_string).collect::<Vec<_>>()).collect::<Vec<_>>()
+            })
+        };
+        let mut result = thread.join().unwrap();
+        result[1].sort();
+
+        assert_eq!(result, vec![vec!["()".to_string()], vec!["A".to_string(), "B".to_string()]]);
+        assert_eq!(space.query(&expr!("fact" "B")).len(), 1);
+    }
+}
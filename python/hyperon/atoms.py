@@ -27,6 +27,10 @@ class Atom:
         """Renders a human-readable text description of the Atom."""
         return hp.atom_to_str(self.catom)
 
+    def pretty(self):
+        """Renders a text description of the Atom splitting it into lines."""
+        return hp.atom_to_pretty_str(self.catom)
+
     def get_metatype(self):
         """Gets the metatype (kind) of the current Atom instance"""
         return hp.atom_get_metatype(self.catom)
@@ -273,6 +277,10 @@ def atoms_are_equivalent(first, second):
     """Check if two atoms are equivalent"""
     return hp.atoms_are_equivalent(first.catom, second.catom)
 
+def pretty_list(atoms):
+    """Renders a text description of the list of atoms splitting it into lines"""
+    return hp.atom_list_to_pretty_str(atoms)
+
 class GroundedObject:
     """A GroundedObject holds some content and, optionally, an identifier."""
 
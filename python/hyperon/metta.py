@@ -33,7 +33,7 @@ def main():
         with open(args.file) as f: program = f.read()
         metta = hyperon.MeTTa(env_builder=hyperon.Environment.custom_env(working_dir=parent_dir, config_dir=""))
         for result in metta.run(program):
-            print(result)
+            print(hyperon.pretty_list(result))
     else:
         parser.print_usage()
 
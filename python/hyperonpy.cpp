@@ -797,6 +797,9 @@ PYBIND11_MODULE(hyperonpy, m) {
     m.def("atom_to_str", [](CAtom& atom) {
             return func_to_string((write_to_buf_func_t)&atom_to_str, atom.ptr());
         }, "Convert atom to human readable string");
+    m.def("atom_to_pretty_str", [](CAtom& atom) {
+            return func_to_string((write_to_buf_func_t)&atom_to_pretty_str, atom.ptr());
+        }, "Convert atom to human readable string splitting it into lines");
     m.def("atom_get_metatype", [](CAtom& atom) { return atom_get_metatype(atom.ptr()); }, "Get type of the atom");
     m.def("atom_get_name", [](CAtom& atom) {
             return func_to_string((write_to_buf_func_t)&atom_get_name, atom.ptr());
@@ -843,6 +846,16 @@ PYBIND11_MODULE(hyperonpy, m) {
     m.def("atom_vec_new", []() { return CVecAtom(atom_vec_new()); }, "New vector of atoms");
     m.def("atom_vec_free", [](CVecAtom& vec) { atom_vec_free(vec.obj); }, "Free vector of atoms");
     m.def("atom_vec_len", [](CVecAtom& vec) { return atom_vec_len(vec.ptr()); }, "Return size of the vector");
+    m.def("atom_list_to_pretty_str", [](pybind11::list pylist) {
+        atom_vec_t vec = atom_vec_new();
+        for(py::handle pyobj : pylist) {
+            CAtom atom = pyobj.attr("catom").cast<CAtom>();
+            atom_vec_push(&vec, atom_clone(atom.ptr()));
+        }
+        std::string str = func_to_string((write_to_buf_func_t)&atom_vec_to_pretty_str, &vec);
+        atom_vec_free(vec);
+        return str;
+    }, "Convert list of atoms to human readable string splitting it into lines");
     m.def("atom_vec_push", [](CVecAtom& vec, CAtom atom) { atom_vec_push(vec.ptr(), atom_clone(atom.ptr())); }, "Push atom into vector");
     m.def("atom_vec_pop", [](CVecAtom& vec) { return CAtom(atom_vec_pop(vec.ptr())); }, "Push atom into vector");
 
@@ -73,6 +73,10 @@ class AtomTest(unittest.TestCase):
     def test_expr_str(self):
         self.assertEqual(str(E(x2Atom, ValueAtom(1.0))), "(*2 1.0)")
 
+    def test_expr_pretty(self):
+        self.assertEqual(E(x2Atom, ValueAtom(1.0)).pretty(), "(*2 1.0)")
+        self.assertEqual(pretty_list([E(S("A"), S("B")), E(S("C"), S("D"))]), "[(A B), (C D)]")
+
     def test_expr_type(self):
         self.assertEqual(E(x2Atom, ValueAtom(1.0)).get_metatype(), AtomKind.EXPR)
 
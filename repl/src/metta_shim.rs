@@ -40,7 +40,6 @@ pub mod metta_interface_mod {
     use pep440_rs::{parse_version_specifiers, Version};
     use pyo3::prelude::*;
     use pyo3::types::{PyTuple, PyString, PyBool, PyList, PyDict};
-    use hyperon_common::collections::VecDisplay;
     use super::{exec_state_prepare, exec_state_should_break};
     use hyperon_atom::gnd::str::unescape;
     use hyperon::metta::text::CharReader;
@@ -172,9 +171,10 @@ pub mod metta_interface_mod {
 
         pub fn print_result(&self) {
             Python::with_gil(|py| -> PyResult<()> {
+                let pretty_list = PyModule::import(py, "hyperon")?.getattr("pretty_list")?;
                 for result_vec in self.result.iter() {
-                    let result_vec: Vec<&PyAny> = result_vec.iter().map(|atom| atom.as_ref(py)).collect();
-                    println!("{}", VecDisplay(&result_vec));
+                    let result_list = PyList::new(py, result_vec.iter().map(|atom| atom.as_ref(py)));
+                    println!("{}", pretty_list.call1((result_list,))?.str()?.to_str()?);
                 }
                 Ok(())
             }).unwrap()
@@ -275,7 +275,7 @@ pub mod metta_interface_mod {
     use hyperon_atom::Atom;
     use hyperon::metta::runner::{Metta, RunnerState, Environment, EnvBuilder};
     use hyperon_atom::gnd::str::Str;
-    use hyperon_common::collections::VecDisplay;
+    use hyperon_atom::pretty::PrettyPrinter;
     use super::{exec_state_prepare, exec_state_should_break};
 
     pub struct MettaShim {
@@ -351,8 +351,9 @@ pub mod metta_interface_mod {
         }
 
         pub fn print_result(&self) {
+            let printer = PrettyPrinter::new();
             for result in self.result.iter() {
-                println!("{}", VecDisplay(result));
+                println!("{}", printer.print_list(result));
             }
         }
 